@@ -1,20 +1,37 @@
 mod ui;
 
 use ferrite::core::{
-    application::{Application, Layer}, scene::Scene, window::Window
+    application::{Application, Layer},
+    scene::Scene,
+    scene_manager::SceneManager,
+    time::Time,
+    window::{Window, WindowConfig},
 };
 use ferrite_ui::ui::UI;
 use glfw::{Glfw, WindowEvent};
 use ui::ecs::EntityComponentsPanel;
 
 fn main() {
-    let mut application = Application::new(1280, 720, "Ferrite Editor");
-    application.add_layer(Box::new(EditorLayer::new()));
-    application.start();
+    let config = WindowConfig::new("Ferrite Editor", 1280, 720);
+    match Application::new(&config) {
+        Ok(mut application) => {
+            let scenes = application.get_scenes_mut();
+            scenes.insert(EDITED_SCENE, Scene::new());
+            if let Err(e) = scenes.switch_to(EDITED_SCENE) {
+                eprintln!("Failed to open scene: {e}");
+                return;
+            }
+            application.push_layer(Box::new(EditorLayer::new()));
+            application.start();
+        }
+        Err(e) => eprintln!("Failed to open window: {e}"),
+    }
 }
 
+/// Name of the scene being edited in the application's `SceneManager`.
+const EDITED_SCENE: &str = "edited";
+
 struct EditorLayer {
-    scene: Scene,
     ui: UI,
 }
 
@@ -22,27 +39,33 @@ impl EditorLayer {
     fn new() -> Self {
         let mut ui = UI::new();
         ui.add(Box::new(EntityComponentsPanel::new()));
-        Self {
-            scene: Scene::new(),
-            ui,
-        }
+        Self { ui }
     }
 }
 
 impl Layer for EditorLayer {
-    fn on_update(&mut self, window: &Window, delta_time: f64) {
-        self.scene.update(delta_time);
-        self.scene.render(window);
+    fn on_update(&mut self, scenes: &mut SceneManager, _: &Window, _: &mut Time) {
+        if let Some(scene) = scenes.get_scene_mut(EDITED_SCENE) {
+            self.ui.update(scene);
+        }
+    }
 
-        self.ui.update(&mut self.scene);
+    fn on_render(&mut self, _: &mut SceneManager, _: &Window) {
         self.ui.render();
     }
 
-    fn on_event(&mut self, glfw: &mut Glfw, window: &mut glfw::Window, event: &WindowEvent) {
-        if self.ui.handle_events(&mut self.scene, window, glfw, &event) {
-            return;
+    /// Events the UI does not handle go on to the scene.
+    fn on_event(
+        &mut self,
+        scenes: &mut SceneManager,
+        glfw: &mut Glfw,
+        window: &mut glfw::Window,
+        event: &WindowEvent,
+    ) -> bool {
+        match scenes.get_scene_mut(EDITED_SCENE) {
+            Some(scene) => self.ui.handle_events(scene, window, glfw, &event),
+            None => false,
         }
-        self.scene.handle_event(glfw, window, event);
     }
 
     fn get_name(&self) -> &str {
@@ -70,6 +70,14 @@ impl UIElement for AddComponentButton {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        f(self.button.as_mut());
+    }
+
     fn get_offset(&self) -> &Offset {
         self.button.get_offset()
     }
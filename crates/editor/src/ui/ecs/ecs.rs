@@ -40,7 +40,7 @@ impl UIElement for EntityComponentsPanel {
     fn update(&mut self, scene: &mut Scene) {
         let entities = scene.get_entities();
         for entity in entities {
-            let entity_handle = UIElementHandle::from(entity.id.into());
+            let entity_handle = UIElementHandle::from_entity(entity.id);
             if !self.panel.contains_child(&entity_handle) {
                 let entity_ui = EntityUI::new(scene, entity.id, 280.0);
                 self.panel.add_child_to(self.entity_panel_handle, Box::new(entity_ui));
@@ -87,6 +87,14 @@ impl UIElement for EntityComponentsPanel {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        f(self.panel.as_mut());
+    }
+
     fn get_offset(&self) -> &Offset {
         self.panel.get_offset()
     }
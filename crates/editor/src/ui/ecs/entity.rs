@@ -1,7 +1,7 @@
 use core::panic;
 
 use ferrite::core::{
-    entity::{Entity, EntityHandle},
+    entity::EntityHandle,
     primitives::{Offset, Position, Size},
     scene::Scene,
     utils::DataSource,
@@ -43,14 +43,14 @@ impl EntityUI {
                     .iter()
                     .map(|child| {
                         let mut entity_ui = EntityUI::new(scene, child.id, width);
-                        entity_ui.set_handle(UIElementHandle::from(child.id.into()));
+                        entity_ui.set_handle(UIElementHandle::from_entity(child.id));
                         Box::new(entity_ui)
                     })
                     .for_each(|entity_ui| {
                         panel.add_child(entity_ui);
                     });
                 return Self {
-                    handle: UIElementHandle::from(entity_handle.into()),
+                    handle: UIElementHandle::from_entity(entity_handle),
                     panel: *panel,
                     entity_handle,
                 };
@@ -75,7 +75,7 @@ impl UIElement for EntityUI {
                 .filter(|child| {
                     !self
                         .panel
-                        .contains_child(&UIElementHandle::from(child.id.into()))
+                        .contains_child(&UIElementHandle::from_entity(child.id))
                 })
                 .map(|child| {
                     Box::new(EntityUI::new(scene, child.id, self.get_size().width - 5.0))
@@ -126,6 +126,14 @@ impl UIElement for EntityUI {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        f(&mut self.panel);
+    }
+
     fn get_offset(&self) -> &Offset {
         self.panel.get_offset()
     }
@@ -148,14 +156,14 @@ impl AddEntityButton {
         let mut button = UI::button(
             "+",
             Box::new(move |scene| {
+                let child = scene.create_entity("Entity");
                 match entity_handle {
                     Some(entity_handle) => {
-                        if let Some(entity) = scene.get_entity_mut(&entity_handle) {
-                            entity.add_child(Entity::new("Entity"))
-                        }
+                        scene.get_commands_mut().spawn_child(entity_handle, child);
+                        scene.apply_commands();
                     }
                     None => {
-                        scene.add_entity(Entity::new("Entity"));
+                        scene.add_entity(child);
                     }
                 }
             }),
@@ -233,6 +241,14 @@ impl UIElement for AddEntityButton {
     fn get_handle(&self) -> &UIElementHandle {
         &self.handle
     }
+
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        f(self.button.as_mut());
+    }
 }
 
 impl EditEntityButton {
@@ -342,4 +358,13 @@ impl UIElement for EditEntityButton {
     fn get_handle(&self) -> &UIElementHandle {
         &self.handle
     }
+
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        f(self.button.as_mut());
+        f(&mut self.popup);
+    }
 }
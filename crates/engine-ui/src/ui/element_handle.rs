@@ -1,18 +1,56 @@
-use rand::prelude::*;
+use ferrite::core::{allocator::HandleAllocator, entity::EntityHandle};
 use std::cmp::Ordering;
 
+/// Set on handles derived from entity handles, which the allocator never
+/// hands out.
+const ENTITY_TAG: u64 = 1 << 63;
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd)]
 pub struct UIElementHandle(u64);
 
 impl UIElementHandle {
+    /// Handle of an element that has not been added to a `UI` yet.
+    pub const UNASSIGNED: Self = Self(u64::MAX);
+
+    /// Elements are built before they belong to a `UI`, which gives them a
+    /// handle from its own allocator when they are added to it.
     pub fn new() -> Self {
-        Self {
-            0: rand::rng().random::<u64>(),
-        }
+        Self::UNASSIGNED
     }
+    /// Fixed handle. Values below `1 << 32` never collide with allocated
+    /// handles.
     pub fn from(id: u64) -> Self {
         Self { 0: id }
     }
+    /// Handle for an element that mirrors an entity, e.g. in the editor's
+    /// entity tree.
+    pub fn from_entity(entity: EntityHandle) -> Self {
+        Self {
+            0: u64::from(entity) | ENTITY_TAG,
+        }
+    }
+    pub fn is_assigned(&self) -> bool {
+        *self != Self::UNASSIGNED
+    }
+    /// Takes a handle from `handles` unless one was already assigned or
+    /// set.
+    pub fn assign(&mut self, handles: &mut HandleAllocator) {
+        if !self.is_assigned() {
+            self.0 = handles.allocate();
+        }
+    }
+    /// Whether this handle is still allocated in `handles`. Fixed and
+    /// entity-derived handles are never alive.
+    pub fn is_alive(&self, handles: &HandleAllocator) -> bool {
+        handles.is_alive(self.0)
+    }
+    /// Returns the handle to `handles` if it came from there. Copies kept
+    /// elsewhere become stale.
+    pub fn release(&mut self, handles: &mut HandleAllocator) {
+        if handles.free(self.0) {
+            *self = Self::UNASSIGNED;
+        }
+    }
 }
 
 impl Ord for UIElementHandle {
@@ -20,3 +58,48 @@ impl Ord for UIElementHandle {
         self.0.cmp(&other.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ferrite::core::{allocator::HandleAllocator, entity::EntityHandle};
+
+    use super::UIElementHandle;
+
+    #[test]
+    fn assign_keeps_fixed_handles() {
+        let mut handles = HandleAllocator::new();
+        let mut fixed = UIElementHandle::from(3);
+        fixed.assign(&mut handles);
+        assert_eq!(fixed, UIElementHandle::from(3));
+
+        let mut handle = UIElementHandle::new();
+        handle.assign(&mut handles);
+        assert!(handle.is_assigned());
+        assert!(handle.is_alive(&handles));
+    }
+
+    #[test]
+    fn released_handles_go_stale() {
+        let mut handles = HandleAllocator::new();
+        let mut handle = UIElementHandle::new();
+        handle.assign(&mut handles);
+        let copy = handle;
+        handle.release(&mut handles);
+        assert!(!handle.is_assigned());
+        assert!(!copy.is_alive(&handles));
+
+        let mut reused = UIElementHandle::new();
+        reused.assign(&mut handles);
+        assert_ne!(reused, copy);
+    }
+
+    #[test]
+    fn entity_handles_are_never_released() {
+        let mut handles = HandleAllocator::new();
+        let id = handles.allocate();
+        let mut entity = UIElementHandle::from_entity(EntityHandle::from(id));
+        entity.release(&mut handles);
+        assert!(entity.is_assigned());
+        assert!(handles.is_alive(id));
+    }
+}
@@ -1,5 +1,3 @@
-use std::collections::BTreeMap;
-
 use ferrite::core::{
     primitives::{Offset, Position, Region, Size},
     renderer::plane::PlaneBuilder,
@@ -25,7 +23,7 @@ impl Button {
             padding: Offset { x: 5.0, y: 1.0 },
 
             on_click,
-            children: BTreeMap::new(),
+            children: Vec::new(),
 
             is_hovering: false,
 
@@ -47,14 +45,14 @@ impl Button {
 
 impl UIElement for Button {
     fn update(&mut self, scene: &mut Scene) {
-        for child in self.children.values_mut() {
+        for child in self.children.iter_mut() {
             child.update(scene);
         }
     }
 
     fn render(&self) {
         self.plane.render();
-        for child in self.children.values() {
+        for child in self.children.iter() {
             child.render();
         }
     }
@@ -95,14 +93,18 @@ impl UIElement for Button {
     }
 
     fn add_child(&mut self, child: Box<dyn UIElement>) {
-        self.children.insert(child.get_handle().clone(), child);
+        self.children.push(child);
     }
 
     fn add_child_to(&mut self, parent: UIElementHandle, child: Box<dyn UIElement>) {
-        if let Some(parent) = self.children.get_mut(&parent) {
+        if let Some(parent) = self
+            .children
+            .iter_mut()
+            .find(|element| *element.get_handle() == parent)
+        {
             parent.add_child(child);
         } else {
-            for child_elem in self.children.values_mut() {
+            for child_elem in self.children.iter_mut() {
                 if child_elem.contains_child(&parent) {
                     child_elem.add_child_to(parent, child);
                     return;
@@ -112,22 +114,20 @@ impl UIElement for Button {
     }
 
     fn contains_child(&self, handle: &UIElementHandle) -> bool {
-        if self.children.contains_key(handle) {
-            return true;
-        }
-        for child in self.children.values() {
-            if child.contains_child(handle) {
-                return true;
-            }
-        }
-        false
+        self.children
+            .iter()
+            .any(|child| child.get_handle() == handle || child.contains_child(handle))
     }
 
     fn get_child(&self, handle: &UIElementHandle) -> Option<&Box<dyn UIElement>> {
-        if let Some(child) = self.children.get(handle) {
+        if let Some(child) = self
+            .children
+            .iter()
+            .find(|child| child.get_handle() == handle)
+        {
             return Some(child);
         }
-        for child in self.children.values() {
+        for child in self.children.iter() {
             if let Some(child) = child.get_child(handle) {
                 return Some(child);
             }
@@ -136,8 +136,8 @@ impl UIElement for Button {
     }
 
     fn get_child_mut(&mut self, handle: &UIElementHandle) -> Option<&mut Box<dyn UIElement>> {
-        for (child_handle, child) in self.children.iter_mut() {
-            if child_handle == handle {
+        for child in self.children.iter_mut() {
+            if child.get_handle() == handle {
                 return Some(child);
             }
             if let Some(child) = child.get_child_mut(handle) {
@@ -151,6 +151,16 @@ impl UIElement for Button {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        for child in self.children.iter_mut() {
+            f(child.as_mut());
+        }
+    }
+
     fn get_offset(&self) -> &Offset {
         &self.region.offset
     }
@@ -158,7 +168,7 @@ impl UIElement for Button {
     fn set_offset(&mut self, offset: Offset) {
         self.region.offset = offset;
         self.plane.set_position(&self.region.position + &self.region.offset);
-        for child in self.children.values_mut() {
+        for child in self.children.iter_mut() {
             child.set_offset(&(&self.region.offset + &self.padding) + &self.region.position);
         }
     }
@@ -170,7 +180,7 @@ impl UIElement for Button {
     fn set_z_index(&mut self, z_index: f32) {
         self.region.position.z = z_index;
         self.plane.set_z_index(z_index);
-        for child in self.children.values_mut() {
+        for child in self.children.iter_mut() {
             child.set_z_index(z_index + 1.0);
         }
     }
@@ -1,5 +1,3 @@
-use std::collections::BTreeMap;
-
 use ferrite::core::{primitives::{Offset, Region}, renderer::plane::Plane, scene::Scene};
 
 use crate::ui::{element_handle::UIElementHandle, UIElement};
@@ -13,7 +11,9 @@ pub struct Button {
     padding: Offset,
 
     on_click: Box<dyn Fn(&mut Scene)>,
-    children: BTreeMap<UIElementHandle, Box<dyn UIElement>>,
+    /// In the order they were added. Handles are only unique once the
+    /// elements belong to a `UI`.
+    children: Vec<Box<dyn UIElement>>,
 
     is_hovering: bool,
 
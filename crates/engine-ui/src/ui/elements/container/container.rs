@@ -1,5 +1,3 @@
-use std::collections::BTreeMap;
-
 use ferrite::core::{
     primitives::{Offset, Position, Region, Size},
     renderer::plane::PlaneBuilder,
@@ -16,7 +14,7 @@ impl Container {
         Self {
             handle: UIElementHandle::new(),
             region: Region::new(position, size),
-            children: BTreeMap::new(),
+            children: Vec::new(),
 
             gap: 5.0,
             direction: Direction::Vertical,
@@ -43,7 +41,7 @@ impl Container {
         self.region.position = position;
         self.plane
             .set_position(&self.region.position + &self.region.offset);
-        for child in self.children.values_mut() {
+        for child in self.children.iter_mut() {
             child.set_offset(&self.region.offset + &self.region.position);
         }
     }
@@ -54,7 +52,7 @@ impl UIElement for Container {
         match self.direction {
             Direction::Horizontal => {
                 let mut x_offset = self.gap;
-                for child in self.children.values_mut() {
+                for child in self.children.iter_mut() {
                     let offset = &self.region.offset + &self.region.position + (x_offset, self.gap);
                     if offset != *child.get_offset() {
                         child.set_offset(offset);
@@ -69,7 +67,7 @@ impl UIElement for Container {
             }
             Direction::Vertical => {
                 let mut y_offset = self.gap;
-                for child in self.children.values_mut() {
+                for child in self.children.iter_mut() {
                     let offset = &self.region.offset + &self.region.position + (self.gap, y_offset);
                     if offset != *child.get_offset() {
                         child.set_offset(offset);
@@ -83,14 +81,14 @@ impl UIElement for Container {
                 }
             }
         }
-        for child in self.children.values_mut() {
+        for child in self.children.iter_mut() {
             child.update(scene);
         }
     }
 
     fn render(&self) {
         self.plane.render();
-        for child in self.children.values() {
+        for child in self.children.iter() {
             child.render();
         }
     }
@@ -102,7 +100,7 @@ impl UIElement for Container {
         glfw: &mut Glfw,
         event: &WindowEvent,
     ) -> bool {
-        for child in self.children.values_mut() {
+        for child in self.children.iter_mut() {
             if child.handle_events(scene, window, glfw, event) {
                 return true;
             }
@@ -131,14 +129,18 @@ impl UIElement for Container {
             }
         }
         self.plane.set_size(self.region.size);
-        self.children.insert(child.get_handle().clone(), child);
+        self.children.push(child);
     }
 
     fn add_child_to(&mut self, parent: UIElementHandle, child: Box<dyn UIElement>) {
-        if let Some(parent) = self.children.get_mut(&parent) {
+        if let Some(parent) = self
+            .children
+            .iter_mut()
+            .find(|element| *element.get_handle() == parent)
+        {
             parent.add_child(child);
         } else {
-            for child_elem in self.children.values_mut() {
+            for child_elem in self.children.iter_mut() {
                 if child_elem.contains_child(&parent) {
                     child_elem.add_child_to(parent, child);
                     return;
@@ -148,22 +150,20 @@ impl UIElement for Container {
     }
 
     fn contains_child(&self, handle: &UIElementHandle) -> bool {
-        if self.children.contains_key(handle) {
-            return true;
-        }
-        for child in self.children.values() {
-            if child.contains_child(handle) {
-                return true;
-            }
-        }
-        false
+        self.children
+            .iter()
+            .any(|child| child.get_handle() == handle || child.contains_child(handle))
     }
 
     fn get_child(&self, handle: &UIElementHandle) -> Option<&Box<dyn UIElement>> {
-        if let Some(child) = self.children.get(handle) {
+        if let Some(child) = self
+            .children
+            .iter()
+            .find(|child| child.get_handle() == handle)
+        {
             return Some(child);
         }
-        for child in self.children.values() {
+        for child in self.children.iter() {
             if let Some(child) = child.get_child(handle) {
                 return Some(child);
             }
@@ -172,8 +172,8 @@ impl UIElement for Container {
     }
 
     fn get_child_mut(&mut self, handle: &UIElementHandle) -> Option<&mut Box<dyn UIElement>> {
-        for (child_handle, child) in self.children.iter_mut() {
-            if child_handle == handle {
+        for child in self.children.iter_mut() {
+            if child.get_handle() == handle {
                 return Some(child);
             }
             if let Some(child) = child.get_child_mut(handle) {
@@ -187,6 +187,16 @@ impl UIElement for Container {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        for child in self.children.iter_mut() {
+            f(child.as_mut());
+        }
+    }
+
     fn get_offset(&self) -> &Offset {
         &self.region.offset
     }
@@ -197,14 +207,14 @@ impl UIElement for Container {
         match self.direction {
             Direction::Horizontal => {
                 let mut current_x_offset = self.gap;
-                for child in &mut self.children.values_mut() {
+                for child in &mut self.children.iter_mut() {
                     child.set_offset(&self.region.offset + &self.region.position + (current_x_offset, self.gap));
                     current_x_offset += child.get_size().width + self.gap;
                 }
             }
             Direction::Vertical => {
                 let mut current_y_offset = self.gap;
-                for child in &mut self.children.values_mut() {
+                for child in &mut self.children.iter_mut() {
                     child.set_offset(&self.region.offset + &self.region.position + (self.gap, current_y_offset));
                     current_y_offset += child.get_size().height + self.gap;
                 }
@@ -219,7 +229,7 @@ impl UIElement for Container {
     fn set_z_index(&mut self, z_index: f32) {
         self.region.position.z = z_index;
         self.plane.set_z_index(z_index);
-        for child in self.children.values_mut() {
+        for child in self.children.iter_mut() {
             child.set_z_index(z_index + 1.0);
         }
     }
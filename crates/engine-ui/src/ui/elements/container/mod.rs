@@ -1,5 +1,3 @@
-use std::collections::BTreeMap;
-
 use ferrite::core::{primitives::Region, renderer::plane::Plane};
 
 use crate::ui::{element_handle::UIElementHandle, UIElement};
@@ -15,7 +13,9 @@ pub struct Container {
     handle: UIElementHandle,
 
     region: Region,
-    children: BTreeMap<UIElementHandle, Box<dyn UIElement>>,
+    /// In the order they were added. Handles are only unique once the
+    /// elements belong to a `UI`.
+    children: Vec<Box<dyn UIElement>>,
 
     gap: f32,
     with_end_gap: bool,
@@ -182,6 +182,10 @@ impl<T: Clone + ToString + FromStr> UIElement for Input<T> {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
     fn set_offset(&mut self, offset: Offset) {
         self.region.offset = offset;
         self.plane
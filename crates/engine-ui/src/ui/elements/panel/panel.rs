@@ -245,6 +245,16 @@ impl UIElement for Panel {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        f(&mut self.title);
+        f(&mut self.content);
+        f(&mut self.controls);
+    }
+
     fn get_offset(&self) -> &Offset {
         &self.region.offset
     }
@@ -99,6 +99,14 @@ impl UIElement for Popup {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
+    fn for_each_child_mut(&mut self, f: &mut dyn FnMut(&mut dyn UIElement)) {
+        f(&mut self.panel);
+    }
+
     fn get_offset(&self) -> &Offset {
         self.panel.get_offset()
     }
@@ -96,6 +96,10 @@ impl UIElement for Text {
         &self.handle
     }
 
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle {
+        &mut self.handle
+    }
+
     fn get_offset(&self) -> &Offset {
         &self.region.offset
     }
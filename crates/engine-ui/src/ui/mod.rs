@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 
 use element_handle::UIElementHandle;
 use ferrite::core::{
+    allocator::HandleAllocator,
     primitives::{Offset, Size},
     scene::Scene,
 };
@@ -29,6 +30,22 @@ pub trait UIElement {
     fn get_child_mut(&mut self, handle: &UIElementHandle) -> Option<&mut Box<dyn UIElement>>;
 
     fn get_handle(&self) -> &UIElementHandle;
+    fn get_handle_mut(&mut self) -> &mut UIElementHandle;
+    /// Calls `f` with each element this one owns directly, for
+    /// `assign_handles` and `release_handles`. Elements without children
+    /// keep the default.
+    fn for_each_child_mut(&mut self, _f: &mut dyn FnMut(&mut dyn UIElement)) {}
+    /// Gives this element and its descendants a handle from `handles` where
+    /// they have none yet.
+    fn assign_handles(&mut self, handles: &mut HandleAllocator) {
+        self.get_handle_mut().assign(handles);
+        self.for_each_child_mut(&mut |child| child.assign_handles(handles));
+    }
+    /// Returns the handles of this element and its descendants to `handles`.
+    fn release_handles(&mut self, handles: &mut HandleAllocator) {
+        self.get_handle_mut().release(handles);
+        self.for_each_child_mut(&mut |child| child.release_handles(handles));
+    }
 
     fn get_offset(&self) -> &Offset;
     fn set_offset(&mut self, offset: Offset);
@@ -38,4 +55,6 @@ pub trait UIElement {
 
 pub struct UI {
     children: BTreeMap<UIElementHandle, Box<dyn UIElement>>,
+    /// Hands out the handles of the elements added to this UI.
+    handles: HandleAllocator,
 }
@@ -1,6 +1,7 @@
 use std::{collections::BTreeMap, str::FromStr};
 
 use ferrite::core::{
+    allocator::HandleAllocator,
     primitives::{Position, Size},
     scene::Scene,
     utils::DataSource,
@@ -17,20 +18,34 @@ impl UI {
     pub fn new() -> Self {
         Self {
             children: BTreeMap::new(),
+            handles: HandleAllocator::new(),
         }
     }
 
-    pub fn add(&mut self, element: Box<dyn UIElement>) -> UIElementHandle {
-        let handle = UIElementHandle::new();
+    /// Adds a top-level element under its own handle, which is assigned
+    /// here along with those of its children.
+    pub fn add(&mut self, mut element: Box<dyn UIElement>) -> UIElementHandle {
+        element.assign_handles(&mut self.handles);
+        let handle = *element.get_handle();
         self.children.insert(handle, element);
         handle
     }
 
-    pub fn insert(&mut self, key: UIElementHandle, element: Box<dyn UIElement>) {
+    /// Removes a top-level element and releases its handle and those of
+    /// its children.
+    pub fn remove(&mut self, key: &UIElementHandle) -> Option<Box<dyn UIElement>> {
+        let mut element = self.children.remove(key)?;
+        element.release_handles(&mut self.handles);
+        Some(element)
+    }
+
+    pub fn insert(&mut self, key: UIElementHandle, mut element: Box<dyn UIElement>) {
+        element.assign_handles(&mut self.handles);
         self.children.insert(key, element);
     }
 
-    pub fn insert_to(&mut self, parent: UIElementHandle, element: Box<dyn UIElement>) {
+    pub fn insert_to(&mut self, parent: UIElementHandle, mut element: Box<dyn UIElement>) {
+        element.assign_handles(&mut self.handles);
         if let Some(parent) = self.children.get_mut(&parent) {
             parent.add_child(element);
         } else {
@@ -43,12 +58,20 @@ impl UI {
         }
     }
 
+    /// Elements that were added below others during the update get their
+    /// handles afterwards.
     pub fn update(&mut self, scene: &mut Scene) {
         for (_, child) in &mut self.children {
             child.update(scene);
+            child.assign_handles(&mut self.handles);
         }
     }
 
+    /// Whether `handle` belongs to an element of this UI.
+    pub fn is_alive(&self, handle: &UIElementHandle) -> bool {
+        handle.is_alive(&self.handles)
+    }
+
     pub fn render(&mut self) {
         for (_, child) in &mut self.children {
             child.render();
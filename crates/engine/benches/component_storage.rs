@@ -0,0 +1,308 @@
+//! Compares the scene's component store against the layout it replaced,
+//! where every entity kept its components in a `Vec<Box<dyn Component>>`
+//! and lookups walked the tree, downcasting every component. Before each
+//! timed pass over the chunks, both despawn their oldest chunks and spawn
+//! new ones, untimed.
+//!
+//! Run with `cargo bench -p ferrite --bench component_storage`.
+
+use std::{
+    collections::VecDeque,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use ferrite::core::{
+    entity::{component::Component, Entity, EntityHandle},
+    scene::Scene,
+};
+
+const CHUNKS: usize = 4096;
+const PROPS: usize = 2048;
+/// Chunks despawned and spawned per frame.
+const CHURN: usize = 64;
+const ITERATIONS: u32 = 200;
+
+struct BenchChunk {
+    triangles: usize,
+}
+
+struct Prop;
+
+struct Sun;
+
+macro_rules! bench_component {
+    ($name:ty) => {
+        impl Component for $name {
+            fn update(&mut self, _: &mut Scene, _: &mut Entity, _: f64) {}
+
+            fn handle_event(
+                &mut self,
+                _: &mut glfw::Glfw,
+                _: &mut glfw::Window,
+                _: &glfw::WindowEvent,
+            ) {
+            }
+        }
+    };
+}
+
+bench_component!(BenchChunk);
+bench_component!(Prop);
+bench_component!(Sun);
+
+/// An entity in the old layout.
+struct Node {
+    id: EntityHandle,
+    components: Vec<Box<dyn Component>>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(id: EntityHandle, component: Box<dyn Component>) -> Self {
+        Self {
+            id,
+            components: vec![component],
+            children: Vec::new(),
+        }
+    }
+
+    fn get_component<T: Component>(&self) -> Option<&T> {
+        for component in self.components.iter() {
+            if let Some(component) = component.as_any().downcast_ref::<T>() {
+                return Some(component);
+            }
+        }
+        self.children.iter().find_map(Node::get_component::<T>)
+    }
+
+    fn get_node(&self, id: &EntityHandle) -> Option<&Node> {
+        if self.id == *id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.get_node(id))
+    }
+
+    fn count<T: Component>(&self) -> usize {
+        let own = self
+            .components
+            .iter()
+            .filter(|component| component.as_any().is::<T>())
+            .count();
+        own + self.children.iter().map(Node::count::<T>).sum::<usize>()
+    }
+
+    fn collect_mut<'a, T: Component>(&'a mut self, out: &mut Vec<&'a mut T>) {
+        for component in self.components.iter_mut() {
+            if let Some(component) = component.as_any_mut().downcast_mut::<T>() {
+                out.push(component);
+            }
+        }
+        for child in self.children.iter_mut() {
+            child.collect_mut(out);
+        }
+    }
+}
+
+/// The same entities in the scene and in the old layout.
+struct World {
+    scene: Scene,
+    nodes: Vec<Node>,
+    terrain: EntityHandle,
+    /// Chunk handles, oldest first.
+    chunks: VecDeque<EntityHandle>,
+    /// Handles of the props' nested parts.
+    parts: Vec<EntityHandle>,
+    spawned: usize,
+}
+
+impl World {
+    fn new() -> Self {
+        let mut scene = Scene::new();
+        let terrain = scene.create_entity("terrain");
+        let mut terrain_node = Node {
+            id: terrain.id,
+            components: Vec::new(),
+            children: Vec::new(),
+        };
+        let terrain = scene.add_entity(terrain);
+        let mut world = Self {
+            scene,
+            nodes: Vec::new(),
+            terrain,
+            chunks: VecDeque::new(),
+            parts: Vec::new(),
+            spawned: 0,
+        };
+        for _ in 0..CHUNKS {
+            let (id, triangles) = world.spawn_chunk();
+            terrain_node
+                .children
+                .push(Node::new(id, Box::new(BenchChunk { triangles })));
+        }
+        world.scene.apply_commands();
+        world.nodes.push(terrain_node);
+
+        for i in 0..PROPS {
+            let mut prop = world.scene.create_entity(&format!("prop-{}", i));
+            prop.add_component(Prop);
+            let mut part = world.scene.create_entity("part");
+            part.add_component(Prop);
+            let mut node = Node::new(prop.id, Box::new(Prop));
+            node.children.push(Node::new(part.id, Box::new(Prop)));
+            world.nodes.push(node);
+            world.parts.push(part.id);
+            prop.add_child(&mut world.scene, part);
+            world.scene.add_entity(prop);
+        }
+
+        let mut sun = world.scene.create_entity("sun");
+        sun.add_component(Sun);
+        world.nodes.push(Node::new(sun.id, Box::new(Sun)));
+        world.scene.add_entity(sun);
+        world.scene.refresh_index();
+        world
+    }
+
+    /// Queues a new chunk under the terrain, returning its handle and
+    /// triangle count.
+    fn spawn_chunk(&mut self) -> (EntityHandle, usize) {
+        let triangles = self.spawned % 64;
+        self.spawned += 1;
+        let mut chunk = self.scene.create_entity("chunk");
+        chunk.add_component(BenchChunk { triangles });
+        self.chunks.push_back(chunk.id);
+        let id = self
+            .scene
+            .get_commands_mut()
+            .spawn_child(self.terrain, chunk);
+        (id, triangles)
+    }
+
+    /// Replaces the `CHURN` oldest chunks in the scene.
+    fn churn_scene(&mut self) {
+        for _ in 0..CHURN {
+            let id = self.chunks.pop_front().unwrap();
+            self.scene.get_commands_mut().despawn(id);
+            self.spawn_chunk();
+        }
+        self.scene.apply_commands();
+        self.scene.refresh_index();
+    }
+
+    /// Replaces the `CHURN` oldest chunks in the old layout, the way
+    /// `Entity::take_child` did.
+    fn churn_nodes(&mut self) {
+        for _ in 0..CHURN {
+            let triangles = self.spawned % 64;
+            self.spawned += 1;
+            let terrain = &mut self.nodes[0];
+            let oldest = terrain.children.remove(0);
+            terrain
+                .children
+                .push(Node::new(oldest.id, Box::new(BenchChunk { triangles })));
+        }
+    }
+}
+
+fn measure<R>(name: &str, mut f: impl FnMut() -> R) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    println!("  {:<10} {:>12.2?}", name, elapsed);
+    elapsed
+}
+
+/// Like `measure`, running `mutate` on `state` untimed before each run.
+fn measure_mutating<S, R>(
+    name: &str,
+    state: &mut S,
+    mut mutate: impl FnMut(&mut S),
+    mut f: impl FnMut(&mut S) -> R,
+) -> Duration {
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        mutate(state);
+        let start = Instant::now();
+        black_box(f(state));
+        elapsed += start.elapsed();
+    }
+    let elapsed = elapsed / ITERATIONS;
+    println!("  {:<10} {:>12.2?}", name, elapsed);
+    elapsed
+}
+
+fn report(walk: Duration, store: Duration) {
+    println!(
+        "  speedup    {:>11.1}x\n",
+        walk.as_secs_f64() / store.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut world = World::new();
+    println!(
+        "{} terrain chunks ({} replaced per frame), {} props ({} nested parts)\n",
+        CHUNKS, CHURN, PROPS, PROPS
+    );
+
+    println!("update every chunk, {} replaced before each pass", CHURN);
+    let walk = measure_mutating("walk", &mut world, World::churn_nodes, |world| {
+        let mut chunks = Vec::new();
+        for node in world.nodes.iter_mut() {
+            node.collect_mut::<BenchChunk>(&mut chunks);
+        }
+        for chunk in chunks.iter_mut() {
+            chunk.triangles = (chunk.triangles + 1) % 64;
+        }
+        chunks.len()
+    });
+    let store = measure_mutating("store", &mut world, World::churn_scene, |world| {
+        let mut chunks = world.scene.query::<&mut BenchChunk>();
+        for chunk in chunks.iter_mut() {
+            chunk.triangles = (chunk.triangles + 1) % 64;
+        }
+        chunks.len()
+    });
+    report(walk, store);
+
+    let World {
+        scene,
+        nodes,
+        parts,
+        ..
+    } = &world;
+
+    println!("get_component (last entity)");
+    let walk = measure("walk", || {
+        nodes.iter().find_map(Node::get_component::<Sun>).is_some()
+    });
+    let store = measure("store", || scene.get_component::<Sun>().is_some());
+    report(walk, store);
+
+    println!("get_entity (1000 nested parts)");
+    let walk = measure("walk", || {
+        parts
+            .iter()
+            .step_by(parts.len() / 1000)
+            .filter(|id| nodes.iter().find_map(|node| node.get_node(id)).is_some())
+            .count()
+    });
+    let store = measure("store", || {
+        parts
+            .iter()
+            .step_by(parts.len() / 1000)
+            .filter(|id| scene.get_entity(id).is_some())
+            .count()
+    });
+    report(walk, store);
+
+    println!("iterate props");
+    let walk = measure("walk", || {
+        nodes.iter().map(Node::count::<Prop>).sum::<usize>()
+    });
+    let store = measure("store", || scene.query_ref::<&Prop>().len());
+    report(walk, store);
+}
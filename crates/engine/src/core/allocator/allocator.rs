@@ -0,0 +1,155 @@
+use super::HandleAllocator;
+
+/// Generations wrap back to 1 after this, keeping the top bit of every id
+/// clear for callers that tag ids from other sources.
+const MAX_GENERATION: u32 = u32::MAX >> 1;
+
+/// How far past the allocated slots `reserve` may reach. Keeps ids that
+/// were never allocated here (e.g. random ids from old scene files) from
+/// growing the slot table without bound.
+const MAX_RESERVE_GAP: usize = 1 << 16;
+
+impl HandleAllocator {
+    pub const fn new() -> Self {
+        Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn allocate(&mut self) -> u64 {
+        while let Some(index) = self.free.pop() {
+            let slot = index as usize;
+            if !self.alive[slot] {
+                self.alive[slot] = true;
+                return Self::join(index, self.generations[slot]);
+            }
+        }
+        let index = self.generations.len() as u32;
+        self.generations.push(1);
+        self.alive.push(true);
+        Self::join(index, 1)
+    }
+
+    /// Releases `id` so its slot can be reused. Returns `false` if `id` was
+    /// not alive.
+    pub fn free(&mut self, id: u64) -> bool {
+        if !self.is_alive(id) {
+            return false;
+        }
+        let index = Self::index_of(id);
+        let slot = index as usize;
+        self.alive[slot] = false;
+        self.generations[slot] = match self.generations[slot] {
+            MAX_GENERATION => 1,
+            generation => generation + 1,
+        };
+        self.free.push(index);
+        true
+    }
+
+    pub fn is_alive(&self, id: u64) -> bool {
+        let slot = Self::index_of(id) as usize;
+        slot < self.generations.len()
+            && self.alive[slot]
+            && self.generations[slot] == Self::generation_of(id)
+    }
+
+    /// Marks an id handed out earlier (e.g. one read back from a scene
+    /// file) as alive again. Returns `false` if it cannot be restored
+    /// because its slot is in use, its generation is 0, or its index lies
+    /// too far past the allocated slots.
+    pub fn reserve(&mut self, id: u64) -> bool {
+        let index = Self::index_of(id);
+        let generation = Self::generation_of(id);
+        let slot = index as usize;
+        if generation == 0 || generation > MAX_GENERATION {
+            return false;
+        }
+        if slot >= self.generations.len() + MAX_RESERVE_GAP {
+            return false;
+        }
+        while self.generations.len() <= slot {
+            self.free.push(self.generations.len() as u32);
+            self.generations.push(1);
+            self.alive.push(false);
+        }
+        if self.alive[slot] {
+            return false;
+        }
+        self.generations[slot] = generation;
+        self.alive[slot] = true;
+        true
+    }
+
+    /// Forgets every id. Ids handed out before are not recognised as stale
+    /// afterwards.
+    pub fn clear(&mut self) {
+        self.generations.clear();
+        self.alive.clear();
+        self.free.clear();
+    }
+
+    pub fn index_of(id: u64) -> u32 {
+        id as u32
+    }
+
+    pub fn generation_of(id: u64) -> u32 {
+        (id >> 32) as u32
+    }
+
+    fn join(index: u32, generation: u32) -> u64 {
+        ((generation as u64) << 32) | index as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HandleAllocator, MAX_RESERVE_GAP};
+
+    #[test]
+    fn freed_slots_come_back_with_a_new_generation() {
+        let mut allocator = HandleAllocator::new();
+        let first = allocator.allocate();
+        let second = allocator.allocate();
+        assert_ne!(first, second);
+        assert_eq!(HandleAllocator::generation_of(first), 1);
+
+        assert!(allocator.free(first));
+        assert!(!allocator.free(first));
+        assert!(!allocator.is_alive(first));
+
+        let reused = allocator.allocate();
+        assert_eq!(
+            HandleAllocator::index_of(reused),
+            HandleAllocator::index_of(first)
+        );
+        assert_eq!(HandleAllocator::generation_of(reused), 2);
+        assert!(allocator.is_alive(second));
+    }
+
+    #[test]
+    fn reserve_restores_saved_ids() {
+        let mut saved = HandleAllocator::new();
+        let ids: Vec<u64> = (0..3).map(|_| saved.allocate()).collect();
+
+        let mut allocator = HandleAllocator::new();
+        assert!(allocator.reserve(ids[2]));
+        assert!(!allocator.reserve(ids[2]));
+        assert!(allocator.is_alive(ids[2]));
+
+        // Slots skipped by `reserve` are handed out before new ones.
+        let fresh = allocator.allocate();
+        assert!(HandleAllocator::index_of(fresh) < 2);
+        assert_ne!(fresh, ids[2]);
+    }
+
+    #[test]
+    fn reserve_rejects_unusable_ids() {
+        let mut allocator = HandleAllocator::new();
+        assert!(!allocator.reserve(5));
+        assert!(!allocator.reserve((1 << 32) | MAX_RESERVE_GAP as u64));
+        assert!(!allocator.reserve(u64::MAX));
+    }
+}
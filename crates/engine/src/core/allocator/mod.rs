@@ -0,0 +1,16 @@
+mod allocator;
+
+/// Hands out `u64` ids made of a slot index (low 32 bits) and that slot's
+/// generation (high 32 bits). Freeing a slot bumps its generation, so ids
+/// kept around after the slot is reused are recognised as stale.
+///
+/// Generation 0 is never handed out, which leaves ids below `1 << 32` free
+/// for fixed keys and for "not assigned yet".
+#[derive(Default)]
+pub struct HandleAllocator {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    /// Freed slots, reused last-in first-out. May hold slots that were
+    /// since revived by `reserve`; `allocate` skips those.
+    free: Vec<u32>,
+}
@@ -1,17 +1,30 @@
+use std::{error::Error, fs};
+
 use crate::core::{
-    renderer::{plane::PlaneRenderer, text::TextRenderer},
-    window::Window,
+    assets::HotReload,
+    console::Console,
+    input::Gamepads,
+    recording::{Recorder, Replay},
+    renderer::{self, plane::PlaneRenderer, text::TextRenderer},
+    scene_manager::SceneManager,
+    time::Time,
+    window::{Window, WindowConfig},
 };
 
-use super::{Application, Layer};
+use super::{Application, FrameCapture, Layer, LayerStack};
 
 impl Application {
-    pub fn new(width: u32, height: u32, title: &str) -> Self {
-        env_logger::init();
-        let mut window = Window::new(width, height, title);
+    /// Fails if GLFW cannot be initialized or the window cannot be created.
+    pub fn new(config: &WindowConfig) -> Result<Self, Box<dyn Error>> {
+        // Does nothing if a logger is already set, e.g. by an earlier
+        // application.
+        Console::init_logger();
+        // An earlier headless application in this process may have set it.
+        renderer::set_headless(false);
+        let mut window = Window::new(config)?;
 
-        TextRenderer::resize(width, height);
-        PlaneRenderer::resize(width, height);
+        TextRenderer::resize(window.width, window.height);
+        PlaneRenderer::resize(window.width, window.height);
 
         window.clear(
             (0.3, 0.3, 0.5, 1.0),
@@ -19,38 +32,353 @@ impl Application {
         );
         window.swap_buffers();
 
-        Self {
+        Ok(Self {
+            scenes: SceneManager::new(),
             window,
-            layers: Vec::new(),
+            time: Time::new(),
+            layers: LayerStack::new(),
+            recorder: None,
+            replay: None,
+            fixed_delta_time: None,
+            frame_capture: None,
+        })
+    }
+
+    /// Runs the layers without a window or GL context, e.g. for tests or a
+    /// dedicated server. Every frame is `delta_time` seconds long; nothing
+    /// is drawn and no GPU resources are created (see
+    /// `renderer::is_headless`). Use `step` to advance a set number of
+    /// frames.
+    pub fn new_headless(width: u32, height: u32, delta_time: f64) -> Self {
+        Console::init_logger();
+        renderer::set_headless(true);
+        Self {
+            scenes: SceneManager::new(),
+            window: Window::headless(width, height),
+            time: Time::new(),
+            layers: LayerStack::new(),
+            recorder: None,
+            replay: None,
+            fixed_delta_time: Some(delta_time),
+            frame_capture: None,
         }
     }
 
+    /// Runs until the window is closed, or until the replay ends. A headless
+    /// application only stops at the end of a replay.
     pub fn start(&mut self) {
         while !self.window.should_close() {
-            self.window.clear(
-                (0.3, 0.3, 0.5, 1.0),
-                gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
-            );
-
-            self.window.handle_events(|window, glfw, event| {
-                PlaneRenderer::resize_from_event(&event);
-                TextRenderer::resize_from_event(&event);
-
-                for layer in &mut self.layers {
-                    layer.on_event(glfw, window, &event);
-                }
-            });
-
-            for layer in &mut self.layers {
-                layer.on_update(&self.window, self.window.calculate_frametime());
+            if !self.run_frame() {
+                break;
             }
+        }
+    }
 
-            self.window.swap_buffers();
+    /// Runs `frames` frames, or fewer if the replay ends first.
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            if !self.run_frame() {
+                break;
+            }
         }
     }
 
-    pub fn add_layer(&mut self, mut layer: Box<dyn Layer>) {
-        layer.on_attach();
-        self.layers.push(layer);
+    /// False once the replay has ended, without running the frame.
+    fn run_frame(&mut self) -> bool {
+        HotReload::poll();
+        self.window.clear(
+            (0.3, 0.3, 0.5, 1.0),
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        );
+
+        let mut events = self.window.poll_events();
+        let mut gamepad_events = self.window.poll_gamepads();
+        let mut delta_time = self
+            .fixed_delta_time
+            .unwrap_or_else(|| self.window.calculate_frametime());
+        if let Some(replay) = &mut self.replay {
+            // Live input is dropped, so the run only depends on the
+            // recording.
+            let Some(frame) = replay.next_frame() else {
+                log::info!("Replay finished");
+                self.replay = None;
+                return false;
+            };
+            events = frame.events;
+            gamepad_events = frame.gamepad_events;
+            delta_time = frame.delta_time;
+        }
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.record_frame(delta_time, &events, &gamepad_events) {
+                log::warn!("Stopped recording: {}", e);
+                self.recorder = None;
+            }
+        }
+
+        let layers = &mut self.layers;
+        let scenes = &mut self.scenes;
+        self.window.dispatch_events(events, |window, glfw, event| {
+            PlaneRenderer::resize_from_event(&event);
+            TextRenderer::resize_from_event(&event);
+
+            let handled = layers
+                .iter_mut()
+                .rev()
+                .any(|layer| layer.on_event(scenes, glfw, window, &event));
+            if !handled {
+                scenes.handle_event(glfw, window, &event);
+            }
+        });
+        for event in gamepad_events {
+            let handled = self
+                .layers
+                .iter_mut()
+                .rev()
+                .any(|layer| layer.on_gamepad_event(&mut self.scenes, &event));
+            if !handled {
+                self.scenes.handle_gamepad_event(&event);
+            }
+        }
+
+        self.time.advance(delta_time);
+        for layer in self.layers.iter_mut() {
+            layer.on_update(&mut self.scenes, &self.window, &mut self.time);
+        }
+        self.scenes.update(&self.time);
+        self.scenes.render(&self.window);
+        for layer in self.layers.iter_mut() {
+            layer.on_render(&mut self.scenes, &self.window);
+        }
+
+        self.save_captures();
+        self.window.swap_buffers();
+        self.window.apply_requests();
+        true
+    }
+
+    /// Saves this frame's requested screenshot and the next image of the
+    /// frame capture, before the back buffer is swapped away.
+    fn save_captures(&mut self) {
+        if let Some(path) = self.window.take_requested_screenshot() {
+            match self.window.save_screenshot(&path) {
+                Ok(()) => log::info!("Saved screenshot to {}", path),
+                Err(e) => log::warn!("Failed to save screenshot to {}: {}", path, e),
+            }
+        }
+        if let Some(capture) = &mut self.frame_capture {
+            let path = capture
+                .directory
+                .join(format!("frame_{:06}.png", capture.next_frame));
+            let path = path.to_string_lossy();
+            if let Err(e) = self.window.save_screenshot(&path) {
+                log::warn!("Stopped frame capture at {}: {}", path, e);
+                self.stop_frame_capture();
+                return;
+            }
+            capture.next_frame += 1;
+        }
+    }
+
+    /// Saves every frame from now on to `directory` as `frame_000000.png`,
+    /// `frame_000001.png` and so on, with a fixed `delta_time` per frame so
+    /// the images play back at `1 / delta_time` frames per second however
+    /// long they take to save.
+    pub fn start_frame_capture(
+        &mut self,
+        directory: &str,
+        delta_time: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.window.is_headless() {
+            return Err("A headless window has nothing to capture".into());
+        }
+        fs::create_dir_all(directory)?;
+        let previous_fixed_delta_time = match self.frame_capture.take() {
+            Some(capture) => capture.previous_fixed_delta_time,
+            None => self.fixed_delta_time,
+        };
+        self.fixed_delta_time = Some(delta_time);
+        self.frame_capture = Some(FrameCapture {
+            directory: directory.into(),
+            next_frame: 0,
+            previous_fixed_delta_time,
+        });
+        Ok(())
+    }
+
+    /// Stops the frame capture and goes back to the previous frame time.
+    pub fn stop_frame_capture(&mut self) {
+        if let Some(capture) = self.frame_capture.take() {
+            self.fixed_delta_time = capture.previous_fixed_delta_time;
+        }
+    }
+
+    pub fn is_capturing_frames(&self) -> bool {
+        self.frame_capture.is_some()
+    }
+
+    /// Writes every frame's input and frame time to `path` until
+    /// `stop_recording`, for `replay`.
+    pub fn record(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.recorder = Some(Recorder::create(path)?);
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    /// Feeds the frames recorded in `path` to the layers instead of live
+    /// input and frame times, and makes `start` return after the last one.
+    /// For the same result as the recorded run, the layers must be set up
+    /// the same way, with deterministic scenes (`Scene::set_deterministic`).
+    pub fn replay(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.replay = Some(Replay::open(path)?);
+        Ok(())
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn get_fixed_delta_time(&self) -> Option<f64> {
+        self.fixed_delta_time
+    }
+
+    /// Uses `delta_time` as every frame's unscaled delta instead of the
+    /// measured frame time, e.g. for recordings that should not depend on the
+    /// machine's speed. `None` measures again; headless applications
+    /// cannot measure and keep their fixed frame time.
+    pub fn set_fixed_delta_time(&mut self, delta_time: Option<f64>) {
+        if delta_time.is_none() && self.window.is_headless() {
+            log::warn!("Headless applications need a fixed delta time");
+            return;
+        }
+        self.fixed_delta_time = delta_time;
+    }
+
+    pub fn get_time(&self) -> &Time {
+        &self.time
+    }
+
+    /// E.g. to start in slow motion or paused.
+    pub fn get_time_mut(&mut self) -> &mut Time {
+        &mut self.time
+    }
+
+    pub fn get_window(&self) -> &Window {
+        &self.window
+    }
+
+    /// E.g. to switch to fullscreen or turn off vsync.
+    pub fn get_window_mut(&mut self) -> &mut Window {
+        &mut self.window
+    }
+
+    /// E.g. to change the stick dead zone.
+    pub fn get_gamepads_mut(&mut self) -> &mut Gamepads {
+        self.window.get_gamepads_mut()
+    }
+
+    /// Adds `layer` below every overlay. It gets events after them and is
+    /// drawn before them.
+    pub fn push_layer(&mut self, layer: Box<dyn Layer>) {
+        self.layers.push_layer(layer);
+    }
+
+    /// Adds `layer` on top, e.g. for debug or UI overlays.
+    pub fn push_overlay(&mut self, layer: Box<dyn Layer>) {
+        self.layers.push_overlay(layer);
+    }
+
+    /// Detaches and returns the topmost layer or overlay named `name`.
+    pub fn pop_layer(&mut self, name: &str) -> Option<Box<dyn Layer>> {
+        self.layers.pop_layer(name)
+    }
+
+    pub fn get_layers(&self) -> &LayerStack {
+        &self.layers
+    }
+
+    /// The topmost layer or overlay of type `T`, e.g. to read a game
+    /// layer's state after `step`.
+    pub fn get_layer<T: Layer>(&self) -> Option<&T> {
+        self.layers.get_layer::<T>()
+    }
+
+    pub fn get_layer_mut<T: Layer>(&mut self) -> Option<&mut T> {
+        self.layers.get_layer_mut::<T>()
+    }
+
+    pub fn get_scenes(&self) -> &SceneManager {
+        &self.scenes
+    }
+
+    /// E.g. to register the game's scenes and switch to the first one.
+    pub fn get_scenes_mut(&mut self) -> &mut SceneManager {
+        &mut self.scenes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rapier3d::prelude::{ColliderBuilder, RigidBodyType};
+
+    use crate::core::{
+        application::{Application, Layer},
+        physics::rigidbody::RigidBody,
+        scene::Scene,
+        scene_manager::SceneManager,
+        time::Time,
+        window::Window,
+    };
+
+    struct FrameCounter {
+        frames: usize,
+    }
+
+    impl Layer for FrameCounter {
+        fn on_update(&mut self, _: &mut SceneManager, _: &Window, _: &mut Time) {
+            self.frames += 1;
+        }
+
+        fn on_event(
+            &mut self,
+            _: &mut SceneManager,
+            _: &mut glfw::Glfw,
+            _: &mut glfw::Window,
+            _: &glfw::WindowEvent,
+        ) -> bool {
+            false
+        }
+
+        fn get_name(&self) -> &str {
+            "Frame counter"
+        }
+    }
+
+    #[test]
+    fn headless_applications_step_their_scenes() {
+        let mut application = Application::new_headless(320, 240, 1.0 / 60.0);
+        let mut scene = Scene::new();
+        let mut ball = scene.create_entity("ball");
+        ball.set_position(&mut scene, (0.0, 10.0, 0.0));
+        let collider = ColliderBuilder::ball(0.5).build();
+        let rigid_body = RigidBody::new(RigidBodyType::Dynamic, &mut scene, &ball, Some(collider));
+        ball.add_component(rigid_body);
+        let ball = scene.add_entity(ball);
+        let scenes = application.get_scenes_mut();
+        scenes.insert("world", scene);
+        scenes.switch_to("world").unwrap();
+        application.push_layer(Box::new(FrameCounter { frames: 0 }));
+
+        application.step(60);
+
+        assert_eq!(application.get_layer::<FrameCounter>().unwrap().frames, 60);
+        let scene = application.get_scenes().get_active().unwrap();
+        let position = scene.get_entity(&ball).unwrap().get_position();
+        // One second of free fall from 10 m drops about 4.9 m.
+        assert!((position.y - 5.1).abs() < 0.3, "y = {}", position.y);
+        assert_eq!(position.x, 0.0);
+        assert_eq!(position.z, 0.0);
     }
 }
@@ -0,0 +1,78 @@
+use super::{Layer, LayerStack};
+
+impl LayerStack {
+    pub fn new() -> Self {
+        Self {
+            layers: Vec::new(),
+            overlay_start: 0,
+        }
+    }
+
+    /// Adds `layer` above the other layers but below every overlay.
+    pub fn push_layer(&mut self, mut layer: Box<dyn Layer>) {
+        layer.on_attach();
+        self.layers.insert(self.overlay_start, layer);
+        self.overlay_start += 1;
+    }
+
+    /// Adds `layer` on top of everything.
+    pub fn push_overlay(&mut self, mut layer: Box<dyn Layer>) {
+        layer.on_attach();
+        self.layers.push(layer);
+    }
+
+    /// Detaches and returns the topmost layer or overlay named `name`.
+    pub fn pop_layer(&mut self, name: &str) -> Option<Box<dyn Layer>> {
+        let index = self
+            .layers
+            .iter()
+            .rposition(|layer| layer.get_name() == name)?;
+        let mut layer = self.layers.remove(index);
+        if index < self.overlay_start {
+            self.overlay_start -= 1;
+        }
+        layer.on_detach();
+        Some(layer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// The topmost layer or overlay of type `T`.
+    pub fn get_layer<T: Layer>(&self) -> Option<&T> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| (**layer).as_any().downcast_ref::<T>())
+    }
+
+    pub fn get_layer_mut<T: Layer>(&mut self) -> Option<&mut T> {
+        self.layers
+            .iter_mut()
+            .rev()
+            .find_map(|layer| (**layer).as_any_mut().downcast_mut::<T>())
+    }
+
+    /// Bottom to top, overlays last.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Box<dyn Layer>> {
+        self.layers.iter()
+    }
+
+    /// Bottom to top, overlays last. Reverse it to go the way events do.
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Box<dyn Layer>> {
+        self.layers.iter_mut()
+    }
+}
+
+impl Drop for LayerStack {
+    fn drop(&mut self) {
+        while let Some(mut layer) = self.layers.pop() {
+            layer.on_detach();
+        }
+    }
+}
@@ -1,22 +1,78 @@
-use super::window::Window;
+use std::path::PathBuf;
+
+use as_any::AsAny;
+
+use super::{
+    input::GamepadEvent,
+    recording::{Recorder, Replay},
+    scene_manager::SceneManager,
+    time::Time,
+    window::Window,
+};
 
 mod application;
+mod layer_stack;
 
 pub struct Application {
+    /// Declared first so the layers detach before the window goes away.
+    layers: LayerStack,
+    /// Updated and drawn after the layers' `on_update` and before their
+    /// `on_render`, and given the events no layer handled. Declared before
+    /// the window so the scenes release their GL resources while the
+    /// context still exists.
+    scenes: SceneManager,
     window: Window,
+    time: Time,
+    recorder: Option<Recorder>,
+    /// While set, its frames replace live input and frame times.
+    replay: Option<Replay>,
+    /// Used instead of the measured frame time when set.
+    fixed_delta_time: Option<f64>,
+    frame_capture: Option<FrameCapture>,
+}
+
+/// Saves every frame to a numbered PNG, see `Application::start_frame_capture`.
+struct FrameCapture {
+    directory: PathBuf,
+    next_frame: u32,
+    /// Restored when the capture stops.
+    previous_fixed_delta_time: Option<f64>,
+}
+
+/// Layers with overlays on top. Updated, and so drawn, from the bottom up;
+/// events go from the top down until a layer handles them. Detaches the
+/// remaining layers when dropped.
+pub struct LayerStack {
     layers: Vec<Box<dyn Layer>>,
+    /// Index of the first overlay in `layers`.
+    overlay_start: usize,
 }
 
-pub trait Layer {
+/// Found by type with `LayerStack::get_layer`.
+pub trait Layer: AsAny {
     fn on_attach(&mut self) {}
     fn on_detach(&mut self) {}
-    fn on_update(&mut self, window: &Window, delta_time: f64);
+    /// Runs before the application's scenes are updated. `time` can be
+    /// changed, e.g. to pause, with effect from the next frame.
+    fn on_update(&mut self, scenes: &mut SceneManager, window: &Window, time: &mut Time);
+    /// Runs after the application's scenes are drawn, e.g. for UI on top
+    /// of them.
+    fn on_render(&mut self, _scenes: &mut SceneManager, _window: &Window) {}
+    /// Returns whether the event was handled, which keeps it from the
+    /// layers below and the scenes.
     fn on_event(
         &mut self,
+        scenes: &mut SceneManager,
         glfw: &mut glfw::Glfw,
         window: &mut glfw::Window,
         event: &glfw::WindowEvent,
-    );
+    ) -> bool;
+    /// Gamepad connections, button presses and stick movement, polled once
+    /// per frame after the window events. Returns whether the event was
+    /// handled, like `on_event`.
+    fn on_gamepad_event(&mut self, _scenes: &mut SceneManager, _event: &GamepadEvent) -> bool {
+        false
+    }
 
     fn get_name(&self) -> &str;
 }
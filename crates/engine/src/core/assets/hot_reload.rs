@@ -0,0 +1,119 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+
+use super::{HotReload, WatchedFile, WatchedPath};
+
+/// Time between two checks of the watched files.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref HOT_RELOAD: Mutex<HotReload> = Mutex::new(HotReload {
+        files: HashMap::new(),
+        last_poll: None,
+    });
+}
+
+impl HotReload {
+    pub fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Relaxed)
+    }
+
+    /// Assets loaded before enabling it are watched as well.
+    pub fn set_enabled(enabled: bool) {
+        ENABLED.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Looks for changed files, at most every `POLL_INTERVAL`. Does nothing
+    /// while disabled.
+    pub fn poll() {
+        if !HotReload::is_enabled() {
+            return;
+        }
+        let mut hot_reload = HOT_RELOAD.lock().unwrap();
+        let now = Instant::now();
+        if hot_reload
+            .last_poll
+            .is_some_and(|last_poll| now - last_poll < POLL_INTERVAL)
+        {
+            return;
+        }
+        hot_reload.last_poll = Some(now);
+        for (path, file) in hot_reload.files.iter_mut() {
+            let modified = modified(path);
+            if modified != file.modified {
+                file.modified = modified;
+                file.version += 1;
+                log::info!("{} changed", path.display());
+            }
+        }
+    }
+
+    fn watch(path: &Path) -> u64 {
+        let mut hot_reload = HOT_RELOAD.lock().unwrap();
+        hot_reload
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| WatchedFile {
+                modified: modified(path),
+                version: 0,
+            })
+            .version
+    }
+
+    fn get_version(path: &Path) -> u64 {
+        let hot_reload = HOT_RELOAD.lock().unwrap();
+        hot_reload.files.get(path).map_or(0, |file| file.version)
+    }
+}
+
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl Clone for WatchedPath {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            version: AtomicU64::new(self.version.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl WatchedPath {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        let path = path.into();
+        let version = HotReload::watch(&path);
+        Self {
+            path,
+            version: AtomicU64::new(version),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// True once for each batch of changes found by `HotReload::poll`.
+    /// Always false while hot reloading is off.
+    pub fn take_change(&self) -> bool {
+        if !HotReload::is_enabled() {
+            return false;
+        }
+        let version = HotReload::get_version(&self.path);
+        self.version.swap(version, Ordering::Relaxed) != version
+    }
+}
@@ -0,0 +1,31 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::atomic::AtomicU64,
+    time::{Instant, SystemTime},
+};
+
+mod hot_reload;
+
+/// Development mode that reloads shaders, textures and animation clips when
+/// their files change. Assets register the files they were loaded from, and
+/// `poll` checks them, once per frame from the `Application`. Off by default.
+pub struct HotReload {
+    files: HashMap<PathBuf, WatchedFile>,
+    last_poll: Option<Instant>,
+}
+
+struct WatchedFile {
+    modified: Option<SystemTime>,
+    /// Bumped on every change.
+    version: u64,
+}
+
+/// A file an asset was loaded from, to find out whether it has changed
+/// since the asset last looked.
+#[derive(Debug)]
+pub struct WatchedPath {
+    path: PathBuf,
+    /// `HotReload` version of the file the asset is up to date with.
+    version: AtomicU64,
+}
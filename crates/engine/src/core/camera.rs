@@ -1,9 +1,10 @@
 use cgmath::{
     perspective, EuclideanSpace, Euler, InnerSpace, Matrix4, Point3, Rad, SquareMatrix, Vector3,
 };
-use glfw::{Action, Key};
 
-use super::utils::DataSource;
+use lazy_static::lazy_static;
+
+use super::{input::Input, utils::DataSource};
 
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = Matrix4::new(
@@ -13,6 +14,15 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = Matrix4::new(
     0.0, 0.0, 0.0, 1.0,
 );
 
+/// Default for the `camera_speed` console variable.
+const CAMERA_SPEED: f32 = 10.0;
+
+lazy_static! {
+    /// The `camera_speed` console variable, kept in sync with the speed of
+    /// the free camera by `CameraComponent`.
+    pub(crate) static ref CAMERA_SPEED_VARIABLE: DataSource<f32> = DataSource::new(CAMERA_SPEED);
+}
+
 
 #[derive(Debug)]
 pub struct Camera {
@@ -113,8 +123,12 @@ pub struct Projection {
 
 impl Projection {
     pub fn new<F: Into<Rad<f32>>>(width: u32, height: u32, fovy: F, znear: f32, zfar: f32) -> Self {
+        Self::with_aspect(width as f32 / height as f32, fovy, znear, zfar)
+    }
+
+    pub fn with_aspect<F: Into<Rad<f32>>>(aspect: f32, fovy: F, znear: f32, zfar: f32) -> Self {
         let mut projection = Self {
-            aspect: width as f32 / height as f32,
+            aspect,
             fovy: fovy.into(),
             znear,
             zfar,
@@ -142,16 +156,18 @@ impl Projection {
     pub fn get_matrix(&self) -> Matrix4<f32> {
         self.matrix
     }
+
+    pub fn get_zfar(&self) -> f32 {
+        self.zfar
+    }
 }
 
 #[derive(Debug)]
 pub struct CameraController {
-    amount_left: f32,
-    amount_right: f32,
+    /// Values of the `fly_forward`, `fly_right` and `fly_up` axes.
     amount_forward: f32,
-    amount_backward: f32,
+    amount_right: f32,
     amount_up: f32,
-    amount_down: f32,
     speed: DataSource<f32>,
     /// Set by PlayerController each frame; when false this controller is a no-op.
     pub is_free: bool,
@@ -160,12 +176,9 @@ pub struct CameraController {
 impl CameraController {
     pub fn new(speed: f32, _sensitivity: f32) -> Self {
         Self {
-            amount_left: 0.0,
-            amount_right: 0.0,
             amount_forward: 0.0,
-            amount_backward: 0.0,
+            amount_right: 0.0,
             amount_up: 0.0,
-            amount_down: 0.0,
             speed: DataSource::new(speed),
             is_free: false,
         }
@@ -186,65 +199,13 @@ impl CameraController {
         }
     }
 
-    pub fn process_keyboard(
-        &mut self,
-        _window: &mut glfw::Window,
-        event: &glfw::WindowEvent,
-    ) -> bool {
+    pub fn process_input(&mut self, input: &Input) {
         if !self.is_free {
-            return false;
-        }
-        match event {
-            glfw::WindowEvent::Key(Key::Up, _, action, _) => {
-                self.amount_forward = match action {
-                    Action::Press => 1.0,
-                    Action::Release => 0.0,
-                    _ => return false,
-                };
-                true
-            }
-            glfw::WindowEvent::Key(Key::Down, _, action, _) => {
-                self.amount_backward = match action {
-                    Action::Press => 1.0,
-                    Action::Release => 0.0,
-                    _ => return false,
-                };
-                true
-            }
-            glfw::WindowEvent::Key(Key::Left, _, action, _) => {
-                self.amount_left = match action {
-                    Action::Press => 1.0,
-                    Action::Release => 0.0,
-                    _ => return false,
-                };
-                true
-            }
-            glfw::WindowEvent::Key(Key::Right, _, action, _) => {
-                self.amount_right = match action {
-                    Action::Press => 1.0,
-                    Action::Release => 0.0,
-                    _ => return false,
-                };
-                true
-            }
-            glfw::WindowEvent::Key(Key::Space, _, action, _) => {
-                self.amount_up = match action {
-                    Action::Press => 1.0,
-                    Action::Release => 0.0,
-                    _ => return false,
-                };
-                true
-            }
-            glfw::WindowEvent::Key(Key::LeftShift, _, action, _) => {
-                self.amount_down = match action {
-                    Action::Press => 1.0,
-                    Action::Release => 0.0,
-                    _ => return false,
-                };
-                true
-            }
-            _ => false,
+            return;
         }
+        self.amount_forward = input.get_axis("fly_forward");
+        self.amount_right = input.get_axis("fly_right");
+        self.amount_up = input.get_axis("fly_up");
     }
 
     pub fn process_mouse(&mut self, _window: &mut glfw::Window, event: &glfw::WindowEvent) {
@@ -267,9 +228,9 @@ impl CameraController {
         let pitch = camera.pitch;
         let speed = self.speed.read();
 
-        position += forward * (self.amount_forward - self.amount_backward) * speed * delta_time;
-        position += right * (self.amount_right - self.amount_left) * speed * delta_time;
-        position.y += (self.amount_up - self.amount_down) * speed * delta_time;
+        position += forward * self.amount_forward * speed * delta_time;
+        position += right * self.amount_right * speed * delta_time;
+        position.y += self.amount_up * speed * delta_time;
 
         camera.update(position, yaw, pitch);
     }
@@ -0,0 +1,301 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use lazy_static::lazy_static;
+
+use crate::{
+    core::{
+        camera::CAMERA_SPEED_VARIABLE,
+        entity::component::debug_component::WIREFRAME_VARIABLE,
+        renderer::light::skylight::SHADOW_DISTANCE_VARIABLE,
+        utils::DataSource,
+        window::VSYNC_VARIABLE,
+    },
+    terrain::CHUNK_RADIUS_VARIABLE,
+};
+
+use super::{Console, ConsoleCommand, ConsoleValue, ConsoleVariable};
+
+/// Older lines are dropped.
+const MAX_LINES: usize = 1000;
+
+lazy_static! {
+    static ref CONSOLE: Mutex<Console> = Mutex::new(Console::new());
+}
+
+impl Console {
+    fn new() -> Self {
+        let mut console = Console {
+            commands: BTreeMap::new(),
+            variables: BTreeMap::new(),
+            lines: VecDeque::new(),
+        };
+        console.add_command("help", "Lists the commands and variables", |_| {
+            Ok(Console::help())
+        });
+        console.add_command("clear", "Clears the console", |_| {
+            Console::clear();
+            Ok(String::new())
+        });
+        console.add_command(
+            "reset",
+            "Sets a variable back to its registered value",
+            |args| {
+                let name = args.first().ok_or("Usage: reset <variable>")?;
+                Console::reset(name)?;
+                Ok(format!(
+                    "{} = {}",
+                    name,
+                    Console::get(name).unwrap_or_default()
+                ))
+            },
+        );
+        console.add_engine_variables();
+        console
+    }
+
+    /// Settings shared by every instance of the components that use them,
+    /// which read them each frame.
+    fn add_engine_variables(&mut self) {
+        self.add_variable(
+            "camera_speed",
+            "Speed of the free camera",
+            CAMERA_SPEED_VARIABLE.clone(),
+        );
+        self.add_variable(
+            "shadow_distance",
+            "How far from the camera shadows are drawn",
+            SHADOW_DISTANCE_VARIABLE.clone(),
+        );
+        self.add_variable(
+            "wireframe",
+            "Draws polygons as lines",
+            WIREFRAME_VARIABLE.clone(),
+        );
+        self.add_variable(
+            "vsync",
+            "Waits for the display to show each frame",
+            VSYNC_VARIABLE.clone(),
+        );
+        self.add_variable(
+            "chunk_radius",
+            "Rings of chunks loaded around each terrain's origin",
+            CHUNK_RADIUS_VARIABLE.clone(),
+        );
+    }
+
+    fn add_command<F>(&mut self, name: &str, description: &str, callback: F)
+    where
+        F: Fn(&[&str]) -> Result<String, Box<dyn Error>> + Send + Sync + 'static,
+    {
+        self.commands.insert(
+            name.to_string(),
+            ConsoleCommand {
+                description: description.to_string(),
+                callback: Arc::new(callback),
+            },
+        );
+    }
+
+    /// Replaces a command or variable with the same name.
+    pub fn register_command<F>(name: &str, description: &str, callback: F)
+    where
+        F: Fn(&[&str]) -> Result<String, Box<dyn Error>> + Send + Sync + 'static,
+    {
+        let mut console = CONSOLE.lock().unwrap();
+        console.variables.remove(name);
+        console.add_command(name, description, callback);
+    }
+
+    fn add_variable<T>(&mut self, name: &str, description: &str, source: DataSource<T>)
+    where
+        T: Clone + ToString + FromStr + Send + Sync + 'static,
+    {
+        self.commands.remove(name);
+        let default = source.to_string();
+        self.variables.insert(
+            name.to_string(),
+            ConsoleVariable {
+                description: description.to_string(),
+                value: Arc::new(source),
+                default,
+            },
+        );
+    }
+
+    /// Typing the name shows the value, typing it followed by a value sets
+    /// `source`. Replaces a command or variable with the same name.
+    pub fn register_variable<T>(name: &str, description: &str, source: DataSource<T>)
+    where
+        T: Clone + ToString + FromStr + Send + Sync + 'static,
+    {
+        let mut console = CONSOLE.lock().unwrap();
+        console.add_variable(name, description, source);
+    }
+
+    /// Removes the command or variable `name`.
+    pub fn unregister(name: &str) {
+        let mut console = CONSOLE.lock().unwrap();
+        console.commands.remove(name);
+        console.variables.remove(name);
+    }
+
+    /// Runs a line typed into the console: a command with its arguments, a
+    /// variable name to show it, or a variable name and a new value.
+    pub fn execute(line: &str) {
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return;
+        };
+        let args: Vec<&str> = words.collect();
+        Console::print(&format!("> {}", line));
+
+        // Called without the lock, so commands can use the console.
+        let callback = CONSOLE
+            .lock()
+            .unwrap()
+            .commands
+            .get(name)
+            .map(|command| command.callback.clone());
+        if let Some(callback) = callback {
+            match callback(&args) {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => Console::print(&output),
+                Err(e) => Console::print(&format!("{}: {}", name, e)),
+            }
+            return;
+        }
+
+        let result = if args.is_empty() {
+            Console::get(name).ok_or_else(|| format!("Unknown command or variable: {}", name))
+        } else {
+            Console::set(name, &args.join(" ")).map(|()| Console::get(name).unwrap_or_default())
+        };
+        match result {
+            Ok(value) => Console::print(&format!("{} = {}", name, value)),
+            Err(e) => Console::print(&e),
+        }
+    }
+
+    pub fn get(name: &str) -> Option<String> {
+        Console::get_value(name).map(|value| value.get())
+    }
+
+    /// Fails if there is no such variable or `value` does not parse.
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        Console::get_value(name)
+            .ok_or_else(|| format!("Unknown variable: {}", name))?
+            .set(value)
+    }
+
+    pub fn reset(name: &str) -> Result<(), String> {
+        let (value, default) = {
+            let console = CONSOLE.lock().unwrap();
+            let variable = console
+                .variables
+                .get(name)
+                .ok_or_else(|| format!("Unknown variable: {}", name))?;
+            (variable.value.clone(), variable.default.clone())
+        };
+        value.set(&default)
+    }
+
+    /// Values are read and written without the lock, since they may log.
+    fn get_value(name: &str) -> Option<Arc<dyn ConsoleValue>> {
+        let console = CONSOLE.lock().unwrap();
+        console
+            .variables
+            .get(name)
+            .map(|variable| variable.value.clone())
+    }
+
+    /// Adds text to the console, one line per line of `text`.
+    pub fn print(text: &str) {
+        let mut console = CONSOLE.lock().unwrap();
+        for line in text.lines() {
+            if console.lines.len() == MAX_LINES {
+                console.lines.pop_front();
+            }
+            console.lines.push_back(line.to_string());
+        }
+    }
+
+    pub fn clear() {
+        CONSOLE.lock().unwrap().lines.clear();
+    }
+
+    /// Up to `count` lines, oldest first, ending `scroll` lines above the
+    /// newest one.
+    pub fn get_lines(count: usize, scroll: usize) -> Vec<String> {
+        let console = CONSOLE.lock().unwrap();
+        let end = console.lines.len().saturating_sub(scroll);
+        let start = end.saturating_sub(count);
+        console.lines.range(start..end).cloned().collect()
+    }
+
+    pub fn get_line_count() -> usize {
+        CONSOLE.lock().unwrap().lines.len()
+    }
+
+    /// Names of the commands and variables starting with `prefix`, sorted.
+    pub fn complete(prefix: &str) -> Vec<String> {
+        let console = CONSOLE.lock().unwrap();
+        let mut names: Vec<String> = console
+            .commands
+            .keys()
+            .chain(console.variables.keys())
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn help() -> String {
+        let mut help = String::from("Commands:");
+        let variables: Vec<(String, Arc<dyn ConsoleValue>, String)> = {
+            let console = CONSOLE.lock().unwrap();
+            for (name, command) in console.commands.iter() {
+                help.push_str(&format!("\n  {} - {}", name, command.description));
+            }
+            console
+                .variables
+                .iter()
+                .map(|(name, variable)| {
+                    (
+                        name.clone(),
+                        variable.value.clone(),
+                        variable.description.clone(),
+                    )
+                })
+                .collect()
+        };
+        help.push_str("\nVariables:");
+        for (name, value, description) in variables {
+            help.push_str(&format!("\n  {} = {} - {}", name, value.get(), description));
+        }
+        help
+    }
+}
+
+impl<T> ConsoleValue for DataSource<T>
+where
+    T: Clone + ToString + FromStr + Send + Sync,
+{
+    fn get(&self) -> String {
+        self.to_string()
+    }
+
+    fn set(&self, value: &str) -> Result<(), String> {
+        let value = value
+            .parse()
+            .map_err(|_| format!("Invalid value: {}", value))?;
+        self.write(value);
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use glfw::{Action, Key, WindowEvent};
+
+use crate::core::{
+    application::Layer,
+    primitives::{Position, Size},
+    renderer::{
+        plane::PlaneBuilder,
+        text::{Fonts, Text, TextRenderer},
+    },
+    scene_manager::SceneManager,
+    time::Time,
+    utils::DataSource,
+    window::Window,
+};
+
+use super::{Console, ConsoleLayer};
+
+const TOGGLE_KEY: Key = Key::GraveAccent;
+const FONT_SIZE: f32 = 16.0;
+const LINE_HEIGHT: f32 = 20.0;
+const PADDING: f32 = 8.0;
+/// Advance of a RobotoMono glyph at `FONT_SIZE`, to cut lines before they
+/// wrap.
+const CHAR_WIDTH: f32 = 9.6;
+const BACKGROUND_COLOR: (f32, f32, f32, f32) = (0.05, 0.05, 0.08, 0.85);
+
+impl ConsoleLayer {
+    pub fn new() -> Self {
+        let (width, height) = TextRenderer::get_size();
+        let mut layer = Self {
+            open: false,
+            skip_char: false,
+            input: String::new(),
+            history: Vec::new(),
+            history_index: None,
+            scroll: 0,
+            previous_cursor_mode: None,
+            time_scale: DataSource::new(1.0),
+            applied_time_scale: 1.0,
+            size: (0, 0),
+            background: PlaneBuilder::new().color(BACKGROUND_COLOR).build(),
+            line_texts: Vec::new(),
+            input_text: Text::new(Fonts::RobotoMono, 0, 0, 1, FONT_SIZE, String::new()),
+        };
+        layer.resize(width, height);
+        layer
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// The console covers the upper half of the window.
+    fn resize(&mut self, width: u32, height: u32) {
+        self.size = (width, height);
+        let console_height = (height / 2) as f32;
+        self.background.set_position(Position {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        });
+        self.background.set_size(Size {
+            width: width as f32,
+            height: console_height,
+        });
+        let line_count = ((console_height - 2.0 * PADDING) / LINE_HEIGHT - 1.0).max(0.0) as usize;
+        self.line_texts = (0..line_count)
+            .map(|i| {
+                let y = PADDING + i as f32 * LINE_HEIGHT;
+                Text::new(
+                    Fonts::RobotoMono,
+                    PADDING as i32,
+                    y as i32,
+                    1,
+                    FONT_SIZE,
+                    String::new(),
+                )
+            })
+            .collect();
+        self.input_text.prepare_render_at(Position {
+            x: PADDING,
+            y: console_height - PADDING - LINE_HEIGHT,
+            z: 1.0,
+        });
+    }
+
+    fn set_open(&mut self, window: &mut glfw::Window, open: bool) {
+        self.open = open;
+        if open {
+            self.previous_cursor_mode = Some(window.get_cursor_mode());
+            window.set_cursor_mode(glfw::CursorMode::Normal);
+        } else if let Some(cursor_mode) = self.previous_cursor_mode.take() {
+            window.set_cursor_mode(cursor_mode);
+        }
+    }
+
+    /// Applies `time_scale` when it was changed in the console, and picks
+    /// up changes made to `Time` elsewhere otherwise.
+    fn sync_time_scale(&mut self, time: &mut Time) {
+        let time_scale = self.time_scale.read();
+        if time_scale != self.applied_time_scale {
+            if time_scale >= 0.0 {
+                time.set_time_scale(time_scale);
+            } else {
+                Console::print("time_scale must not be negative");
+            }
+        }
+        self.applied_time_scale = time.get_time_scale();
+        self.time_scale.write(self.applied_time_scale);
+    }
+
+    fn handle_key(&mut self, window: &mut glfw::Window, key: Key) {
+        match key {
+            Key::Escape => self.set_open(window, false),
+            Key::Enter | Key::KpEnter => {
+                let line = std::mem::take(&mut self.input);
+                if !line.trim().is_empty() && self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+                }
+                self.history_index = None;
+                self.scroll = 0;
+                Console::execute(&line);
+            }
+            Key::Backspace => {
+                self.input.pop();
+            }
+            Key::Tab => self.complete(),
+            Key::Up => {
+                let index = match self.history_index {
+                    Some(index) => index.saturating_sub(1),
+                    None if self.history.is_empty() => return,
+                    None => self.history.len() - 1,
+                };
+                self.history_index = Some(index);
+                self.input = self.history[index].clone();
+            }
+            Key::Down => {
+                let Some(index) = self.history_index else {
+                    return;
+                };
+                if index + 1 < self.history.len() {
+                    self.history_index = Some(index + 1);
+                    self.input = self.history[index + 1].clone();
+                } else {
+                    self.history_index = None;
+                    self.input.clear();
+                }
+            }
+            Key::PageUp => self.scroll_by(self.line_texts.len() as i32),
+            Key::PageDown => self.scroll_by(-(self.line_texts.len() as i32)),
+            _ => {}
+        }
+    }
+
+    /// Completes the command or variable name being typed. With several
+    /// candidates, completes their common start or lists them.
+    fn complete(&mut self) {
+        if self.input.contains(' ') {
+            return;
+        }
+        let names = Console::complete(&self.input);
+        match names.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{} ", name),
+            [first, rest @ ..] => {
+                let mut prefix = first.as_str();
+                for name in rest {
+                    while !name.starts_with(prefix) {
+                        let last = prefix.char_indices().last().map_or(0, |(i, _)| i);
+                        prefix = &prefix[..last];
+                    }
+                }
+                if prefix.len() > self.input.len() {
+                    self.input = prefix.to_string();
+                } else {
+                    Console::print(&names.join("  "));
+                }
+            }
+        }
+    }
+
+    fn scroll_by(&mut self, lines: i32) {
+        let max_scroll = Console::get_line_count().saturating_sub(self.line_texts.len());
+        self.scroll = (self.scroll as i32 + lines).clamp(0, max_scroll as i32) as usize;
+    }
+
+    fn render(&mut self) {
+        let max_chars = ((self.size.0 as f32 - 2.0 * PADDING) / CHAR_WIDTH).max(0.0) as usize;
+        let lines = Console::get_lines(self.line_texts.len(), self.scroll);
+        // The newest line sits right above the input.
+        let empty = self.line_texts.len() - lines.len();
+        for (i, text) in self.line_texts.iter_mut().enumerate() {
+            match i.checked_sub(empty) {
+                Some(line) => text.set_content(truncate(&lines[line], max_chars)),
+                None => text.set_content(""),
+            }
+        }
+        let input = format!("> {}_", self.input);
+        let start = input.chars().count().saturating_sub(max_chars);
+        let input: String = input.chars().skip(start).collect();
+        self.input_text.set_content(&input);
+
+        let mut polygon_mode = 0;
+        unsafe {
+            gl::GetIntegerv(gl::POLYGON_MODE, &mut polygon_mode);
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            // Drawn over everything below, whatever its depth.
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+        self.background.render();
+        for text in self.line_texts.iter() {
+            text.render();
+        }
+        self.input_text.render();
+        unsafe {
+            gl::PolygonMode(gl::FRONT_AND_BACK, polygon_mode as u32);
+        }
+    }
+}
+
+fn truncate(line: &str, max_chars: usize) -> &str {
+    match line.char_indices().nth(max_chars) {
+        Some((end, _)) => &line[..end],
+        None => line,
+    }
+}
+
+impl Layer for ConsoleLayer {
+    fn on_attach(&mut self) {
+        Console::register_variable(
+            "time_scale",
+            "Speed of game time, 0 freezes it",
+            self.time_scale.clone(),
+        );
+    }
+
+    fn on_detach(&mut self) {
+        Console::unregister("time_scale");
+    }
+
+    fn on_update(&mut self, _: &mut SceneManager, _: &Window, time: &mut Time) {
+        self.sync_time_scale(time);
+    }
+
+    fn on_render(&mut self, _: &mut SceneManager, window: &Window) {
+        if !self.open {
+            return;
+        }
+        if (window.width, window.height) != self.size {
+            self.resize(window.width, window.height);
+        }
+        self.render();
+    }
+
+    fn on_event(
+        &mut self,
+        _: &mut SceneManager,
+        _: &mut glfw::Glfw,
+        window: &mut glfw::Window,
+        event: &WindowEvent,
+    ) -> bool {
+        // The toggle key's character, if it types one, comes right after
+        // its press.
+        let skip_char = std::mem::take(&mut self.skip_char);
+        match event {
+            WindowEvent::Key(TOGGLE_KEY, _, Action::Press, _) => {
+                self.set_open(window, !self.open);
+                self.skip_char = true;
+                true
+            }
+            WindowEvent::Char(_) if skip_char => true,
+            _ if !self.open => false,
+            // Releases still reach the layers below, so no key stays held.
+            WindowEvent::Key(_, _, Action::Release, _) => false,
+            WindowEvent::Key(key, _, _, _) => {
+                self.handle_key(window, *key);
+                true
+            }
+            WindowEvent::Char(c) => {
+                self.input.push(*c);
+                self.history_index = None;
+                true
+            }
+            WindowEvent::Scroll(_, y) => {
+                self.scroll_by((*y * 3.0) as i32);
+                true
+            }
+            WindowEvent::MouseButton(..) | WindowEvent::CursorPos(..) => true,
+            _ => false,
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "Console"
+    }
+}
@@ -0,0 +1,39 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+use super::{Console, ConsoleLogger};
+
+/// Records up to this level are captured by the console, whatever
+/// `RUST_LOG` lets through to stderr.
+const CAPTURE_LEVEL: LevelFilter = LevelFilter::Info;
+
+impl Console {
+    /// Installs the logger that copies `log` output into the console. Logs
+    /// to stderr like `env_logger::init` otherwise. Does nothing if a
+    /// logger is already set.
+    pub fn init_logger() {
+        let inner = env_logger::Builder::from_default_env().build();
+        let max_level = inner.filter().max(CAPTURE_LEVEL);
+        if log::set_boxed_logger(Box::new(ConsoleLogger { inner })).is_ok() {
+            log::set_max_level(max_level);
+        }
+    }
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= CAPTURE_LEVEL || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+        if record.level() <= CAPTURE_LEVEL {
+            Console::print(&format!("[{}] {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    sync::Arc,
+};
+
+use crate::core::{
+    renderer::{plane::Plane, text::Text},
+    utils::DataSource,
+};
+
+mod console;
+mod layer;
+mod logger;
+
+/// Commands and variables for the developer console, and the lines it shows.
+/// The engine's own variables are registered once, bound to settings its
+/// components read every frame. Anything can register more, and
+/// `ConsoleLayer` is the overlay to type them in.
+pub struct Console {
+    commands: BTreeMap<String, ConsoleCommand>,
+    variables: BTreeMap<String, ConsoleVariable>,
+    /// Command output and captured `log` records, oldest first.
+    lines: VecDeque<String>,
+}
+
+/// Called with the words after the command name. The returned text is
+/// printed to the console.
+type CommandCallback = Arc<dyn Fn(&[&str]) -> Result<String, Box<dyn Error>> + Send + Sync>;
+
+struct ConsoleCommand {
+    description: String,
+    callback: CommandCallback,
+}
+
+struct ConsoleVariable {
+    description: String,
+    /// Shared so it can be called without holding the console's lock.
+    value: Arc<dyn ConsoleValue>,
+    /// Value at registration, restored by `reset`.
+    default: String,
+}
+
+/// A value the console can show and change, see `Console::register_variable`.
+trait ConsoleValue: Send + Sync {
+    fn get(&self) -> String;
+    fn set(&self, value: &str) -> Result<(), String>;
+}
+
+/// Sends `log` records to the console as well as to `env_logger`.
+struct ConsoleLogger {
+    inner: env_logger::Logger,
+}
+
+/// Overlay for the `Console`, toggled with the key left of 1. Keeps input
+/// from the layers below while open, and registers `time_scale`.
+pub struct ConsoleLayer {
+    open: bool,
+    /// Set by the toggle key's press, to drop the character it types.
+    skip_char: bool,
+    input: String,
+    history: Vec<String>,
+    /// Entry of `history` shown in the input, while browsing it.
+    history_index: Option<usize>,
+    /// Lines scrolled up from the newest one.
+    scroll: usize,
+    /// Restored when the console closes.
+    previous_cursor_mode: Option<glfw::CursorMode>,
+    time_scale: DataSource<f64>,
+    /// Time scale last applied to or read from `Time`.
+    applied_time_scale: f64,
+    size: (u32, u32),
+    background: Plane,
+    line_texts: Vec<Text>,
+    input_text: Text,
+}
@@ -1,33 +1,98 @@
 use glfw::{Glfw, WindowEvent};
 
-use crate::core::{entity::Entity, model::animation_graph::AnimationGraph, scene::Scene};
+use crate::core::{
+    entity::Entity,
+    event::{Event, EventScope, SubscriptionId},
+    model::{animation_graph::AnimationGraph, Pose},
+    scene::Scene,
+};
 
 use super::{model_component::ModelComponent, Component};
 
+/// Plays an `AnimationGraph` on the entity's `ModelComponent`. Poses are
+/// sampled by the scene's animation stage, in parallel across entities, and
+/// applied to the model in `update`.
 pub struct AnimationComponent {
     animation_graph: AnimationGraph,
+    /// Sampled this frame, not applied to the model yet.
+    pose: Option<Pose>,
+    /// Subscription to `SetAnimationInput` events for the owning entity.
+    subscription: Option<SubscriptionId>,
 }
 
+/// Sets an input of the animation graph on the entity the event is
+/// published for, e.g. by the `PlayerController` driving it.
+#[derive(Clone, Debug)]
+pub struct SetAnimationInput {
+    pub name: String,
+    pub value: f32,
+}
+
+impl Event for SetAnimationInput {}
+
+/// Published when the animations of the graph's current state complete a
+/// cycle.
+#[derive(Clone, Debug)]
+pub struct AnimationFinished {
+    pub state: String,
+}
+
+impl Event for AnimationFinished {}
+
 impl AnimationComponent {
     pub fn new(animation_graph: AnimationGraph) -> Self {
-        AnimationComponent { animation_graph }
+        AnimationComponent {
+            animation_graph,
+            pose: None,
+            subscription: None,
+        }
     }
 
     pub fn set_input(&mut self, name: &str, value: f32) {
         self.animation_graph.set_input(name, value);
     }
+
+    /// Advances the graph and samples its pose. Touches nothing but the
+    /// component, so it can run off the main thread.
+    pub(crate) fn sample(&mut self, delta_time: f32) {
+        self.animation_graph.update(delta_time);
+        self.pose = self.animation_graph.get_pose();
+    }
 }
 
 impl Component for AnimationComponent {
-    fn update(&mut self, _: &mut Scene, entity: &mut Entity, delta_time: f64) {
-        self.animation_graph.update(delta_time as f32);
-        let pose = self.animation_graph.get_pose();
-        if let Some(pose) = pose {
-            if let Some(model_component) = entity.get_component_mut::<ModelComponent>() {
+    fn update(&mut self, scene: &mut Scene, entity: &mut Entity, _: f64) {
+        if let Some(pose) = self.pose.take() {
+            if pose.cycle_completed {
+                scene.get_events_mut().publish(
+                    Some(entity.id),
+                    AnimationFinished {
+                        state: self.animation_graph.get_current_state().to_string(),
+                    },
+                );
+            }
+            if let Some(model_component) = entity.get_component_mut::<ModelComponent>(scene) {
                 model_component.get_model_mut().apply_pose(&pose);
             }
         }
     }
 
     fn handle_event(&mut self, _: &mut Glfw, _: &mut glfw::Window, _: &WindowEvent) {}
+
+    fn on_added(&mut self, scene: &mut Scene, entity: &mut Entity) {
+        let subscription = scene
+            .get_events_mut()
+            .subscribe_component::<AnimationComponent, SetAnimationInput, _>(
+                entity.id,
+                EventScope::Entity(entity.id),
+                |animation, _, _, input| animation.set_input(&input.name, input.value),
+            );
+        self.subscription = Some(subscription);
+    }
+
+    fn on_removed(&mut self, scene: &mut Scene, _: &mut Entity) {
+        if let Some(subscription) = self.subscription.take() {
+            scene.get_events_mut().unsubscribe(subscription);
+        }
+    }
 }
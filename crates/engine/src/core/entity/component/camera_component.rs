@@ -1,9 +1,14 @@
-use cgmath::Matrix4;
+use std::error::Error;
+
+use cgmath::{Matrix4, Rad};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::core::{
-    camera::{Camera, CameraController, Projection},
+    camera::{Camera, CameraController, Projection, CAMERA_SPEED_VARIABLE},
     entity::Entity,
     scene::Scene,
+    serialization::SerializableComponent,
 };
 
 use super::Component;
@@ -12,6 +17,21 @@ pub struct CameraComponent {
     camera: Camera,
     projection: Projection,
     camera_controller: CameraController,
+    /// `CAMERA_SPEED_VARIABLE` as of the last sync.
+    synced_speed: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CameraComponentData {
+    position: [f32; 3],
+    relative_position: [f32; 3],
+    yaw: f32,
+    pitch: f32,
+    aspect: f32,
+    fovy: f32,
+    znear: f32,
+    zfar: f32,
+    speed: f32,
 }
 
 impl CameraComponent {
@@ -24,6 +44,7 @@ impl CameraComponent {
             camera,
             projection,
             camera_controller,
+            synced_speed: CAMERA_SPEED_VARIABLE.read(),
         }
     }
 
@@ -54,10 +75,26 @@ impl CameraComponent {
     pub fn get_view_projection(&self) -> Matrix4<f32> {
         self.projection.get_matrix() * self.camera.get_matrix()
     }
+
+    /// While free, applies `camera_speed` when it was changed in the console
+    /// and shows this camera's speed in it otherwise.
+    fn sync_speed(&mut self) {
+        if !self.camera_controller.is_free {
+            return;
+        }
+        let speed = CAMERA_SPEED_VARIABLE.read();
+        if speed != self.synced_speed {
+            self.camera_controller.set_speed(speed);
+        }
+        self.synced_speed = self.camera_controller.get_speed();
+        CAMERA_SPEED_VARIABLE.write(self.synced_speed);
+    }
 }
 
 impl Component for CameraComponent {
-    fn update(&mut self, _: &mut Scene, _: &mut Entity, delta_time: f64) {
+    fn update(&mut self, scene: &mut Scene, _: &mut Entity, delta_time: f64) {
+        self.sync_speed();
+        self.camera_controller.process_input(scene.get_input());
         self.camera_controller
             .update_camera(&mut self.camera, delta_time as f32);
     }
@@ -68,8 +105,41 @@ impl Component for CameraComponent {
         window: &mut glfw::Window,
         event: &glfw::WindowEvent,
     ) {
-        self.camera_controller.process_keyboard(window, event);
         self.camera_controller.process_mouse(window, event);
         self.projection.resize(&event);
     }
 }
+
+impl SerializableComponent for CameraComponent {
+    const TYPE_NAME: &'static str = "CameraComponent";
+
+    fn save(&self, _: &Scene) -> Result<Value, Box<dyn Error>> {
+        let position = self.camera.get_position();
+        let relative_position = self.camera.get_relative_position();
+        Ok(serde_json::to_value(CameraComponentData {
+            position: [position.x, position.y, position.z],
+            relative_position: [
+                relative_position.x,
+                relative_position.y,
+                relative_position.z,
+            ],
+            yaw: self.camera.get_yaw().0,
+            pitch: self.camera.get_pitch().0,
+            aspect: self.projection.aspect,
+            fovy: self.projection.fovy.0,
+            znear: self.projection.znear,
+            zfar: self.projection.get_zfar(),
+            speed: self.camera_controller.get_speed(),
+        })?)
+    }
+
+    fn load(data: Value, _: &mut Scene, _: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        let data: CameraComponentData = serde_json::from_value(data)?;
+        let mut camera = Camera::new(data.position, Rad(data.yaw), Rad(data.pitch));
+        camera.set_relative_position(data.relative_position);
+        let projection =
+            Projection::with_aspect(data.aspect, Rad(data.fovy), data.znear, data.zfar);
+        let camera_controller = CameraController::new(data.speed, 1.0);
+        Ok(CameraComponent::new(camera, projection, camera_controller))
+    }
+}
@@ -1,6 +1,9 @@
-use glfw::{Action, Glfw, Key};
+use glfw::Glfw;
 use rapier3d::prelude::{ColliderHandle, TypedShape};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::error::Error;
 use std::f32::consts::PI;
 
 use crate::{
@@ -14,25 +17,30 @@ use crate::{
             text::{Fonts, Text},
         },
         scene::Scene,
+        serialization::SerializableComponent,
+        utils::DataSource,
+        window::VSYNC_VARIABLE,
     },
     terrain::{dual_contouring::DualContouringChunk, ChunkBounds, Terrain, CHUNK_SIZE},
 };
 use cgmath::{Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Vector3};
+use lazy_static::lazy_static;
 
 use super::model_component::ModelComponent;
 
 const CIRCLE_SEGMENTS: usize = 16;
 const TRIMESH_DRAW_DISTANCE: f32 = 96.0;
 
+lazy_static! {
+    /// The `wireframe` console variable, also toggled with a key.
+    pub(crate) static ref WIREFRAME_VARIABLE: DataSource<bool> = DataSource::new(false);
+}
+
 pub struct DebugController {
     pub debug_ui: bool,
-    wireframe: bool,
-    vsync: bool,
     show_rays: bool,
     show_colliders: bool,
-    f3_held: bool,
-    f3_used_as_modifier: bool,
-    delta_time: f64,
+    applied_wireframe: bool,
 
     bounds: ChunkBounds,
 
@@ -49,17 +57,20 @@ pub struct DebugController {
         HashMap<ColliderHandle, Vec<(Point3<f32>, Point3<f32>, Point3<f32>, Point3<f32>)>>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct DebugControllerData {
+    debug_ui: bool,
+    show_rays: bool,
+    show_colliders: bool,
+}
+
 impl DebugController {
     pub fn new() -> Self {
         Self {
             debug_ui: false,
-            wireframe: false,
-            vsync: true,
             show_rays: false,
             show_colliders: false,
-            f3_held: false,
-            f3_used_as_modifier: false,
-            delta_time: 0.0,
+            applied_wireframe: false,
 
             bounds: ChunkBounds {
                 min: (0, 0, 0),
@@ -176,16 +187,49 @@ fn capsule_wires(a: Point3<f32>, b: Point3<f32>, r: f32) -> Vec<Line> {
 // --- component impl ---
 
 impl Component for DebugController {
-    fn update(&mut self, scene: &mut Scene, _: &mut Entity, delta_time: f64) {
-        self.delta_time = delta_time;
+    fn update(&mut self, scene: &mut Scene, _: &mut Entity, _: f64) {
+        let input = scene.get_input();
+        if input.just_pressed("toggle_wireframe") {
+            WIREFRAME_VARIABLE.write(!WIREFRAME_VARIABLE.read());
+        }
+        let wireframe = WIREFRAME_VARIABLE.read();
+        if wireframe != self.applied_wireframe {
+            unsafe {
+                if wireframe {
+                    gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+                } else {
+                    gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+                }
+            }
+            self.applied_wireframe = wireframe;
+        }
+        if input.just_pressed("toggle_vsync") {
+            VSYNC_VARIABLE.write(!VSYNC_VARIABLE.read());
+        }
+        // F3 alone toggles the overlay; held, it is the modifier of F3+C.
+        if input.just_tapped("toggle_debug") {
+            self.debug_ui = !self.debug_ui;
+        }
+        if input.just_pressed("toggle_colliders") {
+            self.show_colliders = !self.show_colliders;
+        }
+        if input.just_pressed("toggle_rays") {
+            self.show_rays = !self.show_rays;
+        }
 
-        let fps = 1.0 / self.delta_time;
+        let time = scene.get_time();
         self.fps_text.set_content(&format!(
-            "{:.2} FPS ({:.2}ms)",
-            fps,
-            self.delta_time * 1000.0
+            "{:.2} FPS ({:.2}ms, 99%: {:.2}ms){}",
+            time.get_fps(),
+            time.get_smoothed_delta() * 1000.0,
+            time.get_slow_frame_time() * 1000.0,
+            if time.is_paused() { " paused" } else { "" }
         ));
         if self.debug_ui && self.show_colliders {
+            // Drop geometry for colliders that were removed with their entity.
+            self.trimesh_cache
+                .retain(|handle, _| scene.physics_engine.colliders.contains(*handle));
+
             // Cache trimesh geometry for any colliders we haven't seen yet.
             // Terrain is static so world-space positions are computed once.
             for (handle, collider) in scene.physics_engine.colliders.iter() {
@@ -238,59 +282,17 @@ impl Component for DebugController {
                     self.bounds.max.0, self.bounds.max.1, self.bounds.max.2
                 ));
             }
-            let mut triangle_count = 0;
-            for terrain in scene.get_entities_with_component::<Terrain<DualContouringChunk>>() {
-                triangle_count += terrain
-                    .get_component::<Terrain<DualContouringChunk>>()
-                    .unwrap()
-                    .get_triangle_count(&terrain);
-            }
+            let triangle_count: usize = scene
+                .query_ref::<(&Entity, &Terrain<DualContouringChunk>)>()
+                .iter()
+                .map(|(entity, terrain)| terrain.get_triangle_count(scene, entity))
+                .sum();
             self.triangle_count_text
                 .set_content(&format!("Triangles: {}", triangle_count));
         }
     }
 
-    fn handle_event(&mut self, glfw: &mut Glfw, _: &mut glfw::Window, event: &glfw::WindowEvent) {
-        match event {
-            glfw::WindowEvent::Key(Key::F1, _, Action::Press, _) => {
-                self.wireframe = !self.wireframe;
-                unsafe {
-                    if self.wireframe {
-                        gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-                    } else {
-                        gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
-                    }
-                }
-            }
-            glfw::WindowEvent::Key(Key::F2, _, Action::Press, _) => {
-                self.vsync = !self.vsync;
-                if self.vsync {
-                    glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-                } else {
-                    glfw.set_swap_interval(glfw::SwapInterval::None);
-                }
-            }
-            glfw::WindowEvent::Key(Key::F3, _, Action::Press, _) => {
-                self.f3_held = true;
-                self.f3_used_as_modifier = false;
-            }
-            glfw::WindowEvent::Key(Key::F3, _, Action::Release, _) => {
-                if !self.f3_used_as_modifier {
-                    self.debug_ui = !self.debug_ui;
-                }
-                self.f3_held = false;
-                self.f3_used_as_modifier = false;
-            }
-            glfw::WindowEvent::Key(Key::C, _, Action::Press, _) if self.f3_held => {
-                self.show_colliders = !self.show_colliders;
-                self.f3_used_as_modifier = true;
-            }
-            glfw::WindowEvent::Key(Key::F4, _, Action::Press, _) => {
-                self.show_rays = !self.show_rays;
-            }
-            _ => {}
-        }
-    }
+    fn handle_event(&mut self, _: &mut Glfw, _: &mut glfw::Window, _: &glfw::WindowEvent) {}
 
     fn render(&self, scene: &Scene, _: &Entity, view_projection: &Matrix4<f32>, _: &Matrix4<f32>) {
         if self.show_rays {
@@ -344,14 +346,10 @@ impl Component for DebugController {
                 false,
             );
 
-            for entity in scene.get_entities_with_component::<ModelComponent>() {
-                let rot: Matrix4<f32> = entity.get_rotation().into();
-                let transform = Matrix4::from_translation(entity.get_position().to_vec()) * rot;
-                if let Some(model_component) = entity.get_component::<ModelComponent>() {
-                    model_component
-                        .get_model()
-                        .render_bones(view_projection, &transform);
-                }
+            for (entity, model_component) in scene.query_ref::<(&Entity, &ModelComponent)>() {
+                model_component
+                    .get_model()
+                    .render_bones(view_projection, &entity.get_world_matrix());
             }
 
             // Collider wireframes (toggled separately with F3+C)
@@ -443,3 +441,24 @@ impl Component for DebugController {
         }
     }
 }
+
+impl SerializableComponent for DebugController {
+    const TYPE_NAME: &'static str = "DebugController";
+
+    fn save(&self, _: &Scene) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(DebugControllerData {
+            debug_ui: self.debug_ui,
+            show_rays: self.show_rays,
+            show_colliders: self.show_colliders,
+        })?)
+    }
+
+    fn load(data: Value, _: &mut Scene, _: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        let data: DebugControllerData = serde_json::from_value(data)?;
+        let mut debug_controller = DebugController::new();
+        debug_controller.debug_ui = data.debug_ui;
+        debug_controller.show_rays = data.show_rays;
+        debug_controller.show_colliders = data.show_colliders;
+        Ok(debug_controller)
+    }
+}
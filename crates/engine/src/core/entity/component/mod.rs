@@ -5,10 +5,27 @@ use glfw::{Glfw, Window};
 
 use crate::core::scene::Scene;
 
-use super::Entity;
+use super::{ComponentStore, Entity, EntityHandle};
 
-pub trait Component: AsAny {
+/// Moves a boxed component into its type's `Column`. Implemented for every
+/// `Component`.
+pub trait Storable {
+    fn insert_into(self: Box<Self>, store: &mut ComponentStore, owner: EntityHandle);
+}
+
+impl<T: Component> Storable for T {
+    fn insert_into(self: Box<Self>, store: &mut ComponentStore, owner: EntityHandle) {
+        store.insert(owner, *self);
+    }
+}
+
+pub trait Component: AsAny + Storable {
     fn update(&mut self, scene: &mut Scene, entity: &mut Entity, delta_time: f64);
+    /// Runs once per simulation step, right after physics has advanced by
+    /// `fixed_delta` seconds. Use it for anything that feeds the physics
+    /// world (velocities, impulses) so it behaves the same at any frame
+    /// rate. Runs zero or more times per frame, before `update`.
+    fn fixed_update(&mut self, _scene: &mut Scene, _entity: &mut Entity, _fixed_delta: f64) {}
     fn render(
         &self,
         _scene: &Scene,
@@ -30,6 +47,15 @@ pub trait Component: AsAny {
     ) {
     }
     fn handle_event(&mut self, glfw: &mut Glfw, window: &mut Window, event: &glfw::WindowEvent);
+    /// Setup hook, called once when the component first belongs to an
+    /// entity in the scene: right away in `Scene::add_entity`, otherwise
+    /// at the start of the next `Scene::update` or after commands are
+    /// applied.
+    fn on_added(&mut self, _scene: &mut Scene, _entity: &mut Entity) {}
+    /// Teardown hook, called once when the owning entity is removed from the
+    /// scene. Release anything the component registered outside of itself,
+    /// such as rapier bodies and colliders in `scene.physics_engine`.
+    fn on_removed(&mut self, _scene: &mut Scene, _entity: &mut Entity) {}
 }
 
 pub mod animation_component;
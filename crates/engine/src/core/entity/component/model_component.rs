@@ -1,6 +1,16 @@
+use std::error::Error;
+
 use cgmath::Matrix4;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::core::{entity::Entity, model::Model, renderer::light::skylight, scene::Scene};
+use crate::core::{
+    entity::Entity,
+    model::{Model, ModelBuilder},
+    renderer::light::skylight,
+    scene::Scene,
+    serialization::SerializableComponent,
+};
 
 use super::Component;
 
@@ -8,6 +18,12 @@ pub struct ModelComponent {
     model: Model,
 }
 
+#[derive(Serialize, Deserialize)]
+struct ModelComponentData {
+    path: String,
+    position: [f32; 3],
+}
+
 impl ModelComponent {
     pub fn new(model: Model) -> Self {
         ModelComponent { model }
@@ -40,3 +56,24 @@ impl Component for ModelComponent {
 
     fn handle_event(&mut self, _: &mut glfw::Glfw, _: &mut glfw::Window, _: &glfw::WindowEvent) {}
 }
+
+impl SerializableComponent for ModelComponent {
+    const TYPE_NAME: &'static str = "ModelComponent";
+
+    fn save(&self, _: &Scene) -> Result<Value, Box<dyn Error>> {
+        let position = self.model.position;
+        Ok(serde_json::to_value(ModelComponentData {
+            path: self.model.get_path().to_string(),
+            position: [position.x, position.y, position.z],
+        })?)
+    }
+
+    fn load(data: Value, _: &mut Scene, _: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        let data: ModelComponentData = serde_json::from_value(data)?;
+        let mut model = ModelBuilder::new(&data.path)?
+            .with_position(data.position)
+            .build();
+        model.init();
+        Ok(ModelComponent::new(model))
+    }
+}
@@ -0,0 +1,332 @@
+use std::{any::TypeId, collections::HashMap};
+
+use super::{
+    component::Component, AnyColumn, Column, ComponentState, ComponentStore, Detached, EntityHandle,
+};
+
+impl ComponentStore {
+    pub fn new() -> Self {
+        Self {
+            columns: HashMap::new(),
+            unadded: false,
+        }
+    }
+
+    pub fn get_column<T: Component>(&self) -> Option<&Column<T>> {
+        self.columns
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref::<Column<T>>()
+    }
+
+    pub fn get_column_mut<T: Component>(&mut self) -> Option<&mut Column<T>> {
+        self.columns
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Column<T>>()
+    }
+
+    pub fn get<T: Component>(&self, owner: EntityHandle) -> Option<&T> {
+        self.get_column::<T>()?.get(owner)
+    }
+
+    pub fn get_mut<T: Component>(&mut self, owner: EntityHandle) -> Option<&mut T> {
+        self.get_column_mut::<T>()?.get_mut(owner)
+    }
+
+    /// The first `T` in its column, e.g. the scene's only camera.
+    pub fn first<T: Component>(&self) -> Option<&T> {
+        self.get_column::<T>()?.components.first()
+    }
+
+    pub fn first_mut<T: Component>(&mut self) -> Option<&mut T> {
+        self.get_column_mut::<T>()?.components.first_mut()
+    }
+
+    pub fn contains(&self, type_id: TypeId, owner: EntityHandle) -> bool {
+        self.get_state(type_id, owner).is_some()
+    }
+
+    /// Entities owning a component of the given type, in storage order.
+    pub fn get_owners(&self, type_id: TypeId) -> &[EntityHandle] {
+        self.columns
+            .get(&type_id)
+            .map_or(&[], |column| column.get_owners())
+    }
+
+    pub(crate) fn get_dyn(&self, type_id: TypeId, owner: EntityHandle) -> Option<&dyn Component> {
+        self.columns.get(&type_id)?.get_dyn(owner)
+    }
+
+    pub(crate) fn get_dyn_mut(
+        &mut self,
+        type_id: TypeId,
+        owner: EntityHandle,
+    ) -> Option<&mut dyn Component> {
+        self.columns.get_mut(&type_id)?.get_dyn_mut(owner)
+    }
+
+    pub(crate) fn get_state(&self, type_id: TypeId, owner: EntityHandle) -> Option<ComponentState> {
+        self.columns.get(&type_id)?.get_state(owner).copied()
+    }
+
+    pub(crate) fn get_state_mut(
+        &mut self,
+        type_id: TypeId,
+        owner: EntityHandle,
+    ) -> Option<&mut ComponentState> {
+        self.columns.get_mut(&type_id)?.get_state_mut(owner)
+    }
+
+    /// Stores `component` for `owner`, replacing the `T` it had.
+    pub(crate) fn insert<T: Component>(&mut self, owner: EntityHandle, component: T) {
+        self.columns
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Column::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<Column<T>>()
+            .expect("Column stored under another component type")
+            .insert(owner, component, ComponentState::new());
+        self.unadded = true;
+    }
+
+    pub(crate) fn insert_boxed(
+        &mut self,
+        owner: EntityHandle,
+        component: Box<dyn Component>,
+        state: ComponentState,
+    ) {
+        let type_id = component.as_any().type_id();
+        let unadded = self.unadded;
+        component.insert_into(self, owner);
+        if let Some(stored) = self.get_state_mut(type_id, owner) {
+            *stored = state;
+        }
+        self.unadded = unadded || !state.added;
+    }
+
+    pub(crate) fn remove(
+        &mut self,
+        type_id: TypeId,
+        owner: EntityHandle,
+    ) -> Option<(Box<dyn Component>, ComponentState)> {
+        let (component, state, _) = self.columns.get_mut(&type_id)?.remove_boxed(owner)?;
+        Some((component, state))
+    }
+
+    /// Takes `owner`'s component out of its column, so it can be handed
+    /// the scene and its entity at the same time.
+    pub(crate) fn detach(&mut self, type_id: TypeId, owner: EntityHandle) -> Option<Detached> {
+        let (component, state, index) = self.columns.get_mut(&type_id)?.remove_boxed(owner)?;
+        Some(Detached {
+            component,
+            owner,
+            state,
+            index,
+        })
+    }
+
+    /// Puts a detached component back where it was, unless its column
+    /// shrank in the meantime.
+    pub(crate) fn reattach(&mut self, detached: Detached) {
+        let type_id = detached.component.as_any().type_id();
+        self.insert_boxed(detached.owner, detached.component, detached.state);
+        if let Some(column) = self.columns.get_mut(&type_id) {
+            column.move_to(detached.owner, detached.index);
+        }
+    }
+
+    /// Whether components were stored before their `on_added` ran since
+    /// the last call.
+    pub(crate) fn take_unadded(&mut self) -> bool {
+        std::mem::take(&mut self.unadded)
+    }
+}
+
+impl<T: Component> Column<T> {
+    fn new() -> Self {
+        Self {
+            components: Vec::new(),
+            owners: Vec::new(),
+            states: Vec::new(),
+            sparse: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Owners in storage order, one per component.
+    pub fn get_owners(&self) -> &[EntityHandle] {
+        &self.owners
+    }
+
+    pub(crate) fn index_of(&self, owner: EntityHandle) -> Option<usize> {
+        let index = (*self.sparse.get(owner.get_index() as usize)?)? as usize;
+        (self.owners.get(index) == Some(&owner)).then_some(index)
+    }
+
+    pub fn get(&self, owner: EntityHandle) -> Option<&T> {
+        self.index_of(owner).map(|index| &self.components[index])
+    }
+
+    pub fn get_mut(&mut self, owner: EntityHandle) -> Option<&mut T> {
+        self.index_of(owner)
+            .map(|index| &mut self.components[index])
+    }
+
+    fn insert(&mut self, owner: EntityHandle, component: T, state: ComponentState) {
+        if let Some(index) = self.index_of(owner) {
+            self.components[index] = component;
+            self.states[index] = state;
+            return;
+        }
+        let slot = owner.get_index() as usize;
+        if self.sparse.len() <= slot {
+            self.sparse.resize(slot + 1, None);
+        }
+        self.sparse[slot] = Some(self.components.len() as u32);
+        self.components.push(component);
+        self.owners.push(owner);
+        self.states.push(state);
+    }
+
+    fn swap_remove(&mut self, owner: EntityHandle) -> Option<(T, ComponentState, usize)> {
+        let index = self.index_of(owner)?;
+        self.sparse[owner.get_index() as usize] = None;
+        let component = self.components.swap_remove(index);
+        let state = self.states.swap_remove(index);
+        self.owners.swap_remove(index);
+        if let Some(moved) = self.owners.get(index) {
+            self.sparse[moved.get_index() as usize] = Some(index as u32);
+        }
+        Some((component, state, index))
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.components.swap(a, b);
+        self.states.swap(a, b);
+        self.owners.swap(a, b);
+        self.sparse[self.owners[a].get_index() as usize] = Some(a as u32);
+        self.sparse[self.owners[b].get_index() as usize] = Some(b as u32);
+    }
+}
+
+impl<T: Component> AnyColumn for Column<T> {
+    fn get_owners(&self) -> &[EntityHandle] {
+        &self.owners
+    }
+
+    fn get_dyn(&self, owner: EntityHandle) -> Option<&dyn Component> {
+        self.get(owner).map(|component| component as &dyn Component)
+    }
+
+    fn get_dyn_mut(&mut self, owner: EntityHandle) -> Option<&mut dyn Component> {
+        self.get_mut(owner)
+            .map(|component| component as &mut dyn Component)
+    }
+
+    fn get_state(&self, owner: EntityHandle) -> Option<&ComponentState> {
+        self.index_of(owner).map(|index| &self.states[index])
+    }
+
+    fn get_state_mut(&mut self, owner: EntityHandle) -> Option<&mut ComponentState> {
+        self.index_of(owner).map(|index| &mut self.states[index])
+    }
+
+    fn remove_boxed(
+        &mut self,
+        owner: EntityHandle,
+    ) -> Option<(Box<dyn Component>, ComponentState, usize)> {
+        let (component, state, index) = self.swap_remove(owner)?;
+        Some((Box::new(component), state, index))
+    }
+
+    fn move_to(&mut self, owner: EntityHandle, index: usize) {
+        if let Some(current) = self.index_of(owner) {
+            if index < self.len() {
+                self.swap(current, index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::TypeId;
+
+    use glfw::{Glfw, Window, WindowEvent};
+
+    use crate::core::{
+        entity::{component::Component, ComponentStore, Entity},
+        scene::Scene,
+    };
+
+    struct Health(u32);
+
+    impl Component for Health {
+        fn update(&mut self, _: &mut Scene, _: &mut Entity, _: f64) {}
+        fn handle_event(&mut self, _: &mut Glfw, _: &mut Window, _: &WindowEvent) {}
+    }
+
+    #[test]
+    fn removing_keeps_other_owners_reachable() {
+        let mut scene = Scene::new();
+        let mut store = ComponentStore::new();
+        let owners: Vec<_> = (0..3).map(|_| scene.create_entity("unit").id).collect();
+        for (i, owner) in owners.iter().enumerate() {
+            store.insert(*owner, Health(i as u32));
+        }
+
+        assert!(store.remove(TypeId::of::<Health>(), owners[0]).is_some());
+
+        assert!(store.get::<Health>(owners[0]).is_none());
+        assert_eq!(store.get::<Health>(owners[1]).unwrap().0, 1);
+        assert_eq!(store.get::<Health>(owners[2]).unwrap().0, 2);
+        assert_eq!(store.get_column::<Health>().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn reattached_components_keep_their_position() {
+        let mut scene = Scene::new();
+        let mut store = ComponentStore::new();
+        let owners: Vec<_> = (0..3).map(|_| scene.create_entity("unit").id).collect();
+        for (i, owner) in owners.iter().enumerate() {
+            store.insert(*owner, Health(i as u32));
+        }
+
+        let detached = store.detach(TypeId::of::<Health>(), owners[0]).unwrap();
+        assert!(store.get::<Health>(owners[0]).is_none());
+        store.reattach(detached);
+
+        assert_eq!(store.get_owners(TypeId::of::<Health>()), owners.as_slice());
+        assert_eq!(store.get::<Health>(owners[0]).unwrap().0, 0);
+    }
+
+    #[test]
+    fn removed_entities_leave_the_scene_store() {
+        let mut scene = Scene::new();
+        let mut knight = scene.create_entity("knight");
+        knight.add_component(Health(10));
+        let mut page = scene.create_entity("page");
+        page.add_component(Health(3));
+        let page_id = page.id;
+        knight.add_child(&mut scene, page);
+        let knight = scene.add_entity(knight);
+        let mut rock = scene.create_entity("rock");
+        rock.add_component(Health(1));
+        scene.add_entity(rock);
+
+        scene.remove_entity(&knight);
+
+        let store = scene.get_component_store();
+        assert!(store.get::<Health>(knight).is_none());
+        assert!(store.get::<Health>(page_id).is_none());
+        assert_eq!(scene.query_ref::<&Health>().len(), 1);
+    }
+}
@@ -1,48 +1,221 @@
-use cgmath::{EuclideanSpace, Matrix4, Point3, Quaternion};
+use std::any::TypeId;
 
-use crate::core::{physics::rigidbody::RigidBody, scene::Scene, utils::DataSource};
+use cgmath::{EuclideanSpace, Matrix4, Point3, Quaternion, SquareMatrix, Vector3};
 
-use super::{component::Component, Entity, EntityHandle};
+use crate::core::{
+    allocator::HandleAllocator, physics::rigidbody::RigidBody, scene::Scene,
+    serialization::PrefabLink, utils::DataSource,
+};
+
+use super::{
+    component::Component,
+    query::{self, Access, QueryData, QueryFilter, ReadOnlyQueryData, With},
+    ComponentState, ComponentStorage, ComponentStore, Detached, Entity, EntityHandle, LayerMask,
+    Transform,
+};
 
 impl Entity {
+    /// The entity gets its handle when it is added to a scene or to a
+    /// parent. `Scene::create_entity` assigns one right away.
     pub fn new(name: &str) -> Self {
         Entity {
-            id: EntityHandle::new(),
+            id: EntityHandle::UNASSIGNED,
             name: DataSource::new(name.to_string()),
             children: Vec::new(),
-            components: Vec::new(),
-            position: Point3::new(0.0, 0.0, 0.0),
-            rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            components: ComponentStorage::new(),
+            stored: Vec::new(),
+            attached: false,
+            transform: Transform::new(),
+            parent_matrix: Matrix4::identity(),
+            world_matrix: Matrix4::identity(),
+            children_dirty: false,
+            tags: Vec::new(),
+            layers: LayerMask::DEFAULT,
+            enabled: true,
+            prefab: None,
+            persistent: true,
+            from_prefab: false,
         }
     }
 
     pub fn update(&mut self, scene: &mut Scene, delta_time: f64) {
+        if !self.enabled {
+            return;
+        }
+        self.run_stored(scene, |component, scene, entity| {
+            component.update(scene, entity, delta_time)
+        });
         for i in 0..self.components.len() {
-            let mut component = self.components.remove(i);
+            if !self.components.is_enabled(i) {
+                continue;
+            }
+            let (mut component, state) = self.components.remove_entry(i);
             component.update(scene, self, delta_time);
-            self.components.insert(i, component);
+            self.components.insert_entry(i, component, state);
         }
+        // Components added during the update get their `on_added` once the
+        // scene's update pass has finished.
+        self.store_pending(scene);
 
         for child in self.children.iter_mut() {
             child.update(scene, delta_time);
         }
     }
 
-    pub fn render(
-        &self,
-        scene: &Scene,
-        view_projection: &Matrix4<f32>,
-        parent_transform: Matrix4<f32>,
+    pub fn fixed_update(&mut self, scene: &mut Scene, fixed_delta: f64) {
+        if !self.enabled {
+            return;
+        }
+        self.run_stored(scene, |component, scene, entity| {
+            component.fixed_update(scene, entity, fixed_delta)
+        });
+        for i in 0..self.components.len() {
+            if !self.components.is_enabled(i) {
+                continue;
+            }
+            let (mut component, state) = self.components.remove_entry(i);
+            component.fixed_update(scene, self, fixed_delta);
+            self.components.insert_entry(i, component, state);
+        }
+        self.store_pending(scene);
+
+        for child in self.children.iter_mut() {
+            child.fixed_update(scene, fixed_delta);
+        }
+    }
+
+    /// Runs `f` for each enabled component this entity keeps in the scene's
+    /// store, after storing the ones added since the last pass. A component
+    /// whose `on_added` has not run yet gets it first.
+    fn run_stored(
+        &mut self,
+        scene: &mut Scene,
+        mut f: impl FnMut(&mut dyn Component, &mut Scene, &mut Entity),
     ) {
-        let transform = parent_transform
-            * Matrix4::from_translation(self.position.to_vec())
-            * Matrix4::from(self.rotation);
-        for component in self.components.iter() {
-            component.render(scene, self, view_projection, &transform);
+        self.store_pending(scene);
+        self.for_each_stored(
+            scene,
+            |state| state.enabled || !state.added,
+            |detached, scene, entity| {
+                if !detached.state.added {
+                    detached.state.added = true;
+                    detached.component.on_added(scene, entity);
+                }
+                if detached.state.enabled {
+                    f(detached.component.as_mut(), scene, entity);
+                }
+            },
+        );
+    }
+
+    /// Calls `f` for each of this entity's stored components whose state
+    /// passes `filter`, taking it out of the store meanwhile so `f` can
+    /// borrow the scene and the entity.
+    fn for_each_stored(
+        &mut self,
+        scene: &mut Scene,
+        filter: impl Fn(ComponentState) -> bool,
+        mut f: impl FnMut(&mut Detached, &mut Scene, &mut Entity),
+    ) {
+        let mut i = 0;
+        while let Some(type_id) = self.stored.get(i).copied() {
+            i += 1;
+            let store = scene.get_component_store_mut();
+            if !store.get_state(type_id, self.id).is_some_and(&filter) {
+                continue;
+            }
+            if let Some(mut detached) = store.detach(type_id, self.id) {
+                f(&mut detached, scene, self);
+                scene.get_component_store_mut().reattach(detached);
+            }
+        }
+    }
+
+    /// Moves components added to an entity of the scene since it was last
+    /// stored into the scene's store.
+    pub(crate) fn store_pending(&mut self, scene: &mut Scene) {
+        if !self.attached || self.components.is_empty() {
+            return;
+        }
+        for (component, state) in self.components.drain() {
+            self.stored.push(component.as_any().type_id());
+            scene
+                .get_component_store_mut()
+                .insert_boxed(self.id, component, state);
+        }
+    }
+
+    /// Moves the components of this entity and its descendants into the
+    /// scene's store, once they are part of the scene's tree and have
+    /// handles.
+    pub(crate) fn store_components(&mut self, scene: &mut Scene) {
+        self.attached = true;
+        self.store_pending(scene);
+        for child in self.children.iter_mut() {
+            child.store_components(scene);
+        }
+    }
+
+    /// Takes the components of this entity and its descendants back out
+    /// of the scene's store, e.g. before the entity moves to another scene.
+    pub(crate) fn take_components(&mut self, scene: &mut Scene) {
+        self.unstore(scene);
+        for child in self.children.iter_mut() {
+            child.take_components(scene);
+        }
+    }
+
+    /// Puts this entity's stored components back in front of those not
+    /// stored yet, in the order they were added.
+    fn unstore(&mut self, scene: &mut Scene) {
+        let mut components = ComponentStorage::new();
+        for type_id in self.stored.drain(..) {
+            if let Some((component, state)) =
+                scene.get_component_store_mut().remove(type_id, self.id)
+            {
+                components.insert_entry(components.len(), component, state);
+            }
+        }
+        for (component, state) in self.components.drain() {
+            components.insert_entry(components.len(), component, state);
+        }
+        self.components = components;
+        self.attached = false;
+    }
+
+    /// This entity's own components with their flags, the stored ones
+    /// first.
+    fn own_components<'a>(
+        &'a self,
+        store: &'a ComponentStore,
+    ) -> impl Iterator<Item = (&'a dyn Component, ComponentState)> + 'a {
+        let stored = self.stored.iter().filter_map(move |type_id| {
+            Some((
+                store.get_dyn(*type_id, self.id)?,
+                store.get_state(*type_id, self.id)?,
+            ))
+        });
+        let pending = self
+            .components
+            .iter_with_states()
+            .map(|(component, state)| (component.as_ref(), state));
+        stored.chain(pending)
+    }
+
+    pub fn render(&self, scene: &Scene, view_projection: &Matrix4<f32>, layers: LayerMask) {
+        if !self.enabled {
+            return;
+        }
+        if self.layers.intersects(layers) {
+            for (component, state) in self.own_components(scene.get_component_store()) {
+                if state.enabled {
+                    component.render(scene, self, view_projection, &self.world_matrix);
+                }
+            }
         }
 
         for child in self.children.iter() {
-            child.render(scene, view_projection, transform);
+            child.render(scene, view_projection, layers);
         }
     }
 
@@ -50,24 +223,128 @@ impl Entity {
         &self,
         scene: &Scene,
         view_projection: &Matrix4<f32>,
-        parent_transform: Matrix4<f32>,
+        layers: LayerMask,
     ) {
-        let transform = parent_transform
-            * Matrix4::from_translation(self.position.to_vec())
-            * Matrix4::from(self.rotation);
-        for component in self.components.iter() {
-            component.render_transparent(scene, self, view_projection, &transform);
+        if !self.enabled {
+            return;
+        }
+        if self.layers.intersects(layers) {
+            for (component, state) in self.own_components(scene.get_component_store()) {
+                if state.enabled {
+                    component.render_transparent(scene, self, view_projection, &self.world_matrix);
+                }
+            }
         }
 
         for child in self.children.iter() {
-            child.render_transparent(scene, view_projection, transform);
+            child.render_transparent(scene, view_projection, layers);
+        }
+    }
+
+    /// `child` and its descendants get handles from `scene` where they have
+    /// none yet, and their rigid bodies are moved to their world poses under
+    /// this entity. Its components move into `scene`'s store if this
+    /// entity's are there.
+    pub fn add_child(&mut self, scene: &mut Scene, mut child: Entity) {
+        scene.assign_handles(&mut child);
+        child.set_parent_matrix(self.world_matrix);
+        child.sync_rigid_bodies(scene);
+        if self.attached {
+            child.store_components(scene);
+            scene.mark_changed();
         }
+        self.push_child(child);
     }
 
-    pub fn add_child(&mut self, child: Entity) {
+    /// Adds a child whose parent matrix and bodies are already up to date,
+    /// and whose components are stored if this entity's are.
+    pub(crate) fn push_child(&mut self, child: Entity) {
         self.children.push(child);
     }
 
+    /// Removes the descendant with the given handle, tearing down its whole
+    /// subtree. Returns whether a matching entity was found.
+    pub fn remove_child(&mut self, scene: &mut Scene, id: &EntityHandle) -> bool {
+        match self.take_child(id) {
+            Some(mut child) => {
+                scene.mark_changed();
+                child.teardown(scene);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Detaches the descendant with the given handle, leaving its
+    /// components where they are.
+    pub(crate) fn take_child(&mut self, id: &EntityHandle) -> Option<Entity> {
+        if let Some(index) = self.children.iter().position(|child| child.id == *id) {
+            return Some(self.children.remove(index));
+        }
+        for child in self.children.iter_mut() {
+            if let Some(entity) = child.take_child(id) {
+                return Some(entity);
+            }
+        }
+        None
+    }
+
+    /// Runs `Component::on_removed` for this entity and every descendant,
+    /// children first, and releases their handles. The components are
+    /// taken out of the scene's store and stay with the entity.
+    pub(crate) fn teardown(&mut self, scene: &mut Scene) {
+        for child in self.children.iter_mut() {
+            child.teardown(scene);
+        }
+        self.unstore(scene);
+        let mut components = std::mem::take(&mut self.components);
+        for component in components.iter_mut() {
+            component.on_removed(scene, self);
+        }
+        self.components = components;
+        scene.free_handle(self.id);
+    }
+
+    /// Runs `Component::on_added` for every component of this entity and
+    /// its descendants that has not had it yet.
+    pub(crate) fn run_added_hooks(&mut self, scene: &mut Scene) {
+        self.store_pending(scene);
+        self.for_each_stored(
+            scene,
+            |state| !state.added,
+            |detached, scene, entity| {
+                detached.state.added = true;
+                detached.component.on_added(scene, entity);
+            },
+        );
+        for i in 0..self.components.len() {
+            if self.components.get_state(i).added {
+                continue;
+            }
+            let (mut component, mut state) = self.components.remove_entry(i);
+            state.added = true;
+            component.on_added(scene, self);
+            self.components.insert_entry(i, component, state);
+        }
+        for child in self.children.iter_mut() {
+            child.run_added_hooks(scene);
+        }
+    }
+
+    /// Gives this entity and its descendants handles from `allocator`.
+    /// Handles that are already alive are kept, handles read back from a
+    /// scene file are reserved, and anything else gets a fresh one.
+    pub(crate) fn assign_handles(&mut self, allocator: &mut HandleAllocator) {
+        let id = u64::from(self.id);
+        let keep = self.id.is_assigned() && (allocator.is_alive(id) || allocator.reserve(id));
+        if !keep {
+            self.id = EntityHandle::from(allocator.allocate());
+        }
+        for child in self.children.iter_mut() {
+            child.assign_handles(allocator);
+        }
+    }
+
     pub fn get_child(&self, id: &EntityHandle) -> Option<&Entity> {
         for child in self.children.iter() {
             if child.id == *id {
@@ -84,6 +361,17 @@ impl Entity {
         &self.children
     }
 
+    /// First direct child with the given name.
+    pub fn find_child(&self, name: &str) -> Option<&Entity> {
+        self.children.iter().find(|child| child.name.read() == name)
+    }
+
+    pub fn find_child_mut(&mut self, name: &str) -> Option<&mut Entity> {
+        self.children
+            .iter_mut()
+            .find(|child| child.name.read() == name)
+    }
+
     pub fn get_child_mut(&mut self, id: &EntityHandle) -> Option<&mut Entity> {
         for child in self.children.iter_mut() {
             if child.id == *id {
@@ -96,89 +384,395 @@ impl Entity {
         None
     }
 
-    pub fn handle_event(
+    /// `store` is the scene's, borrowed apart from its entities.
+    pub(crate) fn handle_event(
         &mut self,
+        store: &mut ComponentStore,
         glfw: &mut glfw::Glfw,
         window: &mut glfw::Window,
         event: &glfw::WindowEvent,
     ) {
-        for component in self.components.iter_mut() {
+        if !self.enabled {
+            return;
+        }
+        for type_id in self.stored.iter() {
+            if !store
+                .get_state(*type_id, self.id)
+                .is_some_and(|state| state.enabled)
+            {
+                continue;
+            }
+            if let Some(component) = store.get_dyn_mut(*type_id, self.id) {
+                component.handle_event(glfw, window, event);
+            }
+        }
+        for component in self.components.iter_enabled_mut() {
             component.handle_event(glfw, window, event);
         }
 
         for child in self.children.iter_mut() {
-            child.handle_event(glfw, window, event);
+            child.handle_event(store, glfw, window, event);
         }
     }
 
+    /// Entities hold one component per type; adding a second one is
+    /// ignored with a warning.
     pub fn add_component<T: 'static + Component>(&mut self, component: T) {
-        self.components.push(Box::new(component));
+        self.add_boxed_component(Box::new(component));
+    }
+
+    /// Once the entity is part of a scene, the component moves into the
+    /// scene's store at the entity's next update, or right away when added
+    /// through `Commands::add_component`.
+    pub fn add_boxed_component(&mut self, component: Box<dyn Component>) {
+        let type_id = component.as_any().type_id();
+        if self.stored.contains(&type_id) || self.components.contains(type_id) {
+            log::warn!(
+                "Entity {:?} already has a component of this type, ignoring the new one",
+                self.id
+            );
+            return;
+        }
+        self.components.push(component);
+    }
+
+    /// Detaches the component of the given type. The caller is responsible
+    /// for running its `on_removed` hook.
+    pub(crate) fn take_component(
+        &mut self,
+        scene: &mut Scene,
+        type_id: TypeId,
+    ) -> Option<Box<dyn Component>> {
+        if let Some(index) = self.components.position(type_id) {
+            return Some(self.components.remove(index));
+        }
+        let index = self.stored.iter().position(|t| *t == type_id)?;
+        self.stored.remove(index);
+        scene
+            .get_component_store_mut()
+            .remove(type_id, self.id)
+            .map(|(component, _)| component)
+    }
+
+    /// Runs `f` with this entity's `T` detached from it, so `f` can borrow
+    /// the scene and the entity as well. Returns `None` if it has no `T`.
+    pub(crate) fn with_component_detached<T: Component, R>(
+        &mut self,
+        scene: &mut Scene,
+        f: impl FnOnce(&mut T, &mut Scene, &mut Entity) -> R,
+    ) -> Option<R> {
+        let type_id = TypeId::of::<T>();
+        if let Some(index) = self.components.position(type_id) {
+            let (mut component, state) = self.components.remove_entry(index);
+            let result = component
+                .as_any_mut()
+                .downcast_mut::<T>()
+                .map(|component| f(component, scene, self));
+            self.components.insert_entry(index, component, state);
+            return result;
+        }
+        if !self.stored.contains(&type_id) {
+            return None;
+        }
+        let mut detached = scene.get_component_store_mut().detach(type_id, self.id)?;
+        let result = detached
+            .component
+            .as_any_mut()
+            .downcast_mut::<T>()
+            .map(|component| f(component, scene, self));
+        scene.get_component_store_mut().reattach(detached);
+        result
+    }
+
+    /// Enables or disables this entity's own `T`. Returns `false` if it has
+    /// none.
+    pub fn set_component_enabled<T: Component>(
+        &mut self,
+        scene: &mut Scene,
+        enabled: bool,
+    ) -> bool {
+        let type_id = TypeId::of::<T>();
+        if let Some(index) = self.components.position(type_id) {
+            self.components.set_enabled(index, enabled);
+            return true;
+        }
+        if !self.stored.contains(&type_id) {
+            return false;
+        }
+        match scene
+            .get_component_store_mut()
+            .get_state_mut(type_id, self.id)
+        {
+            Some(state) => {
+                state.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether this entity has a `T` of its own and it is enabled.
+    pub fn is_component_enabled<T: Component>(&self, scene: &Scene) -> bool {
+        let type_id = TypeId::of::<T>();
+        match self.components.position(type_id) {
+            Some(index) => self.components.is_enabled(index),
+            None => {
+                self.stored.contains(&type_id)
+                    && scene
+                        .get_component_store()
+                        .get_state(type_id, self.id)
+                        .is_some_and(|state| state.enabled)
+            }
+        }
     }
 
-    pub fn get_component<T>(&self) -> Option<&T>
+    /// This entity's own components, in the order they were added.
+    pub fn get_components<'a>(&'a self, scene: &'a Scene) -> Vec<&'a dyn Component> {
+        self.own_components(scene.get_component_store())
+            .map(|(component, _)| component)
+            .collect()
+    }
+
+    /// Like `get_components`, with the flags the serializer needs.
+    pub(crate) fn iter_components<'a>(
+        &'a self,
+        scene: &'a Scene,
+    ) -> impl Iterator<Item = (&'a dyn Component, ComponentState)> + 'a {
+        self.own_components(scene.get_component_store())
+    }
+
+    pub fn get_component_types(&self) -> Vec<TypeId> {
+        self.stored
+            .iter()
+            .chain(self.components.types())
+            .copied()
+            .collect()
+    }
+
+    /// This entity's `T`, or else the first one found among its
+    /// descendants.
+    pub fn get_component<'a, T>(&'a self, scene: &'a Scene) -> Option<&'a T>
     where
         T: Component,
     {
-        for component in self.components.iter() {
-            if let Some(component) = component.as_any().downcast_ref::<T>() {
-                return Some(component);
-            }
+        if let Some(component) = self.get_own_component::<T>(scene) {
+            return Some(component);
         }
         for child in self.children.iter() {
-            if let Some(component) = child.get_component::<T>() {
+            if let Some(component) = child.get_component::<T>(scene) {
                 return Some(component);
             }
         }
         None
     }
 
-    pub fn get_with_own_component<T>(&self) -> Vec<&Entity>
+    fn get_own_component<'a, T: Component>(&'a self, scene: &'a Scene) -> Option<&'a T> {
+        match self.components.get::<T>() {
+            Some(component) => Some(component),
+            None => scene.get_component_store().get::<T>(self.id),
+        }
+    }
+
+    pub fn get_with_own_component<'a, T>(&'a self, scene: &'a Scene) -> Vec<&'a Entity>
     where
         T: Component,
     {
-        let mut entities = Vec::new();
-        for component in self.components.iter() {
-            if let Some(_) = component.as_any().downcast_ref::<T>() {
-                entities.push(self);
-            }
-        }
-        for child in self.children.iter() {
-            entities.append(&mut child.get_with_own_component::<T>());
+        self.query_ref_filtered::<&Entity, With<T>>(scene)
+    }
+
+    /// Whether this entity itself (not one of its children) has a `T`.
+    pub fn has_own_component<T>(&self) -> bool
+    where
+        T: Component,
+    {
+        let type_id = TypeId::of::<T>();
+        self.stored.contains(&type_id) || self.components.contains(type_id)
+    }
+
+    /// Fetches `Q` from this entity and all of its descendants, e.g.
+    /// `entity.query::<(&ModelComponent, &mut RigidBody)>(scene)`. Only
+    /// components in the scene's store are visited, so the entity has to
+    /// be part of `scene`. Panics if `Q` borrows the same component
+    /// mutably more than once.
+    pub fn query<'a, Q: QueryData>(&'a self, scene: &'a mut Scene) -> Vec<Q::Item<'a>> {
+        self.query_filtered::<Q, ()>(scene)
+    }
+
+    pub fn query_filtered<'a, Q: QueryData, F: QueryFilter>(
+        &'a self,
+        scene: &'a mut Scene,
+    ) -> Vec<Q::Item<'a>> {
+        Access::validate::<Q>();
+        // SAFETY: the store is borrowed mutably for `'a`, and
+        // `Access::validate` rejected aliasing accesses.
+        unsafe {
+            query::collect_tree::<Q, F>(scene.get_component_store_mut(), std::slice::from_ref(self))
         }
-        entities
     }
 
-    pub fn get_component_mut<T>(&mut self) -> Option<&mut T>
+    pub fn query_ref<'a, Q: ReadOnlyQueryData>(&'a self, scene: &'a Scene) -> Vec<Q::Item<'a>> {
+        self.query_ref_filtered::<Q, ()>(scene)
+    }
+
+    pub fn query_ref_filtered<'a, Q: ReadOnlyQueryData, F: QueryFilter>(
+        &'a self,
+        scene: &'a Scene,
+    ) -> Vec<Q::Item<'a>> {
+        let store = scene.get_component_store() as *const ComponentStore as *mut ComponentStore;
+        // SAFETY: read-only query data never writes to the store.
+        unsafe { query::collect_tree::<Q, F>(store, std::slice::from_ref(self)) }
+    }
+
+    /// This entity's own `T`.
+    pub fn get_component_mut<'a, T>(&'a mut self, scene: &'a mut Scene) -> Option<&'a mut T>
     where
         T: Component,
     {
-        for component in self.components.iter_mut() {
-            if let Some(component) = component.as_any_mut().downcast_mut::<T>() {
-                return Some(component);
-            }
+        if self.components.contains(TypeId::of::<T>()) {
+            return self.components.get_mut::<T>();
         }
-        None
+        scene.get_component_store_mut().get_mut::<T>(self.id)
+    }
+
+    /// Entity at `path`, a list of child indices starting below `self`.
+    pub(crate) fn descendant(&self, path: &[usize]) -> Option<&Entity> {
+        let mut entity = self;
+        for &index in path {
+            entity = entity.children.get(index)?;
+        }
+        Some(entity)
+    }
+
+    pub(crate) fn descendant_mut(&mut self, path: &[usize]) -> Option<&mut Entity> {
+        let mut entity = self;
+        for &index in path {
+            entity = entity.children.get_mut(index)?;
+        }
+        Some(entity)
+    }
+
+    /// Transform relative to the parent entity.
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
     }
 
+    pub fn set_transform(&mut self, scene: &mut Scene, transform: Transform) {
+        self.transform = transform;
+        self.update_world_matrix();
+        self.sync_rigid_body(scene, true, true);
+    }
+
+    /// Local position, relative to the parent entity.
     pub fn get_position(&self) -> Point3<f32> {
-        self.position
+        self.transform.get_position()
     }
 
     pub fn get_rotation(&self) -> Quaternion<f32> {
-        self.rotation
+        self.transform.rotation
+    }
+
+    pub fn get_scale(&self) -> Vector3<f32> {
+        self.transform.scale
     }
 
     pub fn set_position<P: Into<Point3<f32>>>(&mut self, scene: &mut Scene, position: P) {
-        let position = position.into();
-        self.position = position;
-        if let Some(rigid_body) = self.get_component_mut::<RigidBody>() {
-            rigid_body.set_position(scene, position);
-        }
+        self.transform.translation = position.into().to_vec();
+        self.update_world_matrix();
+        self.sync_rigid_body(scene, true, false);
     }
 
     pub fn set_rotation(&mut self, scene: &mut Scene, rotation: Quaternion<f32>) {
-        let _ = scene;
-        self.rotation = rotation;
+        self.transform.rotation = rotation;
+        self.update_world_matrix();
+        self.sync_rigid_body(scene, false, true);
+    }
+
+    /// Scales the entity and, through the world matrix, all its children.
+    /// Rigid bodies are not affected; colliders keep their own size.
+    pub fn set_scale(&mut self, scale: Vector3<f32>) {
+        self.transform.scale = scale;
+        self.update_world_matrix();
+    }
+
+    /// Local-to-world matrix. Up to date for this entity as soon as its
+    /// transform changes; children catch up on the next
+    /// `Scene::propagate_transforms`.
+    pub fn get_world_matrix(&self) -> Matrix4<f32> {
+        self.world_matrix
+    }
+
+    pub fn get_world_transform(&self) -> Transform {
+        Transform::from_matrix(self.world_matrix)
+    }
+
+    pub fn get_world_position(&self) -> Point3<f32> {
+        Point3::from_vec(self.world_matrix.w.truncate())
+    }
+
+    /// Sets the local transform so the entity ends up at `transform` in
+    /// world space under its current parent.
+    pub fn set_world_transform(&mut self, scene: &mut Scene, transform: Transform) {
+        let parent_inverse = self
+            .parent_matrix
+            .invert()
+            .unwrap_or_else(Matrix4::identity);
+        let local = Transform::from_matrix(parent_inverse * transform.get_matrix());
+        self.set_transform(scene, local);
+    }
+
+    /// Turns the entity so its local +Z axis points at `target`, given in
+    /// world space.
+    pub fn look_at(&mut self, scene: &mut Scene, target: Point3<f32>, up: Vector3<f32>) {
+        let mut world = self.get_world_transform();
+        world.look_at(target, up);
+        self.set_world_transform(scene, world);
+    }
+
+    fn update_world_matrix(&mut self) {
+        self.world_matrix = self.parent_matrix * self.transform.get_matrix();
+        self.children_dirty = true;
+    }
+
+    pub(crate) fn set_parent_matrix(&mut self, parent_matrix: Matrix4<f32>) {
+        self.parent_matrix = parent_matrix;
+        self.update_world_matrix();
+    }
+
+    /// Pushes changed world matrices down to the children.
+    pub(crate) fn propagate_transforms(&mut self) {
+        if self.children_dirty {
+            self.children_dirty = false;
+            for child in self.children.iter_mut() {
+                child.set_parent_matrix(self.world_matrix);
+            }
+        }
+        for child in self.children.iter_mut() {
+            child.propagate_transforms();
+        }
+    }
+
+    /// Moves the entity's own rigid body (if any) to its world pose. Only
+    /// the parts that changed are written, so turning an entity does not
+    /// pull its body back to the interpolated render position.
+    fn sync_rigid_body(&mut self, scene: &mut Scene, position: bool, rotation: bool) {
+        if !self.has_own_component::<RigidBody>() {
+            return;
+        }
+        let world = self.get_world_transform();
+        self.with_component_detached::<RigidBody, _>(scene, |rigid_body, scene, _| {
+            rigid_body.move_to(scene, &world, position, rotation)
+        });
+    }
+
+    /// `sync_rigid_body` for the entity and its descendants, whose world
+    /// matrices are brought up to date on the way down.
+    pub(crate) fn sync_rigid_bodies(&mut self, scene: &mut Scene) {
+        self.sync_rigid_body(scene, true, true);
+        let world_matrix = self.world_matrix;
+        for child in self.children.iter_mut() {
+            child.set_parent_matrix(world_matrix);
+            child.sync_rigid_bodies(scene);
+        }
     }
 
     pub fn child_count(&self) -> usize {
@@ -196,4 +790,99 @@ impl Entity {
     pub fn get_name_ref(&self) -> DataSource<String> {
         self.name.clone()
     }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabling an entity also stops its children from updating,
+    /// rendering and receiving events.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.binary_search_by(|t| t.as_str().cmp(tag)).is_ok()
+    }
+
+    /// Returns false if the entity already had the tag.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        match self.tags.binary_search_by(|t| t.as_str().cmp(tag)) {
+            Ok(_) => false,
+            Err(index) => {
+                self.tags.insert(index, tag.to_string());
+                true
+            }
+        }
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        match self.tags.binary_search_by(|t| t.as_str().cmp(tag)) {
+            Ok(index) => {
+                self.tags.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn get_layers(&self) -> LayerMask {
+        self.layers
+    }
+
+    /// Layers are not inherited: children keep their own. The colliders of
+    /// the entity's `RigidBody` are moved to the new layers as well.
+    pub fn set_layers(&mut self, scene: &mut Scene, layers: LayerMask) {
+        self.layers = layers;
+        let body = self
+            .get_component::<RigidBody>(scene)
+            .map(|rigid_body| rigid_body.rigid_body_handle);
+        if let Some(body) = body {
+            scene.physics_engine.set_body_layers(body, layers);
+        }
+    }
+
+    pub fn get_prefab(&self) -> Option<&PrefabLink> {
+        self.prefab.as_ref()
+    }
+
+    /// Links the entity to a prefab file, or unlinks it with `None` so it
+    /// is saved with its full contents again.
+    pub fn set_prefab(&mut self, prefab: Option<PrefabLink>) {
+        self.prefab = prefab;
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        self.persistent
+    }
+
+    pub fn set_persistent(&mut self, persistent: bool) {
+        self.persistent = persistent;
+    }
+
+    /// Whether the entity was built from the prefab of an instance above
+    /// it, rather than added to that instance.
+    pub fn is_from_prefab(&self) -> bool {
+        self.from_prefab
+    }
+
+    /// Flags the components and descendants of a freshly built prefab
+    /// instance, so it can be saved as its link plus what was added later.
+    pub(crate) fn mark_prefab_contents(&mut self) {
+        self.components.mark_from_prefab();
+        for child in self.children.iter_mut() {
+            child.mark_from_prefab();
+        }
+    }
+
+    fn mark_from_prefab(&mut self) {
+        self.from_prefab = true;
+        for child in self.children.iter_mut() {
+            child.mark_from_prefab();
+        }
+    }
 }
@@ -1,15 +1,27 @@
-use rand::prelude::*;
+use crate::core::allocator::HandleAllocator;
 
 use super::EntityHandle;
 
 impl EntityHandle {
-    pub fn new() -> Self {
-        EntityHandle(rand::rng().random::<u64>())
-    }
+    /// Handle of an entity that has not been added to a scene yet. The
+    /// scene replaces it with an allocated one when the entity is attached.
+    pub const UNASSIGNED: EntityHandle = EntityHandle(0);
 
     pub fn from(id: u64) -> Self {
         EntityHandle(id)
     }
+
+    pub fn is_assigned(&self) -> bool {
+        self.get_generation() != 0
+    }
+
+    pub fn get_index(&self) -> u32 {
+        HandleAllocator::index_of(self.0)
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        HandleAllocator::generation_of(self.0)
+    }
 }
 
 impl From<EntityHandle> for u64 {
@@ -0,0 +1,86 @@
+use std::ops::{BitAnd, BitOr, Not};
+
+use super::LayerMask;
+
+impl LayerMask {
+    pub const NONE: LayerMask = LayerMask(0);
+    pub const ALL: LayerMask = LayerMask(u32::MAX);
+    /// Layer of every new entity.
+    pub const DEFAULT: LayerMask = LayerMask(1 << 0);
+    /// Debug geometry, left out of the shadow pass by default.
+    pub const DEBUG: LayerMask = LayerMask(1 << 1);
+    pub const PLAYER: LayerMask = LayerMask(1 << 2);
+    pub const TERRAIN: LayerMask = LayerMask(1 << 3);
+
+    pub const fn from_bits(bits: u32) -> Self {
+        LayerMask(bits)
+    }
+
+    /// Mask with only layer `index` (0 to 31) set.
+    pub const fn layer(index: u32) -> Self {
+        LayerMask(1 << index)
+    }
+
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub const fn contains(&self, other: LayerMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn intersects(&self, other: LayerMask) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub const fn with(self, other: LayerMask) -> Self {
+        LayerMask(self.0 | other.0)
+    }
+
+    pub const fn without(self, other: LayerMask) -> Self {
+        LayerMask(self.0 & !other.0)
+    }
+
+    /// Colliders carry their entity's layers in `user_data`; 0 (the rapier
+    /// default) stands for `DEFAULT`.
+    pub fn from_user_data(user_data: u128) -> Self {
+        match user_data as u32 {
+            0 => LayerMask::DEFAULT,
+            bits => LayerMask(bits),
+        }
+    }
+
+    pub fn to_user_data(&self) -> u128 {
+        self.0 as u128
+    }
+}
+
+impl Default for LayerMask {
+    fn default() -> Self {
+        LayerMask::DEFAULT
+    }
+}
+
+impl BitOr for LayerMask {
+    type Output = LayerMask;
+
+    fn bitor(self, other: LayerMask) -> LayerMask {
+        self.with(other)
+    }
+}
+
+impl BitAnd for LayerMask {
+    type Output = LayerMask;
+
+    fn bitand(self, other: LayerMask) -> LayerMask {
+        LayerMask(self.0 & other.0)
+    }
+}
+
+impl Not for LayerMask {
+    type Output = LayerMask;
+
+    fn not(self) -> LayerMask {
+        LayerMask(!self.0)
+    }
+}
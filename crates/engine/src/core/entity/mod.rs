@@ -1,20 +1,147 @@
-use cgmath::{Point3, Quaternion};
+use std::{any::TypeId, collections::HashMap};
+
+use as_any::AsAny;
+use cgmath::{Matrix4, Quaternion, Vector3};
 use component::Component;
 
-use super::utils::DataSource;
+use super::{serialization::PrefabLink, utils::DataSource};
 
 pub mod component;
+mod component_store;
 mod entity;
 mod entity_handle;
+mod layer;
+pub mod query;
+mod storage;
+mod transform;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Generational index handed out by the owning `Scene`. A handle whose
+/// entity was despawned stops resolving, even after its slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct EntityHandle(u64);
 
+/// Set of up to 32 layers an entity belongs to. Rendering and physics
+/// queries take a mask of the layers they include.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayerMask(u32);
+
+/// Components of an entity that is not part of a scene, in insertion
+/// order, with their `TypeId`s kept in a parallel array so type lookups
+/// compare ids instead of downcasting every component through `as_any`.
+/// They move into the scene's `ComponentStore` when the entity is added.
+#[derive(Default)]
+pub struct ComponentStorage {
+    types: Vec<TypeId>,
+    components: Vec<Box<dyn Component>>,
+    states: Vec<ComponentState>,
+}
+
+/// Per-component flags, kept next to the component in `ComponentStorage`
+/// and `Column`.
+#[derive(Clone, Copy)]
+pub(crate) struct ComponentState {
+    /// Disabled components are skipped by `update`, `render` and
+    /// `handle_event`.
+    pub enabled: bool,
+    /// Whether `Component::on_added` has run.
+    pub added: bool,
+    /// Built from a prefab, so saving the instance leaves it to the prefab.
+    pub from_prefab: bool,
+}
+
+/// Every component of a scene's entities, in one densely packed `Column`
+/// per component type. Iterating a type walks a contiguous array, and an
+/// entity's component is found in its column with two array lookups.
+pub struct ComponentStore {
+    columns: HashMap<TypeId, Box<dyn AnyColumn>>,
+    /// Set when a component is stored before its `on_added` has run, so
+    /// `Scene` knows to look for it.
+    unadded: bool,
+}
+
+/// Sparse set holding a `ComponentStore`'s `T`s. `components`, `owners`
+/// and `states` are parallel arrays without gaps; removing a component
+/// moves the last one into its place.
+pub struct Column<T> {
+    components: Vec<T>,
+    owners: Vec<EntityHandle>,
+    states: Vec<ComponentState>,
+    /// Position in `components` of each owner's `T`, by
+    /// `EntityHandle::get_index`.
+    sparse: Vec<Option<u32>>,
+}
+
+/// `Column` with its component type erased.
+pub(crate) trait AnyColumn: AsAny {
+    fn get_owners(&self) -> &[EntityHandle];
+    fn get_dyn(&self, owner: EntityHandle) -> Option<&dyn Component>;
+    fn get_dyn_mut(&mut self, owner: EntityHandle) -> Option<&mut dyn Component>;
+    fn get_state(&self, owner: EntityHandle) -> Option<&ComponentState>;
+    fn get_state_mut(&mut self, owner: EntityHandle) -> Option<&mut ComponentState>;
+    /// Removes `owner`'s component, returning it boxed with its flags and
+    /// the position it was stored at.
+    fn remove_boxed(
+        &mut self,
+        owner: EntityHandle,
+    ) -> Option<(Box<dyn Component>, ComponentState, usize)>;
+    /// Moves `owner`'s component to `index`, swapping it with the one
+    /// there.
+    fn move_to(&mut self, owner: EntityHandle, index: usize);
+}
+
+/// A component taken out of its `Column` while it runs a hook that needs
+/// the scene, put back by `ComponentStore::reattach`.
+pub(crate) struct Detached {
+    component: Box<dyn Component>,
+    owner: EntityHandle,
+    state: ComponentState,
+    index: usize,
+}
+
+/// Translation, rotation and non-uniform scale relative to the parent,
+/// applied in that order (`T * R * S`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
 pub struct Entity {
     pub id: EntityHandle,
     name: DataSource<String>,
     children: Vec<Entity>,
-    components: Vec<Box<dyn Component>>,
-    position: Point3<f32>,
-    rotation: Quaternion<f32>,
+    /// The components themselves while the entity is not part of a scene,
+    /// and any added to it since it was last stored.
+    components: ComponentStorage,
+    /// Types of the components kept for it in the scene's
+    /// `ComponentStore`, in the order they were added.
+    stored: Vec<TypeId>,
+    /// Part of a scene's entity tree, so its components belong in that
+    /// scene's store. Stays set while the entity is detached to update.
+    attached: bool,
+    transform: Transform,
+    /// World matrix of the parent (identity for top-level entities).
+    parent_matrix: Matrix4<f32>,
+    /// Cached `parent_matrix * transform`, refreshed whenever either changes.
+    world_matrix: Matrix4<f32>,
+    /// Set when `world_matrix` changed and the children have not picked up
+    /// the new parent matrix yet (see `Scene::propagate_transforms`).
+    children_dirty: bool,
+    /// Sorted, without duplicates.
+    tags: Vec<String>,
+    layers: LayerMask,
+    /// A disabled entity is skipped, with all of its children, by `update`,
+    /// `render` and `handle_event`.
+    enabled: bool,
+    /// The prefab this entity was instantiated from. `Scene::save` writes
+    /// the link and transform for it and what was added to it since, not
+    /// the prefab's contents.
+    prefab: Option<PrefabLink>,
+    /// Whether `Scene::save` writes this entity (and its children) to disk.
+    /// Runtime-generated entities such as terrain chunks opt out.
+    persistent: bool,
+    /// Built from the contents of the prefab instance above it. Saving that
+    /// instance writes only the children added to it afterwards.
+    from_prefab: bool,
 }
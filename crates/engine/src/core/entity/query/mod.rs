@@ -0,0 +1,96 @@
+use std::{any::TypeId, collections::HashMap, marker::PhantomData};
+
+use super::{ComponentStore, Entity, EntityHandle};
+
+mod query;
+
+pub(crate) use query::{collect, collect_enabled, collect_tree};
+
+/// Components a query borrows, collected through `QueryData::access` so
+/// queries that would hand out aliasing references (e.g. `(&mut A, &A)`)
+/// are rejected before anything is fetched.
+#[derive(Default)]
+pub struct Access {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+    entity: bool,
+}
+
+/// What a query reads: a scene's `ComponentStore`, and how to find the
+/// entities owning its components for `&Entity` items.
+pub struct QuerySource<'a> {
+    store: *mut ComponentStore,
+    lookup: EntityLookup<'a>,
+}
+
+enum EntityLookup<'a> {
+    /// The query walks the entity trees and passes each entity to `fetch`.
+    Walk,
+    /// Child indices from the top-level entities, kept by the scene's index.
+    Paths(&'a [Entity], &'a HashMap<EntityHandle, Vec<usize>>),
+    Map(HashMap<EntityHandle, &'a Entity>),
+}
+
+/// Fetch state of `&mut T`. Reads the column's arrays through raw pointers
+/// taken once, so items for different owners can be handed out together.
+pub struct ColumnMut<'a, T> {
+    components: *mut T,
+    owners: *const EntityHandle,
+    sparse: *const Option<u32>,
+    sparse_len: usize,
+    marker: PhantomData<&'a mut T>,
+}
+
+/// Something that can be fetched for a single entity: `&T`, `&mut T`,
+/// `&Entity`, `EntityHandle`, or a tuple of those.
+///
+/// # Safety
+/// `access` must declare every component `fetch` borrows, and `fetch` may
+/// only borrow components owned by `owner` itself (not its children).
+pub unsafe trait QueryData {
+    type Item<'a>;
+    /// What `fetch` reads from, such as a column, looked up once per query.
+    type Fetch<'a>;
+
+    fn access(access: &mut Access);
+
+    /// `None` if nothing can match, e.g. because no entity owns a
+    /// component that is required.
+    ///
+    /// # Safety
+    /// `source` must stay valid while the fetch state is used, and no live
+    /// reference may alias the columns declared by `access`.
+    unsafe fn prepare<'a>(source: &QuerySource<'a>) -> Option<Self::Fetch<'a>>;
+
+    /// `entity` is the owner itself when the query walks the tree.
+    ///
+    /// # Safety
+    /// `fetch` must come from `prepare`, and each owner may only be fetched
+    /// once per `prepare`.
+    unsafe fn fetch<'a>(
+        fetch: &Self::Fetch<'a>,
+        owner: EntityHandle,
+        entity: Option<&'a Entity>,
+    ) -> Option<Self::Item<'a>>;
+}
+
+/// Query data that only reads, so it can run through `&Scene`/`&Entity`.
+///
+/// # Safety
+/// `prepare` and `fetch` must not write to the store.
+pub unsafe trait ReadOnlyQueryData: QueryData {}
+
+/// Narrows a query by components it does not fetch.
+pub trait QueryFilter {
+    fn matches(store: &ComponentStore, owner: EntityHandle) -> bool;
+
+    /// Components the filter requires, used to pick the column a query
+    /// iterates.
+    fn access(_access: &mut Access) {}
+}
+
+/// Only entities that own a `T`.
+pub struct With<T>(PhantomData<T>);
+
+/// Only entities that do not own a `T`.
+pub struct Without<T>(PhantomData<T>);
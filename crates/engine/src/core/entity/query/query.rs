@@ -0,0 +1,484 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    marker::PhantomData,
+};
+
+use crate::core::entity::{component::Component, Column, ComponentStore, Entity, EntityHandle};
+
+use super::{
+    Access, ColumnMut, EntityLookup, QueryData, QueryFilter, QuerySource, ReadOnlyQueryData, With,
+    Without,
+};
+
+impl Access {
+    pub fn read(&mut self, type_id: TypeId) {
+        self.reads.push(type_id);
+    }
+
+    pub fn write(&mut self, type_id: TypeId) {
+        self.writes.push(type_id);
+    }
+
+    /// Borrows the whole entity, which conflicts with any mutable access.
+    pub fn read_entity(&mut self) {
+        self.entity = true;
+    }
+
+    /// Every component type that has to be present for the query to match.
+    pub(crate) fn component_types(&self) -> impl Iterator<Item = &TypeId> {
+        self.reads.iter().chain(self.writes.iter())
+    }
+
+    /// Whether running alongside `other` could alias: one side writes a
+    /// component the other touches, or borrows whole entities the other
+    /// writes into.
+    pub(crate) fn conflicts_with(&self, other: &Access) -> bool {
+        let writes_into = |a: &Access, b: &Access| {
+            a.writes
+                .iter()
+                .any(|write| b.reads.contains(write) || b.writes.contains(write))
+                || (b.entity && !a.writes.is_empty())
+        };
+        writes_into(self, other) || writes_into(other, self)
+    }
+
+    /// Panics if the accesses declared by `Q` would alias.
+    pub(crate) fn validate<Q: QueryData>() {
+        let mut access = Access::default();
+        Q::access(&mut access);
+
+        if access.entity && !access.writes.is_empty() {
+            panic!(
+                "Query {} borrows whole entities alongside mutable components",
+                type_name::<Q>()
+            );
+        }
+        for (i, write) in access.writes.iter().enumerate() {
+            if access.reads.contains(write) || access.writes[i + 1..].contains(write) {
+                panic!(
+                    "Query {} borrows a component mutably more than once",
+                    type_name::<Q>()
+                );
+            }
+        }
+    }
+}
+
+impl<'a> QuerySource<'a> {
+    /// The entity owning `owner`'s components, found through the lookup.
+    fn locate(&self, owner: EntityHandle) -> Option<&'a Entity> {
+        match &self.lookup {
+            EntityLookup::Walk => None,
+            EntityLookup::Paths(entities, paths) => {
+                let (first, rest) = paths.get(&owner)?.split_first()?;
+                entities
+                    .get(*first)?
+                    .descendant(rest)
+                    .filter(|entity| entity.id == owner)
+            }
+            EntityLookup::Map(entities) => entities.get(&owner).copied(),
+        }
+    }
+}
+
+unsafe impl<T: Component> QueryData for &T {
+    type Item<'a> = &'a T;
+    type Fetch<'a> = &'a Column<T>;
+
+    fn access(access: &mut Access) {
+        access.read(TypeId::of::<T>());
+    }
+
+    unsafe fn prepare<'a>(source: &QuerySource<'a>) -> Option<&'a Column<T>> {
+        (*source.store).get_column::<T>()
+    }
+
+    unsafe fn fetch<'a>(
+        column: &&'a Column<T>,
+        owner: EntityHandle,
+        _: Option<&'a Entity>,
+    ) -> Option<&'a T> {
+        column.get(owner)
+    }
+}
+
+unsafe impl<T: Component> ReadOnlyQueryData for &T {}
+
+unsafe impl<T: Component> QueryData for &mut T {
+    type Item<'a> = &'a mut T;
+    type Fetch<'a> = ColumnMut<'a, T>;
+
+    fn access(access: &mut Access) {
+        access.write(TypeId::of::<T>());
+    }
+
+    unsafe fn prepare<'a>(source: &QuerySource<'a>) -> Option<ColumnMut<'a, T>> {
+        let column = (*source.store).get_column_mut::<T>()?;
+        Some(ColumnMut {
+            components: column.components.as_mut_ptr(),
+            owners: column.owners.as_ptr(),
+            sparse: column.sparse.as_ptr(),
+            sparse_len: column.sparse.len(),
+            marker: PhantomData,
+        })
+    }
+
+    unsafe fn fetch<'a>(
+        column: &ColumnMut<'a, T>,
+        owner: EntityHandle,
+        _: Option<&'a Entity>,
+    ) -> Option<&'a mut T> {
+        // Same lookup as `Column::index_of`, without borrowing the column.
+        let slot = owner.get_index() as usize;
+        if slot >= column.sparse_len {
+            return None;
+        }
+        let index = (*column.sparse.add(slot))? as usize;
+        (*column.owners.add(index) == owner).then(|| &mut *column.components.add(index))
+    }
+}
+
+unsafe impl QueryData for &Entity {
+    type Item<'a> = &'a Entity;
+    type Fetch<'a> = *const QuerySource<'a>;
+
+    fn access(access: &mut Access) {
+        access.read_entity();
+    }
+
+    unsafe fn prepare<'a>(source: &QuerySource<'a>) -> Option<*const QuerySource<'a>> {
+        Some(source)
+    }
+
+    unsafe fn fetch<'a>(
+        source: &*const QuerySource<'a>,
+        owner: EntityHandle,
+        entity: Option<&'a Entity>,
+    ) -> Option<&'a Entity> {
+        entity.or_else(|| (**source).locate(owner))
+    }
+}
+
+unsafe impl ReadOnlyQueryData for &Entity {}
+
+unsafe impl QueryData for EntityHandle {
+    type Item<'a> = EntityHandle;
+    type Fetch<'a> = ();
+
+    fn access(_: &mut Access) {}
+
+    unsafe fn prepare(_: &QuerySource) -> Option<()> {
+        Some(())
+    }
+
+    unsafe fn fetch(_: &(), owner: EntityHandle, _: Option<&Entity>) -> Option<EntityHandle> {
+        Some(owner)
+    }
+}
+
+unsafe impl ReadOnlyQueryData for EntityHandle {}
+
+impl<T: Component> QueryFilter for With<T> {
+    fn matches(store: &ComponentStore, owner: EntityHandle) -> bool {
+        store.contains(TypeId::of::<T>(), owner)
+    }
+
+    fn access(access: &mut Access) {
+        access.read(TypeId::of::<T>());
+    }
+}
+
+impl<T: Component> QueryFilter for Without<T> {
+    fn matches(store: &ComponentStore, owner: EntityHandle) -> bool {
+        !store.contains(TypeId::of::<T>(), owner)
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        unsafe impl<$($name: QueryData),*> QueryData for ($($name,)*) {
+            type Item<'a> = ($($name::Item<'a>,)*);
+            type Fetch<'a> = ($($name::Fetch<'a>,)*);
+
+            fn access(_access: &mut Access) {
+                $($name::access(_access);)*
+            }
+
+            unsafe fn prepare<'a>(_source: &QuerySource<'a>) -> Option<Self::Fetch<'a>> {
+                Some(($($name::prepare(_source)?,)*))
+            }
+
+            unsafe fn fetch<'a>(
+                _fetch: &Self::Fetch<'a>,
+                _owner: EntityHandle,
+                _entity: Option<&'a Entity>,
+            ) -> Option<Self::Item<'a>> {
+                let ($($name,)*) = _fetch;
+                Some(($($name::fetch($name, _owner, _entity)?,)*))
+            }
+        }
+
+        unsafe impl<$($name: ReadOnlyQueryData),*> ReadOnlyQueryData for ($($name,)*) {}
+
+        impl<$($name: QueryFilter),*> QueryFilter for ($($name,)*) {
+            fn matches(_store: &ComponentStore, _owner: EntityHandle) -> bool {
+                true $(&& $name::matches(_store, _owner))*
+            }
+
+            fn access(_access: &mut Access) {
+                $(<$name as QueryFilter>::access(_access);)*
+            }
+        }
+    };
+}
+
+impl_query_tuple!();
+impl_query_tuple!(A);
+impl_query_tuple!(A, B);
+impl_query_tuple!(A, B, C);
+impl_query_tuple!(A, B, C, D);
+impl_query_tuple!(A, B, C, D, E);
+impl_query_tuple!(A, B, C, D, E, F);
+
+/// Fetches `Q` for the owners of the component type in `Q` and `F` with
+/// the fewest owners, in storage order. Queries that require no component
+/// walk `entities` instead. `&Entity` items are found through `paths`, the
+/// scene index, when it is fresh.
+///
+/// # Safety
+/// `store` must hold the components of `entities` and outlive `'a`, and
+/// nothing may borrow the columns `Q` writes to while the items live.
+pub(crate) unsafe fn collect<'a, Q: QueryData, F: QueryFilter>(
+    store: *mut ComponentStore,
+    entities: &'a [Entity],
+    paths: Option<&'a HashMap<EntityHandle, Vec<usize>>>,
+) -> Vec<Q::Item<'a>> {
+    let mut access = Access::default();
+    Q::access(&mut access);
+    F::access(&mut access);
+    let Some(driver) = access
+        .component_types()
+        .min_by_key(|type_id| (*store).get_owners(**type_id).len())
+        .copied()
+    else {
+        return collect_tree::<Q, F>(store, entities);
+    };
+
+    let lookup = match (access.entity, paths) {
+        (false, _) => EntityLookup::Walk,
+        (true, Some(paths)) => EntityLookup::Paths(entities, paths),
+        (true, None) => {
+            let mut map = HashMap::new();
+            for entity in entities {
+                visit(entity, &mut |entity| {
+                    map.insert(entity.id, entity);
+                });
+            }
+            EntityLookup::Map(map)
+        }
+    };
+    let source = QuerySource { store, lookup };
+    // Owners are read before `prepare` takes pointers into the columns.
+    let owners: Vec<EntityHandle> = (*store)
+        .get_owners(driver)
+        .iter()
+        .copied()
+        .filter(|owner| F::matches(&*store, *owner))
+        .collect();
+    let Some(fetch) = Q::prepare(&source) else {
+        return Vec::new();
+    };
+    owners
+        .into_iter()
+        .filter_map(|owner| Q::fetch(&fetch, owner, None))
+        .collect()
+}
+
+/// Fetches `Q` for `entities` and their descendants, depth first.
+///
+/// # Safety
+/// As for `collect`.
+pub(crate) unsafe fn collect_tree<'a, Q: QueryData, F: QueryFilter>(
+    store: *mut ComponentStore,
+    entities: &'a [Entity],
+) -> Vec<Q::Item<'a>> {
+    let mut items = Vec::new();
+    let source = QuerySource {
+        store,
+        lookup: EntityLookup::Walk,
+    };
+    if let Some(fetch) = Q::prepare(&source) {
+        for entity in entities {
+            visit(entity, &mut |entity| {
+                if F::matches(&*store, entity.id) {
+                    items.extend(Q::fetch(&fetch, entity.id, Some(entity)));
+                }
+            });
+        }
+    }
+    items
+}
+
+/// Like `collect_tree`, skipping disabled entities (with their children)
+/// and entities on which a component in `access` is disabled.
+///
+/// # Safety
+/// As for `collect`.
+pub(crate) unsafe fn collect_enabled<'a, Q: QueryData, F: QueryFilter>(
+    store: *mut ComponentStore,
+    entities: &'a [Entity],
+    access: &Access,
+) -> Vec<Q::Item<'a>> {
+    fn visit_enabled<'a>(entity: &'a Entity, f: &mut impl FnMut(&'a Entity)) {
+        if entity.is_enabled() {
+            f(entity);
+            for child in entity.children.iter() {
+                visit_enabled(child, f);
+            }
+        }
+    }
+
+    let mut items = Vec::new();
+    let source = QuerySource {
+        store,
+        lookup: EntityLookup::Walk,
+    };
+    if let Some(fetch) = Q::prepare(&source) {
+        for entity in entities {
+            visit_enabled(entity, &mut |entity| {
+                let store = &*store;
+                let enabled = access.component_types().all(|type_id| {
+                    store
+                        .get_state(*type_id, entity.id)
+                        .is_none_or(|state| state.enabled)
+                });
+                if enabled && F::matches(store, entity.id) {
+                    items.extend(Q::fetch(&fetch, entity.id, Some(entity)));
+                }
+            });
+        }
+    }
+    items
+}
+
+/// Calls `f` for `entity` and its descendants, depth first.
+fn visit<'a>(entity: &'a Entity, f: &mut impl FnMut(&'a Entity)) {
+    f(entity);
+    for child in entity.children.iter() {
+        visit(child, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::{Glfw, Window, WindowEvent};
+
+    use crate::core::{
+        entity::{
+            component::Component,
+            query::{With, Without},
+            Entity, EntityHandle,
+        },
+        scene::Scene,
+    };
+
+    struct Health(u32);
+    struct Armor(u32);
+
+    impl Component for Health {
+        fn update(&mut self, _: &mut Scene, _: &mut Entity, _: f64) {}
+        fn handle_event(&mut self, _: &mut Glfw, _: &mut Window, _: &WindowEvent) {}
+    }
+
+    impl Component for Armor {
+        fn update(&mut self, _: &mut Scene, _: &mut Entity, _: f64) {}
+        fn handle_event(&mut self, _: &mut Glfw, _: &mut Window, _: &WindowEvent) {}
+    }
+
+    /// A knight with health and armor, carrying a page with health only.
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        let mut knight = Entity::new("knight");
+        knight.add_component(Health(10));
+        knight.add_component(Armor(5));
+        let mut page = Entity::new("page");
+        page.add_component(Health(3));
+        knight.add_child(&mut scene, page);
+        scene.add_entity(knight);
+        scene.add_entity(Entity::new("rock"));
+        scene
+    }
+
+    #[test]
+    fn query_visits_nested_children() {
+        let mut scene = scene();
+        let mut health: Vec<u32> = scene
+            .query_ref::<&Health>()
+            .iter()
+            .map(|health| health.0)
+            .collect();
+        health.sort();
+        assert_eq!(health, [3, 10]);
+
+        for health in scene.query::<&mut Health>() {
+            health.0 += 1;
+        }
+        let total: u32 = scene.query_ref::<&Health>().iter().map(|h| h.0).sum();
+        assert_eq!(total, 15);
+    }
+
+    #[test]
+    fn tuples_need_every_component() {
+        let mut scene = scene();
+        let items = scene.query::<(&Health, &mut Armor)>();
+        assert_eq!(items.len(), 1);
+        assert_eq!((items[0].0 .0, items[0].1 .0), (10, 5));
+    }
+
+    #[test]
+    fn filters_narrow_the_matches() {
+        let scene = scene();
+        let names = |entities: Vec<&Entity>| {
+            let mut names: Vec<String> = entities.iter().map(|e| e.get_name()).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            names(scene.query_ref_filtered::<&Entity, With<Health>>()),
+            ["knight", "page"]
+        );
+        assert_eq!(
+            names(scene.query_ref_filtered::<&Entity, (With<Health>, Without<Armor>)>()),
+            ["page"]
+        );
+        assert_eq!(
+            names(scene.query_ref_filtered::<&Entity, Without<Health>>()),
+            ["rock"]
+        );
+    }
+
+    #[test]
+    fn handles_come_with_the_items() {
+        let scene = scene();
+        let page = scene
+            .query_ref::<&Entity>()
+            .into_iter()
+            .find(|entity| entity.get_name() == "page")
+            .unwrap()
+            .id;
+        let handles: Vec<EntityHandle> = scene
+            .query_ref_filtered::<EntityHandle, (With<Health>, Without<Armor>)>()
+            .into_iter()
+            .collect();
+        assert_eq!(handles, [page]);
+    }
+
+    #[test]
+    #[should_panic(expected = "mutably more than once")]
+    fn aliasing_queries_panic() {
+        let mut scene = scene();
+        scene.query::<(&mut Health, &Health)>();
+    }
+}
@@ -0,0 +1,142 @@
+use std::any::TypeId;
+
+use super::{component::Component, ComponentState, ComponentStorage};
+
+impl ComponentStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, component: Box<dyn Component>) {
+        self.types.push(component.as_any().type_id());
+        self.components.push(component);
+        self.states.push(ComponentState::new());
+    }
+
+    pub fn insert(&mut self, index: usize, component: Box<dyn Component>) {
+        self.insert_entry(index, component, ComponentState::new());
+    }
+
+    pub fn remove(&mut self, index: usize) -> Box<dyn Component> {
+        self.remove_entry(index).0
+    }
+
+    /// Like `remove`, but also hands back the component's flags so
+    /// `insert_entry` can put it back unchanged.
+    pub(crate) fn remove_entry(&mut self, index: usize) -> (Box<dyn Component>, ComponentState) {
+        self.types.remove(index);
+        (self.components.remove(index), self.states.remove(index))
+    }
+
+    pub(crate) fn insert_entry(
+        &mut self,
+        index: usize,
+        component: Box<dyn Component>,
+        state: ComponentState,
+    ) {
+        self.types.insert(index, component.as_any().type_id());
+        self.components.insert(index, component);
+        self.states.insert(index, state);
+    }
+
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.states[index].enabled
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        self.states[index].enabled = enabled;
+    }
+
+    pub(crate) fn get_state(&self, index: usize) -> ComponentState {
+        self.states[index]
+    }
+
+    /// Empties the storage, returning the components in insertion order.
+    pub(crate) fn drain(&mut self) -> Vec<(Box<dyn Component>, ComponentState)> {
+        self.types.clear();
+        self.components
+            .drain(..)
+            .zip(self.states.drain(..))
+            .collect()
+    }
+
+    pub(crate) fn mark_from_prefab(&mut self) {
+        for state in self.states.iter_mut() {
+            state.from_prefab = true;
+        }
+    }
+
+    pub fn position(&self, type_id: TypeId) -> Option<usize> {
+        self.types.iter().position(|t| *t == type_id)
+    }
+
+    pub fn contains(&self, type_id: TypeId) -> bool {
+        self.types.contains(&type_id)
+    }
+
+    pub fn get<T: Component>(&self) -> Option<&T> {
+        let index = self.position(TypeId::of::<T>())?;
+        self.components[index].as_any().downcast_ref::<T>()
+    }
+
+    pub fn get_mut<T: Component>(&mut self) -> Option<&mut T> {
+        let index = self.position(TypeId::of::<T>())?;
+        self.components[index].as_any_mut().downcast_mut::<T>()
+    }
+
+    pub fn types(&self) -> &[TypeId] {
+        &self.types
+    }
+
+    pub fn as_vec(&self) -> &Vec<Box<dyn Component>> {
+        &self.components
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Box<dyn Component>> {
+        self.components.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Box<dyn Component>> {
+        self.components.iter_mut()
+    }
+
+    pub(crate) fn iter_with_states(
+        &self,
+    ) -> impl Iterator<Item = (&Box<dyn Component>, ComponentState)> {
+        self.components.iter().zip(self.states.iter().copied())
+    }
+
+    pub fn iter_enabled(&self) -> impl Iterator<Item = &Box<dyn Component>> {
+        self.components
+            .iter()
+            .zip(&self.states)
+            .filter(|(_, state)| state.enabled)
+            .map(|(component, _)| component)
+    }
+
+    pub fn iter_enabled_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Component>> {
+        self.components
+            .iter_mut()
+            .zip(&self.states)
+            .filter(|(_, state)| state.enabled)
+            .map(|(component, _)| component)
+    }
+}
+
+impl ComponentState {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: true,
+            added: false,
+            from_prefab: false,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use cgmath::{
+    EuclideanSpace, InnerSpace, Matrix3, Matrix4, Point3, Quaternion, SquareMatrix, Transform as _,
+    Vector3, VectorSpace,
+};
+
+use super::Transform;
+
+impl Transform {
+    pub fn new() -> Self {
+        Self {
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn from_translation<P: Into<Point3<f32>>>(position: P) -> Self {
+        Self {
+            translation: position.into().to_vec(),
+            ..Self::new()
+        }
+    }
+
+    /// Splits an affine matrix into translation, rotation and scale.
+    /// Shear cannot be represented and is lost.
+    pub fn from_matrix(matrix: Matrix4<f32>) -> Self {
+        let x = matrix.x.truncate();
+        let y = matrix.y.truncate();
+        let z = matrix.z.truncate();
+        let mut scale = Vector3::new(x.magnitude(), y.magnitude(), z.magnitude());
+        if Matrix3::from_cols(x, y, z).determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+        let axis = |v: Vector3<f32>, s: f32| if s != 0.0 { v / s } else { v };
+        let rotation = Matrix3::from_cols(axis(x, scale.x), axis(y, scale.y), axis(z, scale.z));
+        Self {
+            translation: matrix.w.truncate(),
+            rotation: Quaternion::from(rotation).normalize(),
+            scale,
+        }
+    }
+
+    pub fn get_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    pub fn get_position(&self) -> Point3<f32> {
+        Point3::from_vec(self.translation)
+    }
+
+    /// Blends towards `other`; `t = 0` gives `self`, `t = 1` gives `other`.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.nlerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+
+    pub fn transform_point(&self, point: Point3<f32>) -> Point3<f32> {
+        self.get_matrix().transform_point(point)
+    }
+
+    /// Rotates so that local +Z points at `target`, with local +Y as close to
+    /// `up` as possible. Both are in the same space as `translation`.
+    pub fn look_at(&mut self, target: Point3<f32>, up: Vector3<f32>) {
+        let forward = target.to_vec() - self.translation;
+        if forward.magnitude2() == 0.0 {
+            return;
+        }
+        let forward = forward.normalize();
+        let right = up.cross(forward);
+        if right.magnitude2() == 0.0 {
+            return;
+        }
+        let right = right.normalize();
+        let up = forward.cross(right);
+        self.rotation = Quaternion::from(Matrix3::from_cols(right, up, forward));
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+    sync::mpsc::{self, Receiver},
+};
+
+use crate::core::{
+    entity::{component::Component, Entity, EntityHandle},
+    scene::Scene,
+};
+
+use super::{Callback, Event, EventBus, EventScope, QueuedEvent, Subscriber, SubscriptionId};
+
+/// Delivery passes per dispatch. Events published by handlers are delivered
+/// in the next pass; whatever is left after the last one waits a frame.
+const MAX_DISPATCH_PASSES: u32 = 8;
+
+impl EventScope {
+    fn matches(&self, source: Option<EntityHandle>) -> bool {
+        match self {
+            EventScope::Global => true,
+            EventScope::Entity(id) => source == Some(*id),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            queue: Vec::new(),
+            subscribers: HashMap::new(),
+            cancelled: HashSet::new(),
+            despawned: HashSet::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Queues `event` for delivery at the end of the frame. `source` is the
+    /// entity the event concerns, which entity-scoped subscribers filter on.
+    pub fn publish<E: Event>(&mut self, source: Option<EntityHandle>, event: E) {
+        self.queue.push(QueuedEvent {
+            type_id: TypeId::of::<E>(),
+            source,
+            event: Box::new(event),
+        });
+    }
+
+    pub fn subscribe<E, F>(&mut self, scope: EventScope, mut callback: F) -> SubscriptionId
+    where
+        E: Event,
+        F: FnMut(&mut Scene, &E, Option<EntityHandle>) + 'static,
+    {
+        self.insert::<E>(
+            scope,
+            None,
+            Box::new(move |scene, event, source| {
+                if let Some(event) = event.downcast_ref::<E>() {
+                    callback(scene, event, source);
+                }
+                true
+            }),
+        )
+    }
+
+    /// Subscribes `owner`'s `C`, which `handler` gets detached from the
+    /// entity the same way `Component::update` does. Skipped while the
+    /// entity or component is disabled; ends when `owner` is despawned.
+    pub fn subscribe_component<C, E, F>(
+        &mut self,
+        owner: EntityHandle,
+        scope: EventScope,
+        mut handler: F,
+    ) -> SubscriptionId
+    where
+        C: Component,
+        E: Event,
+        F: FnMut(&mut C, &mut Scene, &mut Entity, &E) + 'static,
+    {
+        self.insert::<E>(
+            scope,
+            Some(owner),
+            Box::new(move |scene, event, _| {
+                let Some(event) = event.downcast_ref::<E>() else {
+                    return true;
+                };
+                scene.with_entity_mut(&owner, |scene, entity| {
+                    if entity.is_enabled() && entity.is_component_enabled::<C>(scene) {
+                        entity
+                            .with_component_detached::<C, _>(scene, |component, scene, entity| {
+                                handler(component, scene, entity, event)
+                            });
+                    }
+                });
+                true
+            }),
+        )
+    }
+
+    /// Subscription that forwards events and their source to a channel,
+    /// for code that cannot borrow the scene when they arrive, such as a
+    /// `Layer` draining them in `on_update`. Dropping the receiver ends it.
+    pub fn listen<E: Event + Clone>(
+        &mut self,
+        scope: EventScope,
+    ) -> Receiver<(Option<EntityHandle>, E)> {
+        let (sender, receiver) = mpsc::channel();
+        self.insert::<E>(
+            scope,
+            None,
+            Box::new(move |_, event, source| match event.downcast_ref::<E>() {
+                Some(event) => sender.send((source, event.clone())).is_ok(),
+                None => true,
+            }),
+        );
+        receiver
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        for subscribers in self.subscribers.values_mut() {
+            subscribers.retain(|subscriber| subscriber.id != id);
+        }
+        self.cancelled.insert(id);
+    }
+
+    /// Drops the subscriptions scoped to or owned by a despawned entity.
+    pub(crate) fn remove_entity(&mut self, id: EntityHandle) {
+        for subscribers in self.subscribers.values_mut() {
+            subscribers.retain(|subscriber| !subscriber.involves(id));
+        }
+        self.despawned.insert(id);
+    }
+
+    /// Drops queued events and every subscription tied to an entity, for
+    /// when the scene's entities are replaced. Global subscriptions stay.
+    pub(crate) fn reset_entities(&mut self) {
+        self.queue.clear();
+        for subscribers in self.subscribers.values_mut() {
+            subscribers.retain(|subscriber| {
+                subscriber.owner.is_none() && subscriber.scope == EventScope::Global
+            });
+        }
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn insert<E: Event>(
+        &mut self,
+        scope: EventScope,
+        owner: Option<EntityHandle>,
+        callback: Callback,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers
+            .entry(TypeId::of::<E>())
+            .or_default()
+            .push(Subscriber {
+                id,
+                scope,
+                owner,
+                callback,
+            });
+        id
+    }
+
+    fn is_cancelled(&self, subscriber: &Subscriber) -> bool {
+        self.cancelled.contains(&subscriber.id)
+            || self.despawned.iter().any(|id| subscriber.involves(*id))
+    }
+
+    /// Delivers queued events, applying the commands handlers queue after
+    /// each pass.
+    pub(crate) fn dispatch(scene: &mut Scene) {
+        for _ in 0..MAX_DISPATCH_PASSES {
+            let queue = std::mem::take(&mut scene.get_events_mut().queue);
+            if queue.is_empty() {
+                break;
+            }
+            for event in queue {
+                Self::deliver(scene, event);
+            }
+            scene.apply_commands();
+        }
+
+        let events = scene.get_events_mut();
+        if !events.queue.is_empty() {
+            log::warn!(
+                "{} events still queued after {} dispatch passes, delivering them next frame",
+                events.queue.len(),
+                MAX_DISPATCH_PASSES
+            );
+        }
+        events.cancelled.clear();
+        events.despawned.clear();
+    }
+
+    /// The subscriber list is taken out of the bus while handlers run, so
+    /// they can publish, subscribe and unsubscribe through the scene.
+    fn deliver(scene: &mut Scene, event: QueuedEvent) {
+        let Some(mut subscribers) = scene.get_events_mut().subscribers.remove(&event.type_id)
+        else {
+            return;
+        };
+        subscribers.retain_mut(|subscriber| {
+            if scene.get_events_mut().is_cancelled(subscriber) {
+                return false;
+            }
+            if !subscriber.scope.matches(event.source) {
+                return true;
+            }
+            (subscriber.callback)(scene, event.event.as_ref(), event.source)
+        });
+
+        let events = scene.get_events_mut();
+        if let Some(added) = events.subscribers.remove(&event.type_id) {
+            subscribers.extend(added);
+        }
+        subscribers.retain(|subscriber| !events.is_cancelled(subscriber));
+        if !subscribers.is_empty() {
+            events.subscribers.insert(event.type_id, subscribers);
+        }
+    }
+}
+
+impl Subscriber {
+    fn involves(&self, id: EntityHandle) -> bool {
+        self.owner == Some(id) || self.scope == EventScope::Entity(id)
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet},
+};
+
+use super::{entity::EntityHandle, scene::Scene};
+
+mod event_bus;
+
+/// Marker for types that can be published through the `EventBus`.
+pub trait Event: Any {}
+
+/// Which published events a subscriber receives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventScope {
+    /// Events from any source, including those without one.
+    Global,
+    /// Only events published with this entity as their source. The
+    /// subscription ends when the entity is despawned.
+    Entity(EntityHandle),
+}
+
+/// Returned by the `subscribe` methods, for `EventBus::unsubscribe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// Returns `false` to end the subscription.
+type Callback = Box<dyn FnMut(&mut Scene, &dyn Any, Option<EntityHandle>) -> bool>;
+
+struct Subscriber {
+    id: SubscriptionId,
+    scope: EventScope,
+    /// Entity whose component the subscription belongs to, if any.
+    owner: Option<EntityHandle>,
+    callback: Callback,
+}
+
+struct QueuedEvent {
+    type_id: TypeId,
+    source: Option<EntityHandle>,
+    event: Box<dyn Any>,
+}
+
+/// Typed publish/subscribe channel between components, and between
+/// components and `Layer`s. Events published during a frame are queued and
+/// delivered, in publishing order, by `Scene::dispatch_events` at the end
+/// of `Scene::update`, when every entity is attached to the scene again.
+pub struct EventBus {
+    queue: Vec<QueuedEvent>,
+    subscribers: HashMap<TypeId, Vec<Subscriber>>,
+    /// Subscriptions cancelled, and entities despawned, while a subscriber
+    /// list was checked out for delivery. Cleared after each dispatch.
+    cancelled: HashSet<SubscriptionId>,
+    despawned: HashSet<EntityHandle>,
+    next_id: u64,
+}
@@ -0,0 +1,201 @@
+use glfw::{Action, GamepadAxis, GamepadButton, Glfw, JoystickId};
+
+use super::{Gamepad, GamepadEvent, Gamepads, GAMEPAD_AXES, GAMEPAD_BUTTONS};
+
+const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+const JOYSTICKS: [JoystickId; 16] = [
+    JoystickId::Joystick1,
+    JoystickId::Joystick2,
+    JoystickId::Joystick3,
+    JoystickId::Joystick4,
+    JoystickId::Joystick5,
+    JoystickId::Joystick6,
+    JoystickId::Joystick7,
+    JoystickId::Joystick8,
+    JoystickId::Joystick9,
+    JoystickId::Joystick10,
+    JoystickId::Joystick11,
+    JoystickId::Joystick12,
+    JoystickId::Joystick13,
+    JoystickId::Joystick14,
+    JoystickId::Joystick15,
+    JoystickId::Joystick16,
+];
+
+/// In GLFW's order, so `button as usize` indexes `Gamepad::buttons`.
+pub(super) const BUTTONS: [GamepadButton; GAMEPAD_BUTTONS] = [
+    GamepadButton::ButtonA,
+    GamepadButton::ButtonB,
+    GamepadButton::ButtonX,
+    GamepadButton::ButtonY,
+    GamepadButton::ButtonLeftBumper,
+    GamepadButton::ButtonRightBumper,
+    GamepadButton::ButtonBack,
+    GamepadButton::ButtonStart,
+    GamepadButton::ButtonGuide,
+    GamepadButton::ButtonLeftThumb,
+    GamepadButton::ButtonRightThumb,
+    GamepadButton::ButtonDpadUp,
+    GamepadButton::ButtonDpadRight,
+    GamepadButton::ButtonDpadDown,
+    GamepadButton::ButtonDpadLeft,
+];
+
+/// In GLFW's order, so `axis as usize` indexes `Gamepad::axes`.
+pub(super) const AXES: [GamepadAxis; GAMEPAD_AXES] = [
+    GamepadAxis::AxisLeftX,
+    GamepadAxis::AxisLeftY,
+    GamepadAxis::AxisRightX,
+    GamepadAxis::AxisRightY,
+    GamepadAxis::AxisLeftTrigger,
+    GamepadAxis::AxisRightTrigger,
+];
+
+impl Gamepad {
+    pub(super) fn new(id: usize, name: String) -> Self {
+        Self {
+            id,
+            name,
+            buttons: [false; GAMEPAD_BUTTONS],
+            axes: [0.0; GAMEPAD_AXES],
+        }
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    pub fn get_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    pub(super) fn apply(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent::Button {
+                button, pressed, ..
+            } => self.buttons[*button as usize] = *pressed,
+            GamepadEvent::Axis { axis, value, .. } => self.axes[*axis as usize] = *value,
+            _ => {}
+        }
+    }
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        Self {
+            slots: vec![None; JOYSTICKS.len()],
+            dead_zone: DEFAULT_DEAD_ZONE,
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Gamepad> {
+        self.slots.get(id)?.as_ref()
+    }
+
+    pub fn get_connected(&self) -> impl Iterator<Item = &Gamepad> {
+        self.slots.iter().flatten()
+    }
+
+    pub fn get_dead_zone(&self) -> f32 {
+        self.dead_zone
+    }
+
+    /// Fraction of a stick's or trigger's travel, from 0 to 1, that reads
+    /// as 0. Sticks use a radial dead zone, so diagonals are not cut off.
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+
+    /// Reads every joystick slot and reports what changed since the last
+    /// poll. Joysticks without a gamepad mapping are ignored.
+    pub fn poll(&mut self, glfw: &Glfw) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        for (id, joystick_id) in JOYSTICKS.iter().enumerate() {
+            let joystick = glfw.get_joystick(*joystick_id);
+            let state = if joystick.is_gamepad() {
+                joystick.get_gamepad_state()
+            } else {
+                None
+            };
+            let Some(state) = state else {
+                if self.slots[id].take().is_some() {
+                    events.push(GamepadEvent::Disconnected { id });
+                }
+                continue;
+            };
+
+            let pad = match &mut self.slots[id] {
+                Some(pad) => pad,
+                slot => {
+                    let name = joystick
+                        .get_gamepad_name()
+                        .unwrap_or_else(|| format!("Gamepad {}", id + 1));
+                    events.push(GamepadEvent::Connected {
+                        id,
+                        name: name.clone(),
+                    });
+                    slot.insert(Gamepad::new(id, name))
+                }
+            };
+
+            let mut changes = Vec::new();
+            for button in BUTTONS {
+                let pressed = state.get_button_state(button) == Action::Press;
+                if pad.is_pressed(button) != pressed {
+                    changes.push(GamepadEvent::Button {
+                        id,
+                        button,
+                        pressed,
+                    });
+                }
+            }
+            let mut axes = AXES.map(|axis| state.get_axis(axis));
+            for stick in [0, 2] {
+                let (x, y) = radial_dead_zone(axes[stick], axes[stick + 1], self.dead_zone);
+                axes[stick] = x;
+                axes[stick + 1] = y;
+            }
+            for trigger in [4, 5] {
+                // GLFW reports released triggers as -1.
+                axes[trigger] = dead_zone((axes[trigger] + 1.0) / 2.0, self.dead_zone);
+            }
+            for (axis, value) in AXES.into_iter().zip(axes) {
+                if pad.get_axis(axis) != value {
+                    changes.push(GamepadEvent::Axis { id, axis, value });
+                }
+            }
+
+            for change in changes.iter() {
+                pad.apply(change);
+            }
+            events.extend(changes);
+        }
+        events
+    }
+}
+
+fn dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value <= dead_zone {
+        0.0
+    } else {
+        ((value - dead_zone) / (1.0 - dead_zone)).min(1.0)
+    }
+}
+
+fn radial_dead_zone(x: f32, y: f32, zone: f32) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length <= zone {
+        return (0.0, 0.0);
+    }
+    let scale = dead_zone(length, zone) / length;
+    (x * scale, y * scale)
+}
@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet};
+
+use glfw::{Action, CursorMode, WindowEvent};
+
+use super::{Binding, Gamepad, GamepadEvent, Input, InputMap};
+
+/// Gamepad axes count as held from this far.
+const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Self {
+            map,
+            keys: HashSet::new(),
+            buttons: HashSet::new(),
+            gamepads: HashMap::new(),
+            chord_modifiers: HashSet::new(),
+            actions: HashMap::new(),
+            cursor_mode: None,
+        }
+    }
+
+    pub fn get_map(&self) -> &InputMap {
+        &self.map
+    }
+
+    pub fn set_map(&mut self, map: InputMap) {
+        self.map = map;
+        self.refresh_actions();
+    }
+
+    /// Rebinds actions and axes at runtime. Action states catch up with the
+    /// new bindings on the next key or button event.
+    pub fn get_map_mut(&mut self) -> &mut InputMap {
+        &mut self.map
+    }
+
+    /// Whether a binding of the action is held, or a gamepad axis bound to
+    /// it is at least halfway.
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.actions.get(action).is_some_and(|state| state.pressed)
+    }
+
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|state| state.just_pressed)
+    }
+
+    pub fn just_released(&self, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|state| state.just_released)
+    }
+
+    /// Released without one of its keys having been the modifier of a
+    /// chord meanwhile, so F3 can toggle one thing alone and be held for
+    /// F3+C.
+    pub fn just_tapped(&self, action: &str) -> bool {
+        self.actions.get(action).is_some_and(|state| state.tapped)
+    }
+
+    /// How far the action is pressed, from 0 to 1. Keys and buttons read 0
+    /// or 1, gamepad axes anything in between.
+    pub fn get_value(&self, action: &str) -> f32 {
+        self.get_strength(self.map.get_bindings(action))
+    }
+
+    /// From -1 to 1; 0 for unbound axes.
+    pub fn get_axis(&self, axis: &str) -> f32 {
+        let Some(binding) = self.map.get_axis_binding(axis) else {
+            return 0.0;
+        };
+        self.get_strength(&binding.positive) - self.get_strength(&binding.negative)
+    }
+
+    /// Connected gamepads, for reading buttons and sticks directly.
+    pub fn get_gamepads(&self) -> impl Iterator<Item = &Gamepad> {
+        self.gamepads.values()
+    }
+
+    /// Asks for the window's cursor mode to change. Takes effect after the
+    /// next window event has been handled.
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.cursor_mode = Some(mode);
+    }
+
+    pub(crate) fn take_cursor_mode(&mut self) -> Option<CursorMode> {
+        self.cursor_mode.take()
+    }
+
+    pub(crate) fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::Key(key, _, Action::Press, _) => {
+                self.keys.insert(*key);
+                for bindings in self.map.get_actions().values() {
+                    for binding in bindings {
+                        if let Binding::Chord { modifier, key: k } = binding {
+                            if k == key && self.keys.contains(modifier) {
+                                self.chord_modifiers.insert(*modifier);
+                            }
+                        }
+                    }
+                }
+                self.refresh_actions();
+            }
+            WindowEvent::Key(key, _, Action::Release, _) => {
+                self.keys.remove(key);
+                self.refresh_actions();
+                self.chord_modifiers.remove(key);
+            }
+            WindowEvent::MouseButton(button, Action::Press, _) => {
+                self.buttons.insert(*button);
+                self.refresh_actions();
+            }
+            WindowEvent::MouseButton(button, Action::Release, _) => {
+                self.buttons.remove(button);
+                self.refresh_actions();
+            }
+            WindowEvent::Focus(false) => {
+                // Releases are not reported to an unfocused window.
+                self.keys.clear();
+                self.buttons.clear();
+                self.refresh_actions();
+                self.chord_modifiers.clear();
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn handle_gamepad_event(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent::Connected { id, name } => {
+                self.gamepads.insert(*id, Gamepad::new(*id, name.clone()));
+            }
+            GamepadEvent::Disconnected { id } => {
+                self.gamepads.remove(id);
+            }
+            GamepadEvent::Button { id, .. } | GamepadEvent::Axis { id, .. } => {
+                if let Some(gamepad) = self.gamepads.get_mut(id) {
+                    gamepad.apply(event);
+                }
+            }
+        }
+        self.refresh_actions();
+    }
+
+    /// Clears the presses and releases reported this frame.
+    pub(crate) fn end_frame(&mut self) {
+        for state in self.actions.values_mut() {
+            state.just_pressed = false;
+            state.just_released = false;
+            state.tapped = false;
+        }
+    }
+
+    /// The strongest of `bindings`, from 0 to 1.
+    fn get_strength(&self, bindings: &[Binding]) -> f32 {
+        bindings
+            .iter()
+            .map(|binding| self.get_binding_strength(binding))
+            .fold(0.0, f32::max)
+    }
+
+    fn get_binding_strength(&self, binding: &Binding) -> f32 {
+        let held = match binding {
+            Binding::Key(key) => self.keys.contains(key),
+            Binding::Mouse(button) => self.buttons.contains(button),
+            Binding::Chord { modifier, key } => {
+                self.keys.contains(modifier) && self.keys.contains(key)
+            }
+            Binding::GamepadButton(button) => {
+                self.gamepads.values().any(|pad| pad.is_pressed(*button))
+            }
+            Binding::GamepadAxis { axis, inverted } => {
+                let sign = if *inverted { -1.0 } else { 1.0 };
+                return self
+                    .gamepads
+                    .values()
+                    .map(|pad| (pad.get_axis(*axis) * sign).max(0.0))
+                    .fold(0.0, f32::max);
+            }
+        };
+        if held {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn refresh_actions(&mut self) {
+        for (action, bindings) in self.map.get_actions() {
+            let pressed = self.get_strength(bindings) >= AXIS_PRESS_THRESHOLD;
+            let chorded = bindings
+                .iter()
+                .any(|b| matches!(b, Binding::Key(key) if self.chord_modifiers.contains(key)));
+            let state = self.actions.entry(action.clone()).or_default();
+            if pressed && !state.pressed {
+                state.just_pressed = true;
+                state.chorded = false;
+            } else if !pressed && state.pressed {
+                state.just_released = true;
+                state.tapped = !state.chorded;
+            }
+            state.pressed = pressed;
+            state.chorded |= pressed && chorded;
+        }
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new(InputMap::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::{Action, Key, Modifiers, WindowEvent};
+
+    use crate::core::input::{Binding, Input, InputMap};
+
+    fn key(input: &mut Input, key: Key, action: Action) {
+        input.handle_event(&WindowEvent::Key(key, 0, action, Modifiers::empty()));
+    }
+
+    /// F3 alone toggles the debug view, F3+C copies the camera position.
+    fn input() -> Input {
+        let mut map = InputMap::new();
+        map.bind("debug", Binding::Key(Key::F3));
+        map.bind(
+            "copy_camera",
+            Binding::Chord {
+                modifier: Key::F3,
+                key: Key::C,
+            },
+        );
+        map.bind_axis(
+            "forward",
+            vec![Binding::Key(Key::W)],
+            vec![Binding::Key(Key::S)],
+        );
+        Input::new(map)
+    }
+
+    #[test]
+    fn chords_need_the_modifier_held() {
+        let mut input = input();
+        key(&mut input, Key::C, Action::Press);
+        assert!(!input.is_pressed("copy_camera"));
+        key(&mut input, Key::C, Action::Release);
+
+        key(&mut input, Key::F3, Action::Press);
+        key(&mut input, Key::C, Action::Press);
+        assert!(input.just_pressed("copy_camera"));
+        key(&mut input, Key::F3, Action::Release);
+        assert!(input.just_released("copy_camera"));
+    }
+
+    #[test]
+    fn modifiers_used_in_a_chord_are_not_tapped() {
+        let mut input = input();
+        key(&mut input, Key::F3, Action::Press);
+        key(&mut input, Key::C, Action::Press);
+        key(&mut input, Key::C, Action::Release);
+        key(&mut input, Key::F3, Action::Release);
+        assert!(input.just_released("debug"));
+        assert!(!input.just_tapped("debug"));
+        input.end_frame();
+
+        key(&mut input, Key::F3, Action::Press);
+        key(&mut input, Key::F3, Action::Release);
+        assert!(input.just_tapped("debug"));
+        input.end_frame();
+        assert!(!input.just_tapped("debug"));
+        assert!(!input.just_pressed("debug"));
+    }
+
+    #[test]
+    fn axes_subtract_the_negative_bindings() {
+        let mut input = input();
+        key(&mut input, Key::W, Action::Press);
+        assert_eq!(input.get_axis("forward"), 1.0);
+        key(&mut input, Key::S, Action::Press);
+        assert_eq!(input.get_axis("forward"), 0.0);
+        key(&mut input, Key::W, Action::Release);
+        assert_eq!(input.get_axis("forward"), -1.0);
+        assert_eq!(input.get_axis("strafe"), 0.0);
+    }
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let mut input = input();
+        key(&mut input, Key::F3, Action::Press);
+        input.handle_event(&WindowEvent::Focus(false));
+        assert!(!input.is_pressed("debug"));
+        assert!(input.just_released("debug"));
+    }
+}
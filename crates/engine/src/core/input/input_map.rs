@@ -0,0 +1,409 @@
+use std::{collections::HashMap, error::Error, fmt, fs, str::FromStr};
+
+use glfw::{GamepadAxis, GamepadButton, Key, MouseButton};
+
+use super::{
+    gamepad::{AXES, BUTTONS},
+    AxisBinding, AxisBindingData, Binding, InputMap, InputMapData,
+};
+
+/// Keys that can be named in config files, by their `glfw::Key` name.
+const KEYS: &[Key] = &[
+    Key::Space,
+    Key::Apostrophe,
+    Key::Comma,
+    Key::Minus,
+    Key::Period,
+    Key::Slash,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Semicolon,
+    Key::Equal,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::LeftBracket,
+    Key::Backslash,
+    Key::RightBracket,
+    Key::GraveAccent,
+    Key::Escape,
+    Key::Enter,
+    Key::Tab,
+    Key::Backspace,
+    Key::Insert,
+    Key::Delete,
+    Key::Right,
+    Key::Left,
+    Key::Down,
+    Key::Up,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Home,
+    Key::End,
+    Key::CapsLock,
+    Key::PrintScreen,
+    Key::Pause,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Kp0,
+    Key::Kp1,
+    Key::Kp2,
+    Key::Kp3,
+    Key::Kp4,
+    Key::Kp5,
+    Key::Kp6,
+    Key::Kp7,
+    Key::Kp8,
+    Key::Kp9,
+    Key::KpDecimal,
+    Key::KpDivide,
+    Key::KpMultiply,
+    Key::KpSubtract,
+    Key::KpAdd,
+    Key::KpEnter,
+    Key::LeftShift,
+    Key::LeftControl,
+    Key::LeftAlt,
+    Key::RightShift,
+    Key::RightControl,
+    Key::RightAlt,
+];
+
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+    ("MouseLeft", MouseButton::Button1),
+    ("MouseRight", MouseButton::Button2),
+    ("MouseMiddle", MouseButton::Button3),
+    ("Mouse4", MouseButton::Button4),
+    ("Mouse5", MouseButton::Button5),
+];
+
+/// Names of `BUTTONS` and `AXES`, in the same order.
+const BUTTON_NAMES: [&str; BUTTONS.len()] = [
+    "PadA",
+    "PadB",
+    "PadX",
+    "PadY",
+    "PadLeftBumper",
+    "PadRightBumper",
+    "PadBack",
+    "PadStart",
+    "PadGuide",
+    "PadLeftThumb",
+    "PadRightThumb",
+    "PadUp",
+    "PadRight",
+    "PadDown",
+    "PadLeft",
+];
+const AXIS_NAMES: [&str; AXES.len()] = [
+    "LeftX",
+    "LeftY",
+    "RightX",
+    "RightY",
+    "LeftTrigger",
+    "RightTrigger",
+];
+
+fn parse_key(name: &str) -> Result<Key, String> {
+    KEYS.iter()
+        .copied()
+        .find(|key| format!("{:?}", key) == name)
+        .ok_or_else(|| format!("Unknown key '{}'", name))
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((modifier, key)) = s.split_once('+') {
+            return Ok(Binding::Chord {
+                modifier: parse_key(modifier.trim())?,
+                key: parse_key(key.trim())?,
+            });
+        }
+        if let Some((_, button)) = MOUSE_BUTTONS.iter().find(|(name, _)| *name == s) {
+            return Ok(Binding::Mouse(*button));
+        }
+        if let Some(i) = BUTTON_NAMES.iter().position(|name| *name == s) {
+            return Ok(Binding::GamepadButton(BUTTONS[i]));
+        }
+        let (inverted, axis) = match s.strip_prefix('-') {
+            Some(axis) => (true, axis),
+            None => (false, s),
+        };
+        if let Some(i) = AXIS_NAMES.iter().position(|name| *name == axis) {
+            return Ok(Binding::GamepadAxis {
+                axis: AXES[i],
+                inverted,
+            });
+        }
+        Ok(Binding::Key(parse_key(s)?))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(button) => match MOUSE_BUTTONS.iter().find(|(_, b)| b == button) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", button),
+            },
+            Binding::Chord { modifier, key } => write!(f, "{:?}+{:?}", modifier, key),
+            Binding::GamepadButton(button) => write!(f, "{}", BUTTON_NAMES[*button as usize]),
+            Binding::GamepadAxis { axis, inverted } => {
+                let sign = if *inverted { "-" } else { "" };
+                write!(f, "{}{}", sign, AXIS_NAMES[*axis as usize])
+            }
+        }
+    }
+}
+
+fn parse_bindings(names: Vec<String>) -> Result<Vec<Binding>, String> {
+    names.iter().map(|name| name.parse()).collect()
+}
+
+fn binding_names(bindings: &[Binding]) -> Vec<String> {
+    bindings.iter().map(|binding| binding.to_string()).collect()
+}
+
+impl InputMap {
+    /// A map without any bindings.
+    pub fn new() -> Self {
+        Self {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    /// Reads bindings written by `save` over the current ones. Actions and
+    /// axes missing from the file keep theirs.
+    pub fn load(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let data: InputMapData = serde_json::from_str(&fs::read_to_string(path)?)?;
+        for (action, bindings) in data.actions {
+            self.actions.insert(action, parse_bindings(bindings)?);
+        }
+        for (axis, binding) in data.axes {
+            self.axes.insert(
+                axis,
+                AxisBinding {
+                    positive: parse_bindings(binding.positive)?,
+                    negative: parse_bindings(binding.negative)?,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let data = InputMapData {
+            actions: self
+                .actions
+                .iter()
+                .map(|(action, bindings)| (action.clone(), binding_names(bindings)))
+                .collect(),
+            axes: self
+                .axes
+                .iter()
+                .map(|(axis, binding)| {
+                    let data = AxisBindingData {
+                        positive: binding_names(&binding.positive),
+                        negative: binding_names(&binding.negative),
+                    };
+                    (axis.clone(), data)
+                })
+                .collect(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&data)?)?;
+        Ok(())
+    }
+
+    /// Adds `binding` to the action's bindings.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replaces every binding of the action. An empty list disables it.
+    pub fn set_bindings(&mut self, action: &str, bindings: Vec<Binding>) {
+        self.actions.insert(action.to_string(), bindings);
+    }
+
+    pub fn get_bindings(&self, action: &str) -> &[Binding] {
+        self.actions
+            .get(action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, positive: Vec<Binding>, negative: Vec<Binding>) {
+        self.axes
+            .insert(axis.to_string(), AxisBinding { positive, negative });
+    }
+
+    pub fn get_axis_binding(&self, axis: &str) -> Option<&AxisBinding> {
+        self.axes.get(axis)
+    }
+
+    pub(super) fn get_actions(&self) -> &HashMap<String, Vec<Binding>> {
+        &self.actions
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use GamepadAxis::{
+            AxisLeftTrigger, AxisLeftX, AxisLeftY, AxisRightTrigger, AxisRightX, AxisRightY,
+        };
+        let key = Binding::Key;
+        let pad = Binding::GamepadButton;
+        let axis = |axis, inverted| Binding::GamepadAxis { axis, inverted };
+
+        let mut map = Self::new();
+        let actions = [
+            // Stick up reads negative on Y.
+            ("move_forward", vec![key(Key::W), axis(AxisLeftY, true)]),
+            ("move_backward", vec![key(Key::S), axis(AxisLeftY, false)]),
+            ("move_left", vec![key(Key::A), axis(AxisLeftX, true)]),
+            ("move_right", vec![key(Key::D), axis(AxisLeftX, false)]),
+            (
+                "sprint",
+                vec![key(Key::LeftShift), pad(GamepadButton::ButtonLeftThumb)],
+            ),
+            ("jump", vec![key(Key::Space), pad(GamepadButton::ButtonA)]),
+            ("toggle_free_camera", vec![key(Key::Escape)]),
+            ("toggle_wireframe", vec![key(Key::F1)]),
+            ("toggle_vsync", vec![key(Key::F2)]),
+            ("toggle_debug", vec![key(Key::F3)]),
+            (
+                "toggle_colliders",
+                vec![Binding::Chord {
+                    modifier: Key::F3,
+                    key: Key::C,
+                }],
+            ),
+            ("toggle_rays", vec![key(Key::F4)]),
+            ("toggle_shadow_debug", vec![key(Key::F10)]),
+            ("screenshot", vec![key(Key::F12)]),
+        ];
+        for (action, bindings) in actions {
+            map.set_bindings(action, bindings);
+        }
+
+        let axes = [
+            (
+                "fly_forward",
+                vec![key(Key::Up), axis(AxisLeftY, true)],
+                vec![key(Key::Down), axis(AxisLeftY, false)],
+            ),
+            (
+                "fly_right",
+                vec![key(Key::Right), axis(AxisLeftX, false)],
+                vec![key(Key::Left), axis(AxisLeftX, true)],
+            ),
+            (
+                "fly_up",
+                vec![key(Key::Space), axis(AxisRightTrigger, false)],
+                vec![key(Key::LeftShift), axis(AxisLeftTrigger, false)],
+            ),
+            (
+                "look_right",
+                vec![axis(AxisRightX, false)],
+                vec![axis(AxisRightX, true)],
+            ),
+            (
+                "look_up",
+                vec![axis(AxisRightY, true)],
+                vec![axis(AxisRightY, false)],
+            ),
+        ];
+        for (name, positive, negative) in axes {
+            map.bind_axis(name, positive, negative);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::{GamepadAxis, Key, MouseButton};
+
+    use crate::core::input::Binding;
+
+    #[test]
+    fn bindings_round_trip_through_their_names() {
+        let bindings = [
+            ("W", Binding::Key(Key::W)),
+            ("MouseLeft", Binding::Mouse(MouseButton::Button1)),
+            (
+                "F3+C",
+                Binding::Chord {
+                    modifier: Key::F3,
+                    key: Key::C,
+                },
+            ),
+            (
+                "-LeftY",
+                Binding::GamepadAxis {
+                    axis: GamepadAxis::AxisLeftY,
+                    inverted: true,
+                },
+            ),
+        ];
+        for (name, binding) in bindings {
+            assert_eq!(name.parse::<Binding>(), Ok(binding));
+            assert_eq!(binding.to_string(), name);
+        }
+        assert_eq!(
+            "F3 + C".parse::<Binding>(),
+            Ok(Binding::Chord {
+                modifier: Key::F3,
+                key: Key::C,
+            })
+        );
+        assert!("F3+Nope".parse::<Binding>().is_err());
+    }
+}
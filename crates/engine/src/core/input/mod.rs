@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use glfw::{CursorMode, GamepadAxis, GamepadButton, Key, MouseButton};
+use serde::{Deserialize, Serialize};
+
+mod gamepad;
+mod input;
+mod input_map;
+
+/// What an action or axis direction can be bound to. In config files these
+/// are written as key names like `"W"`, `"LeftShift"` or `"MouseLeft"`,
+/// chords as `"F3+C"`, gamepad buttons as `"PadA"` and gamepad axes as
+/// `"LeftX"`, `"-LeftY"` or `"RightTrigger"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(Key),
+    Mouse(MouseButton),
+    /// Active while `key` is held together with `modifier`, e.g. F3+C.
+    Chord {
+        modifier: Key,
+        key: Key,
+    },
+    GamepadButton(GamepadButton),
+    /// Reads the axis from 0 to 1 in one direction, `inverted` for the
+    /// negative one. Counts as held from halfway.
+    GamepadAxis {
+        axis: GamepadAxis,
+        inverted: bool,
+    },
+}
+
+/// Maps action and axis names to bindings. Loaded from and saved to JSON;
+/// the `Default` holds the engine's own bindings.
+#[derive(Clone, Debug)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<Binding>>,
+    axes: HashMap<String, AxisBinding>,
+}
+
+/// An axis reads the strongest `positive` binding minus the strongest
+/// `negative` one: 1 or -1 for keys and buttons, anything in between for
+/// gamepad axes.
+#[derive(Clone, Debug, Default)]
+pub struct AxisBinding {
+    pub positive: Vec<Binding>,
+    pub negative: Vec<Binding>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct InputMapData {
+    #[serde(default)]
+    actions: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    axes: BTreeMap<String, AxisBindingData>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AxisBindingData {
+    #[serde(default)]
+    positive: Vec<String>,
+    #[serde(default)]
+    negative: Vec<String>,
+}
+
+/// Action state of a scene, fed with window events by `Scene::handle_event`
+/// and read by components during `update`. Presses and releases are
+/// reported until the end of the next `Scene::update`.
+pub struct Input {
+    map: InputMap,
+    keys: HashSet<Key>,
+    buttons: HashSet<MouseButton>,
+    /// Connected gamepads by joystick slot, as reported by `GamepadEvent`s.
+    gamepads: HashMap<usize, Gamepad>,
+    /// Held keys that were the modifier of an active chord since pressed.
+    chord_modifiers: HashSet<Key>,
+    actions: HashMap<String, ActionState>,
+    /// Applied to the window by `Scene::handle_event`.
+    cursor_mode: Option<CursorMode>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ActionState {
+    pressed: bool,
+    just_pressed: bool,
+    just_released: bool,
+    /// One of the action's keys was used as a chord modifier while held.
+    chorded: bool,
+    /// Released this frame without having been `chorded`.
+    tapped: bool,
+}
+
+/// Reported by `Gamepads::poll` once per frame, and passed on to every
+/// `Layer`. Axis values already have the dead zone applied.
+#[derive(Clone, Debug)]
+pub enum GamepadEvent {
+    Connected {
+        id: usize,
+        name: String,
+    },
+    Disconnected {
+        id: usize,
+    },
+    Button {
+        id: usize,
+        button: GamepadButton,
+        pressed: bool,
+    },
+    Axis {
+        id: usize,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// State of one controller with a GLFW gamepad mapping.
+#[derive(Clone, Debug)]
+pub struct Gamepad {
+    /// GLFW joystick slot, 0 to 15.
+    id: usize,
+    name: String,
+    buttons: [bool; GAMEPAD_BUTTONS],
+    /// Sticks from -1 to 1 (up is -1 on Y), triggers from 0 to 1.
+    axes: [f32; GAMEPAD_AXES],
+}
+
+const GAMEPAD_BUTTONS: usize = 15;
+const GAMEPAD_AXES: usize = 6;
+
+/// Polls GLFW's joystick slots for gamepads. Owned by the `Window`.
+pub struct Gamepads {
+    slots: Vec<Option<Gamepad>>,
+    /// Fraction of a stick's or trigger's travel that reads as 0.
+    dead_zone: f32,
+}
@@ -1,12 +1,24 @@
+pub mod allocator;
 pub mod application;
+pub mod assets;
 pub mod camera;
+pub mod console;
 pub mod entity;
+pub mod event;
+pub mod input;
 pub mod model;
 pub mod mouse_picker;
 pub mod physics;
+pub mod prefab;
 pub mod primitives;
+pub mod recording;
 pub mod renderer;
 pub mod scene;
+pub mod scene_manager;
+pub mod scheduler;
+pub mod serialization;
+pub mod time;
+pub mod timer;
 pub mod utils;
 pub mod view_frustum;
 pub mod window;
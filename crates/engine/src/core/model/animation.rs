@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use russimp_ng::scene::Scene;
 
+use crate::core::assets::WatchedPath;
+
 use super::{Animation, Channel, Pose};
 
 impl Animation {
@@ -16,17 +18,46 @@ impl Animation {
             duration: animation.duration as f32,
             ticks_per_second: animation.ticks_per_second as f32,
             channels,
+            source: None,
         }
     }
 
+    /// The file is reloaded when it changes, see `HotReload`.
     pub fn from_file(name: &str, path: &str) -> Result<Animation, Box<dyn std::error::Error>> {
-        let scene = Scene::from_file(format!("assets/animations/{path}").as_str(), vec![])?;
+        let path = format!("assets/animations/{path}");
+        let mut animation = Animation::read_file(&path)?;
+        animation.set_name(name);
+        animation.source = Some(WatchedPath::new(path));
+        Ok(animation)
+    }
+
+    fn read_file(path: &str) -> Result<Animation, Box<dyn std::error::Error>> {
+        let scene = Scene::from_file(path, vec![])?;
         if scene.animations.len() == 0 {
             return Err("No animations found".into());
         }
-        let mut animation = Animation::new(&scene.animations[0]);
-        animation.set_name(name);
-        Ok(animation)
+        Ok(Animation::new(&scene.animations[0]))
+    }
+
+    /// Re-reads the keyframes if the file changed, keeping the name. A file
+    /// that fails to load is logged and the previous keyframes stay in use.
+    pub fn reload_if_changed(&mut self) {
+        let Some(source) = &self.source else {
+            return;
+        };
+        if !source.take_change() {
+            return;
+        }
+        let path = source.get_path().to_string_lossy().to_string();
+        match Animation::read_file(&path) {
+            Ok(animation) => {
+                self.duration = animation.duration;
+                self.ticks_per_second = animation.ticks_per_second;
+                self.channels = animation.channels;
+                log::info!("Reloaded animation {}", path);
+            }
+            Err(e) => log::error!("Keeping the previous animation for {}: {}", path, e),
+        }
     }
 
     pub fn sample(&self, time: f32) -> Pose {
@@ -112,6 +112,10 @@ impl AnimationGraph {
         final_pose
     }
 
+    pub fn get_current_state(&self) -> &str {
+        &self.current_state
+    }
+
     pub fn add_input(&mut self, name: &str, value: f32) {
         self.inputs.insert(name.to_string(), value);
     }
@@ -136,7 +140,8 @@ impl State {
     }
 
     pub fn update(&mut self, delta_time: f32) {
-        for (name, animation) in &self.animations {
+        for (name, animation) in self.animations.iter_mut() {
+            animation.reload_if_changed();
             let time = self.animation_times.entry(name.clone()).or_insert(0.0);
             let cycled = self.animation_cycled.entry(name.clone()).or_insert(false);
             *time += delta_time * animation.ticks_per_second;
@@ -191,10 +196,12 @@ impl State {
         self.animations.insert(animation.name.clone(), animation);
     }
 
+    /// `condition` is evaluated while the scheduler samples poses, which
+    /// may happen on any of its threads.
     pub fn add_transition(
         &mut self,
         to_state: &str,
-        condition: Box<dyn Fn(&HashMap<String, f32>) -> bool>,
+        condition: Box<dyn Fn(&HashMap<String, f32>) -> bool + Send + Sync>,
         transition_time: f32,
     ) {
         self.transitions.push(Transition {
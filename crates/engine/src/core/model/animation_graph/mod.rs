@@ -28,6 +28,6 @@ pub struct State {
 
 pub struct Transition {
     to_state: String,
-    condition: Box<dyn Fn(&HashMap<String, f32>) -> bool>,
+    condition: Box<dyn Fn(&HashMap<String, f32>) -> bool + Send + Sync>,
     transition_time: f32,
 }
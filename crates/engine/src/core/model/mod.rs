@@ -3,9 +3,12 @@ use std::collections::HashMap;
 use cgmath::{Matrix4, Point3, Quaternion, Vector3};
 use russimp_ng::{material::TextureType, scene::Scene};
 
-use crate::core::renderer::{
-    shader::{DynamicVertexArray, Shader},
-    texture::Texture,
+use crate::core::{
+    assets::WatchedPath,
+    renderer::{
+        shader::{DynamicVertexArray, Shader},
+        texture::Texture,
+    },
 };
 
 mod animation;
@@ -17,12 +20,14 @@ mod model_mesh;
 mod pose;
 
 pub struct Model {
+    path: String,
     model: Scene,
     meshes: HashMap<String, ModelMesh>,
     shader: Shader,
     textures: HashMap<TextureType, Texture>,
+    /// Offset from the owning entity, in model units. Scale the entity's
+    /// `Transform` to convert model units to world units.
     pub position: Point3<f32>,
-    scale: f32,
 }
 
 pub struct ModelBuilder {
@@ -77,6 +82,8 @@ pub struct Animation {
     pub duration: f32,
     pub ticks_per_second: f32,
     channels: HashMap<String, Channel>,
+    /// File from `from_file`, for hot reloading.
+    source: Option<WatchedPath>,
 }
 
 #[derive(Clone)]
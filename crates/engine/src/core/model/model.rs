@@ -8,8 +8,9 @@ use russimp_ng::{
 };
 
 use crate::core::renderer::{
+    self,
     line::{Line, LineRenderer},
-    shader::Shader,
+    shader::{embedded_shader, Shader},
     texture::Texture,
 };
 
@@ -30,28 +31,36 @@ impl Model {
                 PostProcess::FlipUVs,
             ],
         )?;
-        let shader: Shader =
-            Shader::new(include_str!("vertex.glsl"), include_str!("fragment.glsl"));
+        let shader: Shader = embedded_shader!("core/model/vertex.glsl", "core/model/fragment.glsl");
         Ok(Model {
+            path: path.to_string(),
             model: scene,
             meshes: HashMap::<String, ModelMesh>::new(),
             shader,
             textures: HashMap::<TextureType, Texture>::new(),
             position: position.into(),
-            scale: 0.01,
         })
     }
 
+    /// Builds the meshes and bones. Textures and vertex buffers are only
+    /// uploaded with a GL context, see `renderer::is_headless`.
     pub fn init(&mut self) {
-        let materials = &self.model.materials;
-        for material in materials {
-            for (tex_type, texture) in &material.textures {
-                let tex = texture.borrow();
-                if let DataContent::Bytes(texture_data) = &tex.data {
-                    let data = image::load_from_memory(texture_data.as_slice()).unwrap();
-                    let texture = Texture::new();
-                    texture.load_from_data(data.width(), data.height(), data.to_rgba8().into_raw());
-                    self.textures.insert(tex_type.clone(), texture);
+        let headless = renderer::is_headless();
+        if !headless {
+            let materials = &self.model.materials;
+            for material in materials {
+                for (tex_type, texture) in &material.textures {
+                    let tex = texture.borrow();
+                    if let DataContent::Bytes(texture_data) = &tex.data {
+                        let data = image::load_from_memory(texture_data.as_slice()).unwrap();
+                        let texture = Texture::new();
+                        texture.load_from_data(
+                            data.width(),
+                            data.height(),
+                            data.to_rgba8().into_raw(),
+                        );
+                        self.textures.insert(tex_type.clone(), texture);
+                    }
                 }
             }
         }
@@ -107,7 +116,9 @@ impl Model {
                 texture_coords.clone(),
                 root_bone,
             );
-            model_mesh.buffer_data();
+            if !headless {
+                model_mesh.buffer_data();
+            }
             self.meshes.insert(mesh.name.clone(), model_mesh);
         }
     }
@@ -164,16 +175,14 @@ impl Model {
             mesh.render(
                 &self.shader,
                 parent_transform * Matrix4::from_translation(self.position.to_vec().into()),
-                Some(self.scale),
+                None,
             );
             unsafe { gl::Enable(gl::CULL_FACE) };
         }
     }
 
     pub fn render_bones(&self, view_projection: &Matrix4<f32>, parent_transform: &Matrix4<f32>) {
-        let root = parent_transform
-            * Matrix4::from_translation(self.position.to_vec())
-            * Matrix4::from_scale(self.scale);
+        let root = parent_transform * Matrix4::from_translation(self.position.to_vec());
         let mut lines: Vec<Line> = Vec::new();
         for mesh in self.meshes.values() {
             if let Some(root_bone) = &mesh.root_bone {
@@ -189,14 +198,12 @@ impl Model {
     }
 
     /// Returns (bone_name, parent_world_pos, bone_world_pos) for every bone, in world space.
-    /// `entity_transform` should be the entity's translation * rotation matrix.
+    /// `entity_transform` should be the entity's world matrix.
     pub fn get_bone_segments(
         &self,
         entity_transform: &Matrix4<f32>,
     ) -> Vec<(String, Point3<f32>, Point3<f32>)> {
-        let root = entity_transform
-            * Matrix4::from_translation(self.position.to_vec())
-            * Matrix4::from_scale(self.scale);
+        let root = entity_transform * Matrix4::from_translation(self.position.to_vec());
         let mut out = Vec::new();
         for mesh in self.meshes.values() {
             if let Some(bone) = &mesh.root_bone {
@@ -224,16 +231,8 @@ impl Model {
         }
     }
 
-    pub fn get_vertices_scaled(&self) -> Vec<[f32; 3]> {
-        self.model
-            .meshes
-            .iter()
-            .flat_map(|mesh| {
-                mesh.vertices
-                    .iter()
-                    .map(|v| [v.x * self.scale, v.y * self.scale, v.z * self.scale])
-            })
-            .collect()
+    pub fn get_path(&self) -> &str {
+        &self.path
     }
 
     pub fn reset_position(&mut self) -> Vector3<f32> {
@@ -249,7 +248,7 @@ impl Model {
                 root_translation += root_bone.apply_pose(pose, true);
             }
         }
-        self.position += root_translation * self.scale;
+        self.position += root_translation;
     }
 
     fn render_child_bones(&self, bone: &Bone, root: cgmath::Matrix4<f32>) -> Vec<Line> {
@@ -1,7 +1,11 @@
 use cgmath::{InnerSpace, Matrix4, Point3, SquareMatrix, Vector3, Vector4};
 use glfw::{Action, MouseButton};
 
-use crate::core::renderer::line::Line;
+use crate::core::{
+    entity::LayerMask,
+    physics::physics_engine::{PhysicsEngine, RayHit},
+    renderer::line::Line,
+};
 
 use super::camera::{Camera, Projection};
 
@@ -28,7 +32,7 @@ impl MousePicker {
         self.projection = projection.get_matrix();
     }
 
-    fn calculate_ray(&mut self) -> Vector3<f32> {
+    fn calculate_ray(&self) -> Vector3<f32> {
         let ray_clip = Vector4::new(0.0, 0.0, -1.0, 1.0);
         let ray_eye = self.projection.invert().unwrap() * ray_clip;
         let ray_eye = Vector4::new(ray_eye.x, ray_eye.y, -1.0, 0.0);
@@ -38,6 +42,17 @@ impl MousePicker {
             .normalize()
     }
 
+    /// Casts the current picking ray against colliders on `layers`, e.g.
+    /// `LayerMask::ALL.without(LayerMask::PLAYER)` to click through the player.
+    pub fn pick(
+        &self,
+        physics_engine: &PhysicsEngine,
+        max_distance: f32,
+        layers: LayerMask,
+    ) -> Option<RayHit> {
+        physics_engine.cast_ray(self.position, self.calculate_ray(), max_distance, layers)
+    }
+
     pub fn handle_event(
         &mut self,
         _: &mut glfw::Glfw,
@@ -1,5 +1,16 @@
+use cgmath::{Point3, Vector3};
 use rapier3d::prelude::*;
 
+use crate::core::entity::LayerMask;
+
+/// Closest collider hit by `PhysicsEngine::cast_ray`.
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub collider: ColliderHandle,
+    pub distance: f32,
+    pub point: Point3<f32>,
+}
+
 pub struct PhysicsEngine {
     pub rigid_bodies: RigidBodySet,
     pub colliders: ColliderSet,
@@ -88,4 +99,69 @@ impl PhysicsEngine {
             self.colliders.insert(collider)
         }
     }
+
+    /// Removes a rigid body along with every collider and joint attached to it.
+    pub fn remove_rigid_body(&mut self, handle: RigidBodyHandle) {
+        self.rigid_bodies.remove(
+            handle,
+            &mut self.island_manager,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            true,
+        );
+    }
+
+    pub fn remove_collider(&mut self, handle: ColliderHandle) {
+        self.colliders.remove(
+            handle,
+            &mut self.island_manager,
+            &mut self.rigid_bodies,
+            true,
+        );
+    }
+
+    /// Moves every collider attached to the body onto `layers`.
+    pub fn set_body_layers(&mut self, handle: RigidBodyHandle, layers: LayerMask) {
+        let Some(rigid_body) = self.rigid_bodies.get(handle) else {
+            return;
+        };
+        for collider in rigid_body.colliders() {
+            if let Some(collider) = self.colliders.get_mut(*collider) {
+                collider.user_data = layers.to_user_data();
+            }
+        }
+    }
+
+    /// Casts a ray against the colliders on any of `layers`. `direction`
+    /// should be normalized so the hit distance is in world units.
+    pub fn cast_ray(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+        layers: LayerMask,
+    ) -> Option<RayHit> {
+        let predicate = |_: ColliderHandle, collider: &Collider| {
+            layers.intersects(LayerMask::from_user_data(collider.user_data))
+        };
+        let filter = QueryFilter::default().predicate(&predicate);
+        let query_pipeline = self.broad_phase.as_query_pipeline(
+            self.narrow_phase.query_dispatcher(),
+            &self.rigid_bodies,
+            &self.colliders,
+            filter,
+        );
+
+        let ray = Ray::new(
+            Vector::new(origin.x, origin.y, origin.z),
+            Vector::new(direction.x, direction.y, direction.z),
+        );
+        let (collider, distance) = query_pipeline.cast_ray(&ray, max_distance, true)?;
+        Some(RayHit {
+            collider,
+            distance,
+            point: origin + direction * distance,
+        })
+    }
 }
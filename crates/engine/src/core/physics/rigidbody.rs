@@ -1,24 +1,82 @@
-use cgmath::Point3;
+use std::error::Error;
+
+use cgmath::{InnerSpace, Point3, Quaternion};
 use glfw::{Glfw, WindowEvent};
 use rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::core::{
-    entity::{component::Component, Entity},
+    entity::{component::Component, Entity, Transform},
     scene::Scene,
+    serialization::SerializableComponent,
 };
 
+/// Differences below this between an entity and its body are float noise
+/// from decomposing the world matrix, not movement.
+const POSE_EPSILON: f32 = 1e-6;
+
 pub struct RigidBody {
     pub rigid_body_handle: RigidBodyHandle,
+    /// World pose of the body after the last two simulation steps. The
+    /// entity is drawn between them according to
+    /// `Scene::get_interpolation_alpha`.
+    previous_pose: Option<Transform>,
+    current_pose: Option<Transform>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RigidBodyData {
+    body_type: BodyTypeData,
+    lock_rotations: bool,
+    colliders: Vec<ColliderData>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum BodyTypeData {
+    Fixed,
+    Dynamic,
+    KinematicPositionBased,
+    KinematicVelocityBased,
+}
+
+/// Only primitive shapes are stored. Trimesh colliders belong to terrain
+/// chunks, which are regenerated rather than loaded.
+#[derive(Serialize, Deserialize)]
+enum ShapeData {
+    Ball {
+        radius: f32,
+    },
+    /// Segment endpoints in the collider's space, so any orientation is kept.
+    Capsule {
+        a: [f32; 3],
+        b: [f32; 3],
+        radius: f32,
+    },
+    Cuboid {
+        half_extents: [f32; 3],
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct ColliderData {
+    shape: ShapeData,
+    /// Pose relative to the body.
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    friction: f32,
+    sensor: bool,
 }
 
 impl RigidBody {
+    /// The collider is put on the entity's layers.
     pub fn new(
         rigid_body_type: RigidBodyType,
         scene: &mut Scene,
         entity: &Entity,
         collider: Option<Collider>,
     ) -> Self {
-        let translation = entity.get_position();
+        let translation = entity.get_world_position();
         let rigid_body_builder = match rigid_body_type {
             RigidBodyType::Fixed => RigidBodyBuilder::fixed(),
             RigidBodyType::Dynamic => RigidBodyBuilder::dynamic(),
@@ -29,20 +87,76 @@ impl RigidBody {
             .translation(Vector::new(translation.x, translation.y, translation.z))
             .build();
         let rigid_body_handle = scene.physics_engine.add_rigid_body(rigid_body);
-        if let Some(collider) = collider {
+        if let Some(mut collider) = collider {
+            collider.user_data = entity.get_layers().to_user_data();
             scene
                 .physics_engine
                 .add_collider(collider, Some(rigid_body_handle));
         }
-        RigidBody { rigid_body_handle }
+        RigidBody::from_handle(rigid_body_handle)
     }
 
+    /// Wraps a body that was already added to `scene.physics_engine`.
+    pub fn from_handle(rigid_body_handle: RigidBodyHandle) -> Self {
+        RigidBody {
+            rigid_body_handle,
+            previous_pose: None,
+            current_pose: None,
+        }
+    }
+
+    /// World pose of the body in the physics world. Scale is taken from
+    /// the entity, since bodies have none.
+    fn get_pose(&self, scene: &Scene, entity: &Entity) -> Transform {
+        let rigidbody = &scene.physics_engine.rigid_bodies[self.rigid_body_handle];
+        let translation = rigidbody.translation();
+        let rotation = rigidbody.rotation();
+        Transform {
+            translation: cgmath::Vector3::new(translation.x, translation.y, translation.z),
+            rotation: Quaternion::new(rotation.w, rotation.x, rotation.y, rotation.z),
+            scale: entity.get_world_transform().scale,
+        }
+    }
+
+    /// Teleports the body. The entity jumps there too instead of being
+    /// interpolated from its old position.
     pub fn set_position<P: Into<Point3<f32>>>(&mut self, scene: &mut Scene, position: P) {
         let position = position.into();
+        self.previous_pose = None;
+        self.current_pose = None;
         let rigid_body = &mut scene.physics_engine.rigid_bodies[self.rigid_body_handle];
         rigid_body.set_translation(Vector::new(position.x, position.y, position.z), true);
     }
 
+    pub fn set_rotation(&mut self, scene: &mut Scene, rotation: Quaternion<f32>) {
+        let rigid_body = &mut scene.physics_engine.rigid_bodies[self.rigid_body_handle];
+        let v = rotation.v;
+        let rotation = rapier3d::math::Rotation::from_xyzw(v.x, v.y, v.z, rotation.s);
+        rigid_body.set_rotation(rotation, true);
+    }
+
+    /// Moves the body to `world`, skipping the parts it already matches so
+    /// resting bodies are not woken up.
+    pub(crate) fn move_to(
+        &mut self,
+        scene: &mut Scene,
+        world: &Transform,
+        position: bool,
+        rotation: bool,
+    ) {
+        let rigid_body = &scene.physics_engine.rigid_bodies[self.rigid_body_handle];
+        let t = rigid_body.translation();
+        let r = rigid_body.rotation();
+        let current = cgmath::Vector3::new(t.x, t.y, t.z);
+        let current_rotation = Quaternion::new(r.w, r.x, r.y, r.z);
+        if position && (world.translation - current).magnitude2() > POSE_EPSILON {
+            self.set_position(scene, world.get_position());
+        }
+        if rotation && 1.0 - world.rotation.dot(current_rotation).abs() > POSE_EPSILON {
+            self.set_rotation(scene, world.rotation);
+        }
+    }
+
     pub fn set_linvel(&mut self, scene: &mut Scene, linvel: cgmath::Vector3<f32>) {
         let rigid_body = &mut scene.physics_engine.rigid_bodies[self.rigid_body_handle];
         rigid_body.set_linvel(Vector::new(linvel.x, linvel.y, linvel.z), true);
@@ -57,13 +171,134 @@ impl RigidBody {
 
 impl Component for RigidBody {
     fn update(&mut self, scene: &mut Scene, entity: &mut Entity, _: f64) {
-        let rigidbody = &scene.physics_engine.rigid_bodies[self.rigid_body_handle];
-        let translation = rigidbody.translation();
-        let rotation = rigidbody.rotation();
-        let quat = cgmath::Quaternion::new(rotation.w, rotation.x, rotation.y, rotation.z);
-        entity.set_position(scene, (translation.x, translation.y, translation.z));
-        entity.set_rotation(scene, quat);
+        // The body lives in world space. The component is detached while it
+        // updates, so this does not write the pose back to the body.
+        let transform = match (&self.previous_pose, &self.current_pose) {
+            (Some(previous), Some(current)) => {
+                previous.lerp(current, scene.get_interpolation_alpha())
+            }
+            _ => self.get_pose(scene, entity),
+        };
+        entity.set_world_transform(scene, transform);
+    }
+
+    fn fixed_update(&mut self, scene: &mut Scene, entity: &mut Entity, _: f64) {
+        let pose = self.get_pose(scene, entity);
+        self.previous_pose = Some(self.current_pose.unwrap_or(pose));
+        self.current_pose = Some(pose);
     }
 
     fn handle_event(&mut self, _: &mut Glfw, _: &mut glfw::Window, _: &WindowEvent) {}
+
+    fn on_removed(&mut self, scene: &mut Scene, _: &mut Entity) {
+        scene
+            .physics_engine
+            .remove_rigid_body(self.rigid_body_handle);
+    }
+}
+
+impl SerializableComponent for RigidBody {
+    const TYPE_NAME: &'static str = "RigidBody";
+
+    fn save(&self, scene: &Scene) -> Result<Value, Box<dyn Error>> {
+        let rigid_body = &scene.physics_engine.rigid_bodies[self.rigid_body_handle];
+        let body_type = match rigid_body.body_type() {
+            RigidBodyType::Fixed => BodyTypeData::Fixed,
+            RigidBodyType::Dynamic => BodyTypeData::Dynamic,
+            RigidBodyType::KinematicPositionBased => BodyTypeData::KinematicPositionBased,
+            RigidBodyType::KinematicVelocityBased => BodyTypeData::KinematicVelocityBased,
+        };
+
+        let mut colliders = Vec::new();
+        for collider_handle in rigid_body.colliders() {
+            let collider = &scene.physics_engine.colliders[*collider_handle];
+            let shape = match collider.shape().as_typed_shape() {
+                TypedShape::Ball(ball) => ShapeData::Ball {
+                    radius: ball.radius,
+                },
+                TypedShape::Capsule(capsule) => {
+                    let (a, b) = (capsule.segment.a, capsule.segment.b);
+                    ShapeData::Capsule {
+                        a: [a.x, a.y, a.z],
+                        b: [b.x, b.y, b.z],
+                        radius: capsule.radius,
+                    }
+                }
+                TypedShape::Cuboid(cuboid) => ShapeData::Cuboid {
+                    half_extents: [
+                        cuboid.half_extents.x,
+                        cuboid.half_extents.y,
+                        cuboid.half_extents.z,
+                    ],
+                },
+                _ => {
+                    return Err(format!(
+                        "Unsupported collider shape {:?} on a RigidBody",
+                        collider.shape().shape_type()
+                    )
+                    .into())
+                }
+            };
+            let pose = collider.position_wrt_parent().copied().unwrap_or_default();
+            let (t, r) = (pose.translation, pose.rotation);
+            colliders.push(ColliderData {
+                shape,
+                translation: [t.x, t.y, t.z],
+                rotation: [r.x, r.y, r.z, r.w],
+                friction: collider.friction(),
+                sensor: collider.is_sensor(),
+            });
+        }
+
+        Ok(serde_json::to_value(RigidBodyData {
+            body_type,
+            lock_rotations: rigid_body
+                .locked_axes()
+                .contains(LockedAxes::ROTATION_LOCKED),
+            colliders,
+        })?)
+    }
+
+    fn load(data: Value, scene: &mut Scene, entity: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        let data: RigidBodyData = serde_json::from_value(data)?;
+        let body_type = match data.body_type {
+            BodyTypeData::Fixed => RigidBodyType::Fixed,
+            BodyTypeData::Dynamic => RigidBodyType::Dynamic,
+            BodyTypeData::KinematicPositionBased => RigidBodyType::KinematicPositionBased,
+            BodyTypeData::KinematicVelocityBased => RigidBodyType::KinematicVelocityBased,
+        };
+        let rigid_body = RigidBody::new(body_type, scene, entity, None);
+        if data.lock_rotations {
+            scene.physics_engine.rigid_bodies[rigid_body.rigid_body_handle]
+                .lock_rotations(true, true);
+        }
+
+        for collider in data.colliders {
+            let builder = match collider.shape {
+                ShapeData::Ball { radius } => ColliderBuilder::ball(radius),
+                ShapeData::Capsule { a, b, radius } => {
+                    ColliderBuilder::capsule_from_endpoints(a.into(), b.into(), radius)
+                }
+                ShapeData::Cuboid { half_extents } => {
+                    ColliderBuilder::cuboid(half_extents[0], half_extents[1], half_extents[2])
+                }
+            };
+            let [x, y, z, w] = collider.rotation;
+            let pose = Pose::from_parts(
+                collider.translation.into(),
+                rapier3d::math::Rotation::from_xyzw(x, y, z, w),
+            );
+            let collider = builder
+                .position(pose)
+                .friction(collider.friction)
+                .sensor(collider.sensor)
+                .user_data(entity.get_layers().to_user_data())
+                .build();
+            scene
+                .physics_engine
+                .add_collider(collider, Some(rigid_body.rigid_body_handle));
+        }
+
+        Ok(rigid_body)
+    }
 }
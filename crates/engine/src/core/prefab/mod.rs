@@ -0,0 +1,39 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use serde_json::Value;
+
+use super::{entity::Transform, serialization::EntityData};
+
+mod prefab;
+
+/// Reusable entity template: an entity subtree with its component
+/// configuration, stored as JSON in the same format `Scene::save` uses for
+/// entities. A prefab may contain instances of other prefabs.
+pub struct Prefab {
+    root: EntityData,
+    /// File the prefab was loaded from or saved to. Only instances of a
+    /// prefab with a path are linked to it and saved as links.
+    path: Option<String>,
+}
+
+/// Prefab files read while building entities, so each one is parsed once
+/// however many instances link to it.
+#[derive(Default)]
+pub(crate) struct PrefabCache {
+    prefabs: HashMap<String, Rc<Prefab>>,
+}
+
+/// Per-instance changes applied by `Prefab::instantiate`.
+#[derive(Clone, Default)]
+pub struct PrefabOverrides {
+    pub name: Option<String>,
+    /// Replaces the prefab root's local transform.
+    pub transform: Option<Transform>,
+    /// Merged into the saved data of the prefab root's components, keyed by
+    /// component type name (e.g. `"PlayerController"`). Objects are merged
+    /// field by field, anything else is replaced.
+    pub components: BTreeMap<String, Value>,
+}
@@ -0,0 +1,200 @@
+use std::{collections::BTreeMap, error::Error, fs, rc::Rc};
+
+use cgmath::Point3;
+use serde_json::Value;
+
+use crate::core::{
+    entity::{Entity, Transform},
+    scene::Scene,
+    serialization::{ComponentRegistry, EntityData, PrefabLink},
+};
+
+use super::{Prefab, PrefabCache, PrefabOverrides};
+
+impl Prefab {
+    /// Captures `entity` and its persistent children, with every component
+    /// registered in the scene's `ComponentRegistry`.
+    pub fn from_entity(entity: &Entity, scene: &Scene) -> Result<Self, Box<dyn Error>> {
+        let mut root = EntityData::from_entity(entity, scene, scene.get_component_registry())?;
+        clear_ids(&mut root);
+        Ok(Self { root, path: None })
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut root: EntityData = serde_json::from_str(&fs::read_to_string(path)?)?;
+        clear_ids(&mut root);
+        Ok(Self {
+            root,
+            path: Some(path.to_string()),
+        })
+    }
+
+    /// Writes the prefab to `path`. Instances created afterwards link to it.
+    pub fn save(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(&self.root)?)?;
+        self.path = Some(path.to_string());
+        Ok(())
+    }
+
+    pub fn get_path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Builds a new entity from the prefab. Add it to the scene (or to a
+    /// parent entity) to get its handles assigned.
+    pub fn instantiate(
+        &self,
+        scene: &mut Scene,
+        overrides: &PrefabOverrides,
+    ) -> Result<Entity, Box<dyn Error>> {
+        let mut entity = scene.with_component_registry(|scene, registry| {
+            let mut prefabs = PrefabCache::new();
+            self.build(scene, registry, &overrides.components, &mut prefabs, 0)
+        })?;
+        if let Some(name) = &overrides.name {
+            entity.set_name(name.clone());
+        }
+        if let Some(transform) = overrides.transform {
+            entity.set_transform(scene, transform);
+            entity.sync_rigid_bodies(scene);
+        }
+        if let Some(path) = &self.path {
+            entity.set_prefab(Some(PrefabLink {
+                path: path.clone(),
+                components: overrides.components.clone(),
+            }));
+        }
+        Ok(entity)
+    }
+
+    /// The entity's components and descendants are marked as coming from
+    /// the prefab.
+    pub(crate) fn build(
+        &self,
+        scene: &mut Scene,
+        registry: &ComponentRegistry,
+        components: &BTreeMap<String, Value>,
+        prefabs: &mut PrefabCache,
+        depth: usize,
+    ) -> Result<Entity, Box<dyn Error>> {
+        let mut root = self.root.clone();
+        for (type_name, patch) in components {
+            match root
+                .components
+                .iter_mut()
+                .find(|component| component.type_name == *type_name)
+            {
+                Some(component) => merge(&mut component.data, patch),
+                None => log::warn!(
+                    "Prefab {:?} has no '{}' component to override",
+                    self.path,
+                    type_name
+                ),
+            }
+        }
+        let mut entity = root.build(scene, registry, prefabs, depth)?;
+        entity.mark_prefab_contents();
+        Ok(entity)
+    }
+}
+
+impl PrefabCache {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the prefab at `path` unless it was read before.
+    pub(crate) fn load(&mut self, path: &str) -> Result<Rc<Prefab>, Box<dyn Error>> {
+        if let Some(prefab) = self.prefabs.get(path) {
+            return Ok(prefab.clone());
+        }
+        let prefab = Rc::new(Prefab::load(path)?);
+        self.prefabs.insert(path.to_string(), prefab.clone());
+        Ok(prefab)
+    }
+}
+
+impl PrefabOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_position<P: Into<Point3<f32>>>(self, position: P) -> Self {
+        self.with_transform(Transform::from_translation(position))
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn with_component(mut self, type_name: &str, data: Value) -> Self {
+        self.components.insert(type_name.to_string(), data);
+        self
+    }
+}
+
+/// Instances get fresh handles from the scene they are added to.
+fn clear_ids(data: &mut EntityData) {
+    data.id = 0;
+    for child in data.children.iter_mut() {
+        clear_ids(child);
+    }
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::core::{
+        entity::Entity,
+        prefab::{Prefab, PrefabOverrides},
+        scene::Scene,
+        serialization::{ComponentRegistry, EntityData},
+    };
+
+    #[test]
+    fn instances_save_what_was_added_to_them() {
+        let mut scene = Scene::new();
+        let registry = ComponentRegistry::with_builtins();
+        let mut cart = Entity::new("cart");
+        cart.add_child(&mut scene, Entity::new("wheel"));
+        let path = env::temp_dir().join(format!("cart-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut prefab = Prefab::from_entity(&cart, &scene).unwrap();
+        prefab.save(path).unwrap();
+
+        let mut instance = prefab
+            .instantiate(&mut scene, &PrefabOverrides::new())
+            .unwrap();
+        instance.add_child(&mut scene, Entity::new("flag"));
+        let data = EntityData::from_entity(&instance, &scene, &registry).unwrap();
+        let names: Vec<&str> = data.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["flag"]);
+
+        let rebuilt = data.into_entity(&mut scene, &registry).unwrap();
+        let names: Vec<String> = rebuilt
+            .get_children()
+            .iter()
+            .map(Entity::get_name)
+            .collect();
+        assert_eq!(names, ["wheel", "flag"]);
+        fs::remove_file(path).unwrap();
+    }
+}
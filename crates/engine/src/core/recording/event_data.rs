@@ -0,0 +1,152 @@
+use glfw::{Action, Modifiers, WindowEvent};
+
+use crate::core::input::{Binding, GamepadEvent};
+
+use super::{RecordedAction, RecordedEvent, RecordedGamepadEvent};
+
+impl RecordedAction {
+    fn from_action(action: Action) -> Self {
+        match action {
+            Action::Press => RecordedAction::Press,
+            Action::Release => RecordedAction::Release,
+            Action::Repeat => RecordedAction::Repeat,
+        }
+    }
+
+    fn to_action(self) -> Action {
+        match self {
+            RecordedAction::Press => Action::Press,
+            RecordedAction::Release => Action::Release,
+            RecordedAction::Repeat => Action::Repeat,
+        }
+    }
+}
+
+impl RecordedEvent {
+    /// `None` for events the engine does not react to.
+    pub(super) fn from_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::Key(key, scancode, action, mods) => RecordedEvent::Key {
+                key: Binding::Key(*key).to_string(),
+                scancode: *scancode,
+                action: RecordedAction::from_action(*action),
+                mods: mods.bits(),
+            },
+            WindowEvent::MouseButton(button, action, mods) => RecordedEvent::MouseButton {
+                button: Binding::Mouse(*button).to_string(),
+                action: RecordedAction::from_action(*action),
+                mods: mods.bits(),
+            },
+            WindowEvent::CursorPos(x, y) => RecordedEvent::CursorPos { x: *x, y: *y },
+            WindowEvent::Scroll(x, y) => RecordedEvent::Scroll { x: *x, y: *y },
+            WindowEvent::Char(c) => RecordedEvent::Char { c: *c },
+            WindowEvent::FramebufferSize(width, height) => RecordedEvent::FramebufferSize {
+                width: *width,
+                height: *height,
+            },
+            WindowEvent::Focus(focused) => RecordedEvent::Focus { focused: *focused },
+            _ => return None,
+        })
+    }
+
+    pub(super) fn to_event(&self) -> Result<WindowEvent, String> {
+        Ok(match self {
+            RecordedEvent::Key {
+                key,
+                scancode,
+                action,
+                mods,
+            } => match key.parse()? {
+                Binding::Key(key) => WindowEvent::Key(
+                    key,
+                    *scancode,
+                    action.to_action(),
+                    Modifiers::from_bits_truncate(*mods),
+                ),
+                _ => return Err(format!("'{}' is not a key", key)),
+            },
+            RecordedEvent::MouseButton {
+                button,
+                action,
+                mods,
+            } => match button.parse()? {
+                Binding::Mouse(button) => WindowEvent::MouseButton(
+                    button,
+                    action.to_action(),
+                    Modifiers::from_bits_truncate(*mods),
+                ),
+                _ => return Err(format!("'{}' is not a mouse button", button)),
+            },
+            RecordedEvent::CursorPos { x, y } => WindowEvent::CursorPos(*x, *y),
+            RecordedEvent::Scroll { x, y } => WindowEvent::Scroll(*x, *y),
+            RecordedEvent::Char { c } => WindowEvent::Char(*c),
+            RecordedEvent::FramebufferSize { width, height } => {
+                WindowEvent::FramebufferSize(*width, *height)
+            }
+            RecordedEvent::Focus { focused } => WindowEvent::Focus(*focused),
+        })
+    }
+}
+
+impl RecordedGamepadEvent {
+    pub(super) fn from_event(event: &GamepadEvent) -> Self {
+        match event {
+            GamepadEvent::Connected { id, name } => RecordedGamepadEvent::Connected {
+                id: *id,
+                name: name.clone(),
+            },
+            GamepadEvent::Disconnected { id } => RecordedGamepadEvent::Disconnected { id: *id },
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            } => RecordedGamepadEvent::Button {
+                id: *id,
+                button: Binding::GamepadButton(*button).to_string(),
+                pressed: *pressed,
+            },
+            GamepadEvent::Axis { id, axis, value } => RecordedGamepadEvent::Axis {
+                id: *id,
+                axis: Binding::GamepadAxis {
+                    axis: *axis,
+                    inverted: false,
+                }
+                .to_string(),
+                value: *value,
+            },
+        }
+    }
+
+    pub(super) fn to_event(&self) -> Result<GamepadEvent, String> {
+        Ok(match self {
+            RecordedGamepadEvent::Connected { id, name } => GamepadEvent::Connected {
+                id: *id,
+                name: name.clone(),
+            },
+            RecordedGamepadEvent::Disconnected { id } => GamepadEvent::Disconnected { id: *id },
+            RecordedGamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            } => match button.parse()? {
+                Binding::GamepadButton(button) => GamepadEvent::Button {
+                    id: *id,
+                    button,
+                    pressed: *pressed,
+                },
+                _ => return Err(format!("'{}' is not a gamepad button", button)),
+            },
+            RecordedGamepadEvent::Axis { id, axis, value } => match axis.parse()? {
+                Binding::GamepadAxis {
+                    axis,
+                    inverted: false,
+                } => GamepadEvent::Axis {
+                    id: *id,
+                    axis,
+                    value: *value,
+                },
+                _ => return Err(format!("'{}' is not a gamepad axis", axis)),
+            },
+        })
+    }
+}
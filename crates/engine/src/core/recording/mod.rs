@@ -0,0 +1,110 @@
+use std::{fs::File, io::BufWriter};
+
+use glfw::WindowEvent;
+use serde::{Deserialize, Serialize};
+
+use super::input::GamepadEvent;
+
+mod event_data;
+mod recording;
+
+/// Version written to the first line of a recording. Files with another
+/// version are rejected on replay.
+const RECORDING_VERSION: u32 = 1;
+
+/// Writes every frame's window events, gamepad events and delta time to a
+/// file, one JSON line per frame, for `Application::replay`.
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+/// A recording read back by `Application::replay`.
+pub struct Replay {
+    frames: std::vec::IntoIter<ReplayFrame>,
+}
+
+/// One recorded frame, fed to the layers instead of live input.
+pub(crate) struct ReplayFrame {
+    pub delta_time: f64,
+    pub events: Vec<WindowEvent>,
+    pub gamepad_events: Vec<GamepadEvent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordingHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedFrame {
+    delta_time: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    events: Vec<RecordedEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    gamepad_events: Vec<RecordedGamepadEvent>,
+}
+
+/// The `glfw::WindowEvent`s the engine reacts to. Keys and buttons use the
+/// names of `input::Binding`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum RecordedEvent {
+    Key {
+        key: String,
+        scancode: i32,
+        action: RecordedAction,
+        mods: i32,
+    },
+    MouseButton {
+        button: String,
+        action: RecordedAction,
+        mods: i32,
+    },
+    CursorPos {
+        x: f64,
+        y: f64,
+    },
+    Scroll {
+        x: f64,
+        y: f64,
+    },
+    Char {
+        c: char,
+    },
+    FramebufferSize {
+        width: i32,
+        height: i32,
+    },
+    Focus {
+        focused: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+enum RecordedAction {
+    Press,
+    Release,
+    Repeat,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum RecordedGamepadEvent {
+    Connected {
+        id: usize,
+        name: String,
+    },
+    Disconnected {
+        id: usize,
+    },
+    Button {
+        id: usize,
+        button: String,
+        pressed: bool,
+    },
+    Axis {
+        id: usize,
+        axis: String,
+        value: f32,
+    },
+}
@@ -0,0 +1,188 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufWriter, Write},
+};
+
+use glfw::WindowEvent;
+
+use crate::core::input::GamepadEvent;
+
+use super::{
+    RecordedEvent, RecordedFrame, RecordedGamepadEvent, Recorder, RecordingHeader, Replay,
+    ReplayFrame, RECORDING_VERSION,
+};
+
+impl Recorder {
+    /// Creates or truncates the file at `path`.
+    pub fn create(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let header = RecordingHeader {
+            version: RECORDING_VERSION,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writeln!(writer)?;
+        Ok(Self { writer })
+    }
+
+    /// Appends one frame. Flushed right away, so a crash keeps every frame
+    /// up to it.
+    pub fn record_frame(
+        &mut self,
+        delta_time: f64,
+        events: &[WindowEvent],
+        gamepad_events: &[GamepadEvent],
+    ) -> Result<(), Box<dyn Error>> {
+        let frame = RecordedFrame {
+            delta_time,
+            events: events
+                .iter()
+                .filter_map(RecordedEvent::from_event)
+                .collect(),
+            gamepad_events: gamepad_events
+                .iter()
+                .map(RecordedGamepadEvent::from_event)
+                .collect(),
+        };
+        serde_json::to_writer(&mut self.writer, &frame)?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Replay {
+    /// Reads a file written by `Recorder`. Events that cannot be restored,
+    /// e.g. keys without a name, are logged and left out.
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+        let header: RecordingHeader =
+            serde_json::from_str(lines.next().ok_or("Recording is empty")?)?;
+        if header.version != RECORDING_VERSION {
+            return Err(format!(
+                "Recording has version {}, expected {}",
+                header.version, RECORDING_VERSION
+            )
+            .into());
+        }
+
+        let mut frames = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let frame: RecordedFrame = serde_json::from_str(line)?;
+            let mut events = Vec::new();
+            for event in frame.events.iter() {
+                match event.to_event() {
+                    Ok(event) => events.push(event),
+                    Err(e) => log::warn!("Skipping event in frame {} of {}: {}", i, path, e),
+                }
+            }
+            let mut gamepad_events = Vec::new();
+            for event in frame.gamepad_events.iter() {
+                match event.to_event() {
+                    Ok(event) => gamepad_events.push(event),
+                    Err(e) => log::warn!("Skipping event in frame {} of {}: {}", i, path, e),
+                }
+            }
+            frames.push(ReplayFrame {
+                delta_time: frame.delta_time,
+                events,
+                gamepad_events,
+            });
+        }
+        Ok(Self {
+            frames: frames.into_iter(),
+        })
+    }
+
+    pub fn remaining_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub(crate) fn next_frame(&mut self) -> Option<ReplayFrame> {
+        self.frames.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use glfw::{Action, GamepadButton, Key, Modifiers, MouseButton, WindowEvent};
+
+    use crate::core::{
+        input::GamepadEvent,
+        recording::{Recorder, Replay},
+    };
+
+    fn path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("{}-{}.jsonl", name, std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn replays_return_the_recorded_frames() {
+        let path = path("recording");
+        let events = [
+            WindowEvent::Key(Key::W, 17, Action::Press, Modifiers::Shift),
+            WindowEvent::MouseButton(MouseButton::Button1, Action::Release, Modifiers::empty()),
+            WindowEvent::CursorPos(12.5, 40.0),
+            // Not needed by the engine, so not recorded.
+            WindowEvent::Close,
+        ];
+        let gamepad_events = [
+            GamepadEvent::Connected {
+                id: 0,
+                name: "pad".to_string(),
+            },
+            GamepadEvent::Button {
+                id: 0,
+                button: GamepadButton::ButtonA,
+                pressed: true,
+            },
+        ];
+        let mut recorder = Recorder::create(&path).unwrap();
+        recorder
+            .record_frame(1.0 / 60.0, &events, &gamepad_events)
+            .unwrap();
+        recorder.record_frame(1.0 / 30.0, &[], &[]).unwrap();
+        drop(recorder);
+
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(replay.remaining_frames(), 2);
+        let frame = replay.next_frame().unwrap();
+        assert_eq!(frame.delta_time, 1.0 / 60.0);
+        assert_eq!(frame.events, events[..3]);
+        assert_eq!(
+            format!("{:?}", frame.gamepad_events),
+            format!("{:?}", gamepad_events)
+        );
+        let frame = replay.next_frame().unwrap();
+        assert_eq!(frame.delta_time, 1.0 / 30.0);
+        assert!(frame.events.is_empty());
+        assert!(replay.next_frame().is_none());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unknown_events_are_skipped_and_other_versions_rejected() {
+        let path = path("old-recording");
+        fs::write(
+            &path,
+            concat!(
+                "{\"version\":1}\n",
+                "{\"delta_time\":0.5,\"events\":[",
+                "{\"type\":\"Key\",\"key\":\"Nope\",\"scancode\":0,\"action\":\"Press\",\"mods\":0},",
+                "{\"type\":\"Focus\",\"focused\":false}]}\n",
+            ),
+        )
+        .unwrap();
+        let mut replay = Replay::open(&path).unwrap();
+        let frame = replay.next_frame().unwrap();
+        assert_eq!(frame.events, [WindowEvent::Focus(false)]);
+
+        fs::write(&path, "{\"version\":0}\n{\"delta_time\":0.5}\n").unwrap();
+        assert!(Replay::open(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
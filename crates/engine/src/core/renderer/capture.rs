@@ -0,0 +1,115 @@
+use std::error::Error;
+
+use gl::types::{GLint, GLvoid};
+use image::{imageops, GrayImage, ImageFormat, RgbImage};
+
+/// How the depth values in a capture were projected, so they can be
+/// written as linear distances.
+#[derive(Clone, Copy, Debug)]
+pub enum DepthProjection {
+    /// Depth is already linear, e.g. in the shadow map.
+    Orthographic,
+    Perspective {
+        near: f32,
+        far: f32,
+    },
+}
+
+/// Writes the colour of `framebuffer` (0 for the window's back buffer) to a
+/// PNG at `path`.
+pub fn save_color(
+    framebuffer: u32,
+    width: u32,
+    height: u32,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut pixels = vec![0u8; (width * height * 3) as usize];
+    read_pixels(
+        framebuffer,
+        width,
+        height,
+        gl::RGB,
+        gl::UNSIGNED_BYTE,
+        &mut pixels,
+    );
+    let mut image =
+        RgbImage::from_raw(width, height, pixels).ok_or("Framebuffer size does not match")?;
+    // GL rows start at the bottom.
+    imageops::flip_vertical_in_place(&mut image);
+    image.save_with_format(path, ImageFormat::Png)?;
+    Ok(())
+}
+
+/// Writes the depth of `framebuffer` to a PNG at `path` as grayscale, black
+/// at the near plane and white at the far plane.
+pub fn save_depth(
+    framebuffer: u32,
+    width: u32,
+    height: u32,
+    projection: DepthProjection,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut depth = vec![0f32; (width * height) as usize];
+    read_pixels(
+        framebuffer,
+        width,
+        height,
+        gl::DEPTH_COMPONENT,
+        gl::FLOAT,
+        &mut depth,
+    );
+    let pixels = depth
+        .into_iter()
+        .map(|depth| (linearize(depth, projection).clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    let mut image =
+        GrayImage::from_raw(width, height, pixels).ok_or("Framebuffer size does not match")?;
+    imageops::flip_vertical_in_place(&mut image);
+    image.save_with_format(path, ImageFormat::Png)?;
+    Ok(())
+}
+
+/// Maps a depth buffer value to 0 at the near plane and 1 at the far plane.
+fn linearize(depth: f32, projection: DepthProjection) -> f32 {
+    match projection {
+        DepthProjection::Orthographic => depth,
+        DepthProjection::Perspective { near, far } => {
+            let ndc = depth * 2.0 - 1.0;
+            let distance = 2.0 * near * far / (far + near - ndc * (far - near));
+            (distance - near) / (far - near)
+        }
+    }
+}
+
+fn read_pixels<T>(
+    framebuffer: u32,
+    width: u32,
+    height: u32,
+    format: u32,
+    data_type: u32,
+    pixels: &mut [T],
+) {
+    unsafe {
+        let mut previous: GLint = 0;
+        gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous);
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+        if format != gl::DEPTH_COMPONENT {
+            gl::ReadBuffer(if framebuffer == 0 {
+                gl::BACK
+            } else {
+                gl::COLOR_ATTACHMENT0
+            });
+        }
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as i32,
+            height as i32,
+            format,
+            data_type,
+            pixels.as_mut_ptr() as *mut GLvoid,
+        );
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous as u32);
+    }
+}
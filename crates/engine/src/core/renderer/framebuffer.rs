@@ -1,4 +1,10 @@
-use super::texture::Texture;
+use std::error::Error;
+
+use super::{
+    capture::{self, DepthProjection},
+    is_headless,
+    texture::Texture,
+};
 
 pub struct FrameBuffer {
     id: u32,
@@ -9,12 +15,15 @@ pub struct FrameBuffer {
 }
 
 impl FrameBuffer {
+    /// Has id 0 when headless, like its textures, and is never bound.
     pub fn new(width: u32, height: u32) -> Self {
         let mut id = 0;
-        unsafe {
-            gl::GenFramebuffers(1, &mut id);
-            gl::BindFramebuffer(gl::FRAMEBUFFER, id);
-            gl::DrawBuffer(gl::COLOR_ATTACHMENT0);
+        if !is_headless() {
+            unsafe {
+                gl::GenFramebuffers(1, &mut id);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+                gl::DrawBuffer(gl::COLOR_ATTACHMENT0);
+            }
         }
         Self {
             id,
@@ -26,6 +35,10 @@ impl FrameBuffer {
     }
 
     pub fn append_depth_texture(&mut self, texture: Texture) {
+        if is_headless() {
+            self.depth_texture = Some(texture);
+            return;
+        }
         self.bind();
         unsafe {
             gl::FramebufferTexture2D(
@@ -41,6 +54,10 @@ impl FrameBuffer {
     }
 
     pub fn append_color_texture(&mut self, texture: Texture) {
+        if is_headless() {
+            self.color_texture = Some(texture);
+            return;
+        }
         self.bind();
         unsafe {
             gl::FramebufferTexture2D(
@@ -75,6 +92,9 @@ impl FrameBuffer {
     }
 
     pub fn depth_only(&self) {
+        if is_headless() {
+            return;
+        }
         self.bind();
         unsafe {
             gl::DrawBuffer(gl::NONE);
@@ -94,10 +114,39 @@ impl FrameBuffer {
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    /// Writes the colour attachment to a PNG.
+    pub fn save_color(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        if self.color_texture.is_none() {
+            return Err("Framebuffer has no colour attachment".into());
+        }
+        if is_headless() {
+            return Err("Nothing is drawn when headless".into());
+        }
+        capture::save_color(self.id, self.width, self.height, path)
+    }
+
+    /// Writes the depth attachment to a PNG as linear grayscale.
+    pub fn save_depth(
+        &self,
+        path: &str,
+        projection: DepthProjection,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.depth_texture.is_none() {
+            return Err("Framebuffer has no depth attachment".into());
+        }
+        if is_headless() {
+            return Err("Nothing is drawn when headless".into());
+        }
+        capture::save_depth(self.id, self.width, self.height, projection, path)
+    }
 }
 
 impl Drop for FrameBuffer {
     fn drop(&mut self) {
+        if self.id == 0 {
+            return;
+        }
         unsafe {
             gl::DeleteFramebuffers(1, &self.id);
         }
@@ -135,4 +184,16 @@ impl ShadowFrameBuffer {
     pub fn get_id(&self) -> u32 {
         self.0.get_id()
     }
+
+    pub fn save_color(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.0.save_color(path)
+    }
+
+    pub fn save_depth(
+        &self,
+        path: &str,
+        projection: DepthProjection,
+    ) -> Result<(), Box<dyn Error>> {
+        self.0.save_depth(path, projection)
+    }
 }
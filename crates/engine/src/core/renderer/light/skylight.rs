@@ -1,5 +1,10 @@
+use std::error::Error;
+
 use cgmath::{ortho, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Vector3, Vector4};
 use glfw::{Glfw, WindowEvent};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::core::{
     camera::{Camera, Projection},
@@ -8,17 +13,32 @@ use crate::core::{
         Entity,
     },
     scene::Scene,
+    serialization::SerializableComponent,
+    utils::DataSource,
 };
 
 const OFFSET: f32 = 150.0;
+/// Default for the `shadow_distance` console variable.
 const SHADOW_DISTANCE: f32 = 50.0;
 
+lazy_static! {
+    /// The `shadow_distance` console variable: how far from the camera
+    /// shadows are drawn.
+    pub(crate) static ref SHADOW_DISTANCE_VARIABLE: DataSource<f32> =
+        DataSource::new(SHADOW_DISTANCE);
+}
+
 pub struct SkyLight {
     position: Point3<f32>,
     light_view: Matrix4<f32>,
     shadow_box: ShadowBox,
 }
 
+#[derive(Serialize, Deserialize)]
+struct SkyLightData {
+    position: [f32; 3],
+}
+
 impl SkyLight {
     pub fn new<P: Into<Point3<f32>>>(position: P) -> Self {
         let position = position.into();
@@ -47,7 +67,7 @@ impl SkyLight {
         let fov = projection.fovy.0;
         let aspect = projection.aspect;
         let near = projection.znear;
-        let far = SHADOW_DISTANCE;
+        let far = SHADOW_DISTANCE_VARIABLE.read();
 
         let nc = camera_pos + forward * near;
         let fc = camera_pos + forward * far;
@@ -75,7 +95,7 @@ impl SkyLight {
         center /= 8.0;
 
         let light_direction = -self.position.to_vec().normalize();
-        let distance = SHADOW_DISTANCE;
+        let distance = far;
         let light_pos = center - light_direction * distance;
 
         let light_up = if light_direction.x.abs() < 0.001 && light_direction.z.abs() < 0.001 {
@@ -128,6 +148,21 @@ impl Component for SkyLight {
     fn handle_event(&mut self, _: &mut Glfw, _: &mut glfw::Window, _: &WindowEvent) {}
 }
 
+impl SerializableComponent for SkyLight {
+    const TYPE_NAME: &'static str = "SkyLight";
+
+    fn save(&self, _: &Scene) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(SkyLightData {
+            position: [self.position.x, self.position.y, self.position.z],
+        })?)
+    }
+
+    fn load(data: Value, _: &mut Scene, _: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        let data: SkyLightData = serde_json::from_value(data)?;
+        Ok(SkyLight::new(data.position))
+    }
+}
+
 #[derive(Debug)]
 struct ShadowBox {
     min_x: f32,
@@ -1,7 +1,8 @@
 use cgmath::{Matrix4, Point3, Vector3};
 use gl::types::*;
 
-use super::{Line, LineRenderer, Shader};
+use super::{Line, LineRenderer};
+use crate::core::renderer::{is_headless, shader::embedded_shader};
 
 use lazy_static::lazy_static;
 use std::sync::Mutex;
@@ -22,10 +23,16 @@ impl Line {
 
 impl LineRenderer {
     fn new() -> Self {
-        let shader = Shader::new(include_str!("vertex.glsl"), include_str!("fragment.glsl"));
+        let shader = embedded_shader!(
+            "core/renderer/line/vertex.glsl",
+            "core/renderer/line/fragment.glsl"
+        );
 
         let mut vao = 0;
         let mut vbo = 0;
+        if is_headless() {
+            return Self { shader, vao, vbo };
+        }
         unsafe {
             gl::GenVertexArrays(1, &mut vao);
             gl::GenBuffers(1, &mut vbo);
@@ -56,6 +63,9 @@ impl LineRenderer {
         color: Vector3<f32>,
         always_on_top: bool,
     ) {
+        if is_headless() {
+            return;
+        }
         let renderer = RENDERER.lock().unwrap();
         unsafe {
             if always_on_top {
@@ -104,6 +114,9 @@ impl LineRenderer {
         color: Vector3<f32>,
         always_on_top: bool,
     ) {
+        if is_headless() {
+            return;
+        }
         let renderer = RENDERER.lock().unwrap();
         unsafe {
             if always_on_top {
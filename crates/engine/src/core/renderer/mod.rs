@@ -1,3 +1,6 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub mod capture;
 pub mod framebuffer;
 pub mod light;
 pub mod line;
@@ -5,3 +8,19 @@ pub mod plane;
 pub mod shader;
 pub mod text;
 pub mod texture;
+
+/// Set for headless applications, which have no GL context.
+static HEADLESS: AtomicBool = AtomicBool::new(false);
+
+/// Whether GL work is skipped. Shaders are then created with id 0, and
+/// meshes, textures and framebuffers are not uploaded.
+pub fn is_headless() -> bool {
+    HEADLESS.load(Ordering::Relaxed)
+}
+
+/// Done by `Application::new_headless` and undone by `Application::new`.
+/// Call it yourself to update a `Scene` without any `Application`, e.g. in
+/// tests.
+pub fn set_headless(headless: bool) {
+    HEADLESS.store(headless, Ordering::Relaxed);
+}
@@ -1,6 +1,6 @@
 use crate::core::{
     primitives::{Position, Region, Size},
-    renderer::shader::{DynamicVertexArray, Shader, VertexAttributes},
+    renderer::shader::{embedded_shader, DynamicVertexArray, VertexAttributes},
 };
 
 use super::{Plane, PlaneBuilder, PlaneRenderer, PlaneVertex};
@@ -14,7 +14,10 @@ lazy_static! {
 impl PlaneRenderer {
     fn new(width: f32, height: f32) -> Self {
         Self {
-            shader: Shader::new(include_str!("vertex.glsl"), include_str!("fragment.glsl")),
+            shader: embedded_shader!(
+                "core/renderer/plane/vertex.glsl",
+                "core/renderer/plane/fragment.glsl"
+            ),
             width,
             height,
         }
@@ -1,10 +1,39 @@
 use cgmath::{Array, Matrix};
 use gl::types::*;
-use std::{ffi::CString, ptr};
+use std::{
+    error::Error,
+    ffi::CString,
+    fs,
+    path::Path,
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::core::assets::WatchedPath;
+
+use super::is_headless;
 
 pub struct Shader {
-    pub id: GLuint,
+    /// Replaced when a hot reload compiles.
+    id: AtomicU32,
+    /// Vertex and fragment source files, for hot reloading.
+    sources: Option<(WatchedPath, WatchedPath)>,
+}
+
+/// Builds a `Shader` from GLSL files given relative to the engine's `src`
+/// directory. The sources are embedded, and the files are watched by
+/// `HotReload` while the engine's source tree is where it was built.
+macro_rules! embedded_shader {
+    ($vertex:literal, $fragment:literal) => {
+        $crate::core::renderer::shader::Shader::embedded(
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/", $vertex)),
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/", $fragment)),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/src/", $vertex),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/src/", $fragment),
+        )
+    };
 }
+pub(crate) use embedded_shader;
 
 pub struct DynamicVertexArray<T> {
     id: GLuint,
@@ -19,22 +48,114 @@ pub trait VertexAttributes {
 }
 
 impl Shader {
+    /// Id 0 without compiling anything when headless. Compile errors are
+    /// logged and leave the id at 0 as well.
     pub fn new(vertex_source: &str, fragment_source: &str) -> Self {
         Shader {
-            id: Shader::create_shader(vertex_source, fragment_source),
+            id: AtomicU32::new(Shader::compile(vertex_source, fragment_source)),
+            sources: None,
+        }
+    }
+
+    /// Like `new`, and reloaded from `vertex_path` and `fragment_path` when
+    /// they change. See `embedded_shader!`.
+    pub fn embedded(
+        vertex_source: &str,
+        fragment_source: &str,
+        vertex_path: &str,
+        fragment_path: &str,
+    ) -> Self {
+        Shader {
+            id: AtomicU32::new(Shader::compile(vertex_source, fragment_source)),
+            sources: Some((
+                WatchedPath::new(vertex_path),
+                WatchedPath::new(fragment_path),
+            )),
+        }
+    }
+
+    /// Reads and compiles the two files, which are reloaded when they
+    /// change.
+    pub fn from_files(vertex_path: &str, fragment_path: &str) -> Result<Self, Box<dyn Error>> {
+        let vertex_source = fs::read_to_string(vertex_path)?;
+        let fragment_source = fs::read_to_string(fragment_path)?;
+        let id = if is_headless() {
+            0
+        } else {
+            Shader::create_shader(&vertex_source, &fragment_source)?
+        };
+        Ok(Shader {
+            id: AtomicU32::new(id),
+            sources: Some((
+                WatchedPath::new(vertex_path),
+                WatchedPath::new(fragment_path),
+            )),
+        })
+    }
+
+    fn compile(vertex_source: &str, fragment_source: &str) -> GLuint {
+        if is_headless() {
+            return 0;
         }
+        Shader::create_shader(vertex_source, fragment_source).unwrap_or_else(|e| {
+            log::error!("{}", e);
+            0
+        })
     }
 
+    pub fn get_id(&self) -> GLuint {
+        self.id.load(Ordering::Relaxed)
+    }
+
+    /// Recompiles from the source files if they changed. A program that
+    /// fails to compile is logged and the previous one stays in use.
+    pub fn reload_if_changed(&self) {
+        let Some((vertex, fragment)) = &self.sources else {
+            return;
+        };
+        if is_headless() {
+            return;
+        }
+        // Both are checked, so a change to each is only seen once.
+        let vertex_changed = vertex.take_change();
+        let fragment_changed = fragment.take_change();
+        if !vertex_changed && !fragment_changed {
+            return;
+        }
+        match Shader::load_program(vertex.get_path(), fragment.get_path()) {
+            Ok(id) => {
+                let previous = self.id.swap(id, Ordering::Relaxed);
+                unsafe {
+                    gl::DeleteProgram(previous);
+                }
+                log::info!("Reloaded shader {}", fragment.get_path().display());
+            }
+            Err(e) => log::error!(
+                "Keeping the previous shader for {}: {}",
+                fragment.get_path().display(),
+                e
+            ),
+        }
+    }
+
+    fn load_program(vertex_path: &Path, fragment_path: &Path) -> Result<GLuint, Box<dyn Error>> {
+        let vertex_source = fs::read_to_string(vertex_path)?;
+        let fragment_source = fs::read_to_string(fragment_path)?;
+        Ok(Shader::create_shader(&vertex_source, &fragment_source)?)
+    }
+
+    /// Checks for a hot reload first.
     pub fn bind(&self) {
+        self.reload_if_changed();
         unsafe {
-            gl::UseProgram(self.id);
+            gl::UseProgram(self.get_id());
         }
     }
 
     pub fn set_uniform_mat4(&self, name: &str, matrix: &cgmath::Matrix4<f32>) {
         unsafe {
             let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = gl::GetUniformLocation(self.get_id(), name.as_ptr());
             gl::UniformMatrix4fv(location, 1, gl::FALSE, matrix.as_ptr());
         }
     }
@@ -42,7 +163,7 @@ impl Shader {
     pub fn set_uniform_mat4_array(&self, name: &str, matrices: &Vec<cgmath::Matrix4<f32>>) {
         unsafe {
             let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = gl::GetUniformLocation(self.get_id(), name.as_ptr());
             gl::UniformMatrix4fv(
                 location,
                 matrices.len() as i32,
@@ -55,7 +176,7 @@ impl Shader {
     pub fn set_uniform_1i(&self, name: &str, value: i32) {
         unsafe {
             let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = gl::GetUniformLocation(self.get_id(), name.as_ptr());
             gl::Uniform1i(location, value);
         }
     }
@@ -63,7 +184,7 @@ impl Shader {
     pub fn set_uniform_1f(&self, name: &str, value: f32) {
         unsafe {
             let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = gl::GetUniformLocation(self.get_id(), name.as_ptr());
             gl::Uniform1f(location, value);
         }
     }
@@ -71,7 +192,7 @@ impl Shader {
     pub fn set_uniform_3f(&self, name: &str, float1: f32, float2: f32, float3: f32) {
         unsafe {
             let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = gl::GetUniformLocation(self.get_id(), name.as_ptr());
             gl::Uniform3f(location, float1, float2, float3);
         }
     }
@@ -79,7 +200,7 @@ impl Shader {
     pub fn set_uniform_4f(&self, name: &str, float1: f32, float2: f32, float3: f32, float4: f32) {
         unsafe {
             let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = gl::GetUniformLocation(self.get_id(), name.as_ptr());
             gl::Uniform4f(location, float1, float2, float3, float4);
         }
     }
@@ -87,97 +208,104 @@ impl Shader {
     pub fn set_uniform_3fv(&self, name: &str, value: &cgmath::Vector3<f32>) {
         unsafe {
             let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name.as_ptr());
+            let location = gl::GetUniformLocation(self.get_id(), name.as_ptr());
             gl::Uniform3fv(location, 1, value.as_ptr());
         }
     }
 
-    pub fn create_shader(vertex_shader_source: &str, fragment_shader_source: &str) -> GLuint {
+    /// Compiles and links a program. On failure nothing is left behind and
+    /// the error holds GL's info log.
+    pub fn create_shader(
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Result<GLuint, String> {
         unsafe {
             // 1. Compile vertex shader
-            let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
-            let c_str_vert = CString::new(vertex_shader_source.as_bytes()).unwrap();
-            gl::ShaderSource(vertex_shader, 1, &c_str_vert.as_ptr(), ptr::null());
-            gl::CompileShader(vertex_shader);
+            let vertex_shader = Shader::compile_stage(gl::VERTEX_SHADER, vertex_shader_source)
+                .map_err(|log| format!("Vertex shader compilation failed\n{}", log))?;
 
-            // 2. Check for vertex shader compilation errors
-            let mut success = gl::FALSE as GLint;
-            let mut info_log = Vec::with_capacity(512);
-            info_log.set_len(512 - 1); // subtract 1 to skip the trailing null character
-            gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetShaderInfoLog(
-                    vertex_shader,
-                    512,
-                    ptr::null_mut(),
-                    info_log.as_mut_ptr() as *mut GLchar,
-                );
-                println!(
-                    "Vertex Shader Compilation failed\n{}",
-                    String::from_utf8_lossy(&info_log)
-                );
-            }
-
-            // 3. Compile fragment shader
-            let fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-            let c_str_frag = CString::new(fragment_shader_source.as_bytes()).unwrap();
-            gl::ShaderSource(fragment_shader, 1, &c_str_frag.as_ptr(), ptr::null());
-            gl::CompileShader(fragment_shader);
-
-            // 4. Check for fragment shader compilation errors
-            gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut success);
-            if success != gl::TRUE as GLint {
-                gl::GetShaderInfoLog(
-                    fragment_shader,
-                    512,
-                    ptr::null_mut(),
-                    info_log.as_mut_ptr() as *mut GLchar,
-                );
-                println!(
-                    "Fragment Shader Compilation failed\n{}",
-                    String::from_utf8_lossy(&info_log)
-                );
-            }
+            // 2. Compile fragment shader
+            let fragment_shader =
+                match Shader::compile_stage(gl::FRAGMENT_SHADER, fragment_shader_source) {
+                    Ok(fragment_shader) => fragment_shader,
+                    Err(log) => {
+                        gl::DeleteShader(vertex_shader);
+                        return Err(format!("Fragment shader compilation failed\n{}", log));
+                    }
+                };
 
-            // 5. Link shaders
+            // 3. Link shaders
             let shader_program = gl::CreateProgram();
             gl::AttachShader(shader_program, vertex_shader);
             gl::AttachShader(shader_program, fragment_shader);
             gl::LinkProgram(shader_program);
 
-            // 6. Check for linking errors
+            // 4. Delete the shaders as they're linked into our program now and no longer necessary
+            gl::DeleteShader(vertex_shader);
+            gl::DeleteShader(fragment_shader);
+
+            // 5. Check for linking errors
+            let mut success = gl::FALSE as GLint;
             gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
             if success != gl::TRUE as GLint {
+                let mut info_log = vec![0u8; 512];
+                let mut length = 0;
                 gl::GetProgramInfoLog(
                     shader_program,
-                    512,
-                    ptr::null_mut(),
+                    info_log.len() as GLsizei,
+                    &mut length,
                     info_log.as_mut_ptr() as *mut GLchar,
                 );
-                println!(
+                gl::DeleteProgram(shader_program);
+                info_log.truncate(length as usize);
+                return Err(format!(
                     "Linking shaders failed\n{}",
                     String::from_utf8_lossy(&info_log)
-                );
+                ));
             }
 
-            // 7. Delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex_shader);
-            gl::DeleteShader(fragment_shader);
+            Ok(shader_program)
+        }
+    }
+
+    /// Returns the shader, or the info log if it does not compile.
+    unsafe fn compile_stage(stage: GLenum, source: &str) -> Result<GLuint, String> {
+        let shader = gl::CreateShader(stage);
+        let c_str = CString::new(source.as_bytes()).map_err(|e| e.to_string())?;
+        gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
+        gl::CompileShader(shader);
 
-            shader_program
+        let mut success = gl::FALSE as GLint;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+        if success != gl::TRUE as GLint {
+            let mut info_log = vec![0u8; 512];
+            let mut length = 0;
+            gl::GetShaderInfoLog(
+                shader,
+                info_log.len() as GLsizei,
+                &mut length,
+                info_log.as_mut_ptr() as *mut GLchar,
+            );
+            gl::DeleteShader(shader);
+            info_log.truncate(length as usize);
+            return Err(String::from_utf8_lossy(&info_log).into_owned());
         }
+        Ok(shader)
     }
 }
 
 impl<T: VertexAttributes + Clone> DynamicVertexArray<T> {
+    /// Allocates nothing when headless.
     pub fn new() -> Self {
         let mut vao = 0;
         let mut vbo = 0;
         let mut ebo = 0;
-        unsafe {
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
-            gl::GenBuffers(1, &mut ebo);
+        if !is_headless() {
+            unsafe {
+                gl::GenVertexArrays(1, &mut vao);
+                gl::GenBuffers(1, &mut vbo);
+                gl::GenBuffers(1, &mut ebo);
+            }
         }
         DynamicVertexArray {
             id: vao,
@@ -188,7 +316,13 @@ impl<T: VertexAttributes + Clone> DynamicVertexArray<T> {
         }
     }
 
+    /// Only keeps the data when headless.
     pub fn buffer_data(&mut self, data: &Vec<T>, indices: &Option<Vec<u32>>) {
+        if is_headless() {
+            self.current_vertex_data = Some(data.to_vec());
+            self.indices = indices.clone();
+            return;
+        }
         self.bind();
         unsafe {
             gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
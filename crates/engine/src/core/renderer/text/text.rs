@@ -2,10 +2,11 @@ use rusttype::gpu_cache::Cache;
 use rusttype::{point, PositionedGlyph, Rect, Scale};
 
 use crate::core::primitives::{Position, Size};
-use crate::core::renderer::shader::{DynamicVertexArray, VertexAttributes};
+use crate::core::renderer::is_headless;
+use crate::core::renderer::shader::{embedded_shader, DynamicVertexArray, VertexAttributes};
 use crate::core::renderer::text::Fonts;
 
-use super::{Font, Shader, Text, TextMesh, TextRenderer, TextVertex, Texture};
+use super::{Font, Text, TextMesh, TextRenderer, TextVertex, Texture};
 
 use lazy_static::lazy_static;
 use std::sync::{Mutex, OnceLock};
@@ -93,7 +94,12 @@ impl Text {
         }
     }
 
+    /// Glyphs are uploaded as they are laid out, so nothing is laid out
+    /// when headless.
     fn layout(&mut self, width: u32) {
+        if is_headless() {
+            return;
+        }
         if self.dirty {
             self.glyphs = self.layout_text(Scale::uniform(self.size), width, &self.content);
             self.dirty = false;
@@ -196,7 +202,10 @@ impl TextRenderer {
     fn new(width: u32, height: u32) -> TextRenderer {
         let cache: Cache<'static> = Cache::builder().dimensions(1024, 1024).build();
 
-        let shader = Shader::new(include_str!("vertex.glsl"), include_str!("fragment.glsl"));
+        let shader = embedded_shader!(
+            "core/renderer/text/vertex.glsl",
+            "core/renderer/text/fragment.glsl"
+        );
         TextRenderer {
             cache,
             shader,
@@ -210,6 +219,9 @@ impl TextRenderer {
     ///
     /// Returns the width and height of the text
     pub fn render(text: &Text) -> (i32, i32) {
+        if is_headless() {
+            return (0, 0);
+        }
         let renderer = RENDERER.lock().unwrap();
         let mut polygon_mode = 0;
         unsafe {
@@ -335,6 +347,9 @@ impl VertexAttributes for TextVertex {
 
 impl Texture {
     pub fn new(width: i32, height: i32) -> Texture {
+        if is_headless() {
+            return Texture { id: 0 };
+        }
         let mut texture_buffer = 0;
         let data = vec![0u8; width as usize * height as usize];
         unsafe {
@@ -371,6 +386,9 @@ impl Texture {
 
 impl Drop for Texture {
     fn drop(&mut self) {
+        if self.id == 0 {
+            return;
+        }
         unsafe {
             gl::DeleteTextures(1, &self.id);
         }
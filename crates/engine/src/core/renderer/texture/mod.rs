@@ -1,11 +1,15 @@
+use std::sync::Mutex;
+
 use gl::types::GLuint;
 
-use crate::core::renderer::shader::Shader;
+use crate::core::{assets::WatchedPath, renderer::shader::Shader};
 
 pub mod texture;
 
 pub struct Texture {
     pub id: GLuint,
+    /// Image file from `load_from_file`, for hot reloading.
+    source: Mutex<Option<WatchedPath>>,
 }
 
 pub struct TextureRenderer {
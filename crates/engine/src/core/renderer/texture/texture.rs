@@ -1,10 +1,16 @@
-use std::path::Path;
+use std::{error::Error, path::Path, sync::Mutex};
 
 use gl::types::{GLint, GLsizei, GLsizeiptr, GLvoid};
+use image::RgbaImage;
 
-use super::{Shader, Texture, TextureRenderer};
+use super::{Texture, TextureRenderer};
+use crate::core::{
+    assets::{HotReload, WatchedPath},
+    renderer::{is_headless, shader::embedded_shader},
+};
 
 impl Texture {
+    /// Has id 0 when headless, and nothing is uploaded to it.
     pub fn new() -> Self {
         let texture = Texture::gen_texture();
         texture
@@ -12,13 +18,21 @@ impl Texture {
 
     fn gen_texture() -> Self {
         let mut id = 0;
-        unsafe {
-            gl::GenTextures(1, &mut id);
+        if !is_headless() {
+            unsafe {
+                gl::GenTextures(1, &mut id);
+            }
+        }
+        Texture {
+            id,
+            source: Mutex::new(None),
         }
-        Texture { id }
     }
 
     pub fn set_as_depth_texture(&self, width: u32, height: u32) {
+        if is_headless() {
+            return;
+        }
         self.bind();
         unsafe {
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
@@ -50,6 +64,9 @@ impl Texture {
     }
 
     pub fn set_as_color_texture(&self, width: u32, height: u32) {
+        if is_headless() {
+            return;
+        }
         self.bind();
         unsafe {
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
@@ -70,13 +87,24 @@ impl Texture {
         }
     }
 
+    /// The file is reloaded when it changes, see `HotReload`. Not read
+    /// when headless.
     pub fn load_from_file(&self, path: &Path) {
-        self.bind();
-        let img = image::open(path)
-            .expect("Image not found")
-            .flipv()
-            .to_rgba8();
+        if is_headless() {
+            return;
+        }
+        let img = Texture::read_image(path).expect("Image not found");
+        self.upload_image(&img);
+        *self.source.lock().unwrap() = Some(WatchedPath::new(path));
+    }
+
+    fn read_image(path: &Path) -> Result<RgbaImage, Box<dyn Error>> {
+        Ok(image::open(path)?.flipv().to_rgba8())
+    }
+
+    fn upload_image(&self, img: &RgbaImage) {
         unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
@@ -97,6 +125,10 @@ impl Texture {
     }
 
     pub fn load_from_data(&self, width: u32, height: u32, data: Vec<u8>) {
+        *self.source.lock().unwrap() = None;
+        if is_headless() {
+            return;
+        }
         self.bind();
         unsafe {
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
@@ -118,7 +150,36 @@ impl Texture {
         Texture::unbind();
     }
 
+    /// Re-reads the image file if it changed. An image that fails to load
+    /// is logged and the previous one stays in use.
+    pub fn reload_if_changed(&self) {
+        // Binding checks this, so it must not lock anything while hot
+        // reloading is off.
+        if !HotReload::is_enabled() {
+            return;
+        }
+        let source = self.source.lock().unwrap();
+        let Some(source) = source.as_ref() else {
+            return;
+        };
+        if !source.take_change() {
+            return;
+        }
+        match Texture::read_image(source.get_path()) {
+            Ok(img) => {
+                self.upload_image(&img);
+                log::info!("Reloaded texture {}", source.get_path().display());
+            }
+            Err(e) => log::error!(
+                "Keeping the previous image for {}: {}",
+                source.get_path().display(),
+                e
+            ),
+        }
+    }
+
     pub fn bind(&self) {
+        self.reload_if_changed();
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
         }
@@ -133,6 +194,9 @@ impl Texture {
 
 impl Drop for Texture {
     fn drop(&mut self) {
+        if self.id == 0 {
+            return;
+        }
         unsafe {
             gl::DeleteTextures(1, &self.id);
         }
@@ -141,7 +205,10 @@ impl Drop for Texture {
 
 impl TextureRenderer {
     pub fn new() -> Self {
-        let shader = Shader::new(include_str!("vertex.glsl"), include_str!("fragment.glsl"));
+        let shader = embedded_shader!(
+            "core/renderer/texture/vertex.glsl",
+            "core/renderer/texture/fragment.glsl"
+        );
         Self { shader }
     }
 
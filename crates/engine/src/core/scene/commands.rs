@@ -0,0 +1,67 @@
+use std::any::TypeId;
+
+use crate::core::entity::{component::Component, Entity, EntityHandle};
+
+use super::{Command, Commands};
+
+impl Commands {
+    pub fn new() -> Self {
+        Self { queue: Vec::new() }
+    }
+
+    /// Queues a new top-level entity and returns its handle. Only entities
+    /// made with `Scene::create_entity` have a handle before they are
+    /// attached, so later commands can refer to them.
+    pub fn spawn(&mut self, entity: Entity) -> EntityHandle {
+        let id = entity.id;
+        self.queue.push(Command::Spawn {
+            entity,
+            parent: None,
+        });
+        id
+    }
+
+    pub fn spawn_child(&mut self, parent: EntityHandle, entity: Entity) -> EntityHandle {
+        let id = entity.id;
+        self.queue.push(Command::Spawn {
+            entity,
+            parent: Some(parent),
+        });
+        id
+    }
+
+    pub fn despawn(&mut self, id: EntityHandle) {
+        self.queue.push(Command::Despawn(id));
+    }
+
+    pub fn add_component<T: Component>(&mut self, id: EntityHandle, component: T) {
+        self.queue
+            .push(Command::AddComponent(id, Box::new(component)));
+    }
+
+    pub fn remove_component<T: Component>(&mut self, id: EntityHandle) {
+        self.queue
+            .push(Command::RemoveComponent(id, TypeId::of::<T>()));
+    }
+
+    pub fn reparent(&mut self, id: EntityHandle, parent: Option<EntityHandle>) {
+        self.queue.push(Command::Reparent { entity: id, parent });
+    }
+
+    pub fn push(&mut self, command: Command) {
+        self.queue.push(command);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Takes every queued command, or `None` if the queue is empty.
+    pub(crate) fn take(&mut self) -> Option<Vec<Command>> {
+        if self.queue.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.queue))
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use crate::core::entity::{Entity, EntityHandle};
+
+use super::SceneIndex;
+
+impl SceneIndex {
+    pub fn new() -> Self {
+        Self {
+            version: None,
+            locations: HashMap::new(),
+        }
+    }
+
+    pub fn is_fresh(&self, version: u64) -> bool {
+        self.version == Some(version)
+    }
+
+    pub fn rebuild(&mut self, entities: &[Entity], version: u64) {
+        self.locations.clear();
+        let mut path = Vec::new();
+        for (i, entity) in entities.iter().enumerate() {
+            path.push(i);
+            self.insert(entity, &mut path);
+            path.pop();
+        }
+        self.version = Some(version);
+    }
+
+    fn insert(&mut self, entity: &Entity, path: &mut Vec<usize>) {
+        self.locations.insert(entity.id, path.clone());
+        for (i, child) in entity.get_children().iter().enumerate() {
+            path.push(i);
+            self.insert(child, path);
+            path.pop();
+        }
+    }
+
+    pub fn get_locations(&self) -> &HashMap<EntityHandle, Vec<usize>> {
+        &self.locations
+    }
+
+    /// Resolves a handle to its entity. Misses entities that are detached
+    /// while they update, as the tree walk does.
+    pub fn locate<'a>(&self, entities: &'a [Entity], id: &EntityHandle) -> Option<&'a Entity> {
+        let (first, rest) = self.locations.get(id)?.split_first()?;
+        entities
+            .get(*first)?
+            .descendant(rest)
+            .filter(|entity| entity.id == *id)
+    }
+
+    pub fn locate_mut<'a>(
+        &self,
+        entities: &'a mut [Entity],
+        id: &EntityHandle,
+    ) -> Option<&'a mut Entity> {
+        let (first, rest) = self.locations.get(id)?.split_first()?;
+        entities
+            .get_mut(*first)?
+            .descendant_mut(rest)
+            .filter(|entity| entity.id == *id)
+    }
+}
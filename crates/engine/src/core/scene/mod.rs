@@ -1,19 +1,101 @@
+use std::{any::TypeId, cell::Cell, collections::HashMap};
+
 use super::{
-    entity::Entity,
+    allocator::HandleAllocator,
+    entity::{component::Component, ComponentStore, Entity, EntityHandle, LayerMask},
+    event::EventBus,
+    input::Input,
     physics::physics_engine::PhysicsEngine,
     renderer::{framebuffer::ShadowFrameBuffer, texture::TextureRenderer},
+    scene_manager::SceneTransition,
+    scheduler::Scheduler,
+    serialization::ComponentRegistry,
+    time::TimeSnapshot,
+    timer::{Tasks, Timers},
 };
 
+mod commands;
+mod index;
 mod scene;
 
 pub struct Scene {
     entities: Vec<Entity>,
+    /// Components of every entity in `entities`, by type.
+    components: ComponentStore,
+    /// Bumped whenever an entity tree changes shape, so `index` knows it
+    /// is stale.
+    version: u64,
     pub physics_engine: PhysicsEngine,
     shadow_fbo: Option<ShadowFrameBuffer>,
     /// Camera-perspective depth capture — terrain only, no water.
     terrain_debug_fbo: Option<ShadowFrameBuffer>,
     /// Camera-perspective depth+colour capture — terrain depth + water on top.
     water_debug_fbo: Option<ShadowFrameBuffer>,
-    texture_renderer: TextureRenderer,
+    /// Created together with the first debug framebuffer, so building a
+    /// scene does not need a GL context.
+    texture_renderer: Option<TextureRenderer>,
     show_shadow_debug: bool,
+    component_registry: ComponentRegistry,
+    commands: Commands,
+    events: EventBus,
+    scheduler: Scheduler,
+    timers: Timers,
+    tasks: Tasks,
+    input: Input,
+    index: SceneIndex,
+    entity_allocator: HandleAllocator,
+    /// Seconds simulated by each fixed step.
+    fixed_timestep: f64,
+    /// Frame time not yet consumed by fixed steps.
+    accumulator: f64,
+    /// Factor applied to the delta of the `Time` passed to `update`.
+    time_scale: f64,
+    /// The application's `Time` as of the last `update`.
+    time: TimeSnapshot,
+    /// Asks components that load asynchronously to do so in a fixed order,
+    /// for replays.
+    deterministic: bool,
+    /// Requests for the `SceneManager`, collected after each update.
+    transitions: Vec<SceneTransition>,
+    /// Layers drawn by the camera passes.
+    render_layers: LayerMask,
+    /// Layers drawn into the shadow map.
+    shadow_layers: LayerMask,
+    /// Layers of the pass being rendered.
+    drawn_layers: Cell<LayerMask>,
+}
+
+/// Where each entity sits in the tree, rebuilt by `Scene::refresh_index`
+/// after structural changes. While stale, lookups fall back to walking the
+/// tree.
+struct SceneIndex {
+    /// `Scene::version` at the last rebuild.
+    version: Option<u64>,
+    /// Child indices leading from `Scene::entities` to each entity.
+    locations: HashMap<EntityHandle, Vec<usize>>,
+}
+
+/// A structural change recorded during `Component::update`, when the
+/// entity being updated (and its ancestors) are detached from the scene.
+pub enum Command {
+    Spawn {
+        entity: Entity,
+        parent: Option<EntityHandle>,
+    },
+    Despawn(EntityHandle),
+    AddComponent(EntityHandle, Box<dyn Component>),
+    RemoveComponent(EntityHandle, TypeId),
+    /// Moves an entity under a new parent (or to the top level for `None`).
+    /// The local position and rotation are kept as they are.
+    Reparent {
+        entity: EntityHandle,
+        parent: Option<EntityHandle>,
+    },
+}
+
+/// Queue of `Command`s, reachable from components through
+/// `Scene::get_commands_mut` and applied by `Scene::apply_commands` at the
+/// end of `Scene::update`.
+pub struct Commands {
+    queue: Vec<Command>,
 }
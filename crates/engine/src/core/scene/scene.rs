@@ -1,44 +1,105 @@
+use std::{cell::Cell, error::Error, fs};
+
 use cgmath::{Matrix4, SquareMatrix};
-use glfw::{Action, Glfw, Key, WindowEvent};
+use glfw::{CursorMode, Glfw, WindowEvent};
 
 use crate::core::{
+    allocator::HandleAllocator,
     entity::{
-        component::{camera_component::CameraComponent, Component},
-        Entity, EntityHandle,
+        component::{
+            animation_component::AnimationComponent, camera_component::CameraComponent, Component,
+        },
+        query::{self, Access, QueryData, QueryFilter, ReadOnlyQueryData, With},
+        ComponentStore, Entity, EntityHandle, LayerMask,
     },
+    event::EventBus,
+    input::{GamepadEvent, Input},
     physics::physics_engine::PhysicsEngine,
+    prefab::PrefabCache,
     renderer::{
+        self,
         framebuffer::{FrameBuffer, ShadowFrameBuffer},
         light::skylight::SkyLight,
         texture::TextureRenderer,
     },
+    scene_manager::SceneTransition,
+    scheduler::{Scheduler, Stage, SystemContext},
+    serialization::{ComponentRegistry, EntityData, SceneData},
+    time::{Time, TimeSnapshot},
+    timer::{Tasks, Timers},
     window::Window,
 };
 
-use super::Scene;
+use super::{Command, Commands, Scene, SceneIndex};
+
+const DEFAULT_FIXED_TIMESTEP: f64 = 1.0 / 60.0;
+/// Fixed steps allowed per `update`. A frame that took longer drops the
+/// rest of its backlog instead of making the next frame slower still.
+const MAX_FIXED_STEPS: u32 = 8;
 
 impl Scene {
     pub fn new() -> Self {
+        let mut scheduler = Scheduler::new();
+        scheduler.add_system::<&mut AnimationComponent, _>(
+            Stage::Animation,
+            "animation_sampling",
+            |animation: &mut AnimationComponent, context: SystemContext| {
+                animation.sample(context.delta_time as f32)
+            },
+        );
+
         Scene {
             entities: Vec::new(),
+            components: ComponentStore::new(),
+            version: 0,
             physics_engine: PhysicsEngine::new(),
             shadow_fbo: None,
             terrain_debug_fbo: None,
             water_debug_fbo: None,
-            texture_renderer: TextureRenderer::new(),
+            texture_renderer: None,
             show_shadow_debug: false,
+            component_registry: ComponentRegistry::with_builtins(),
+            commands: Commands::new(),
+            events: EventBus::new(),
+            scheduler,
+            timers: Timers::new(),
+            tasks: Tasks::new(),
+            input: Input::default(),
+            index: SceneIndex::new(),
+            entity_allocator: HandleAllocator::new(),
+            fixed_timestep: DEFAULT_FIXED_TIMESTEP,
+            accumulator: 0.0,
+            time_scale: 1.0,
+            time: Time::new().get_snapshot(),
+            deterministic: false,
+            transitions: Vec::new(),
+            render_layers: LayerMask::ALL,
+            shadow_layers: LayerMask::ALL.without(LayerMask::DEBUG),
+            drawn_layers: Cell::new(LayerMask::ALL),
         }
     }
 
+    /// Does nothing when headless.
     pub fn add_shadow_map(&mut self, width: u32, height: u32) {
+        if renderer::is_headless() {
+            return;
+        }
         self.shadow_fbo = Some(ShadowFrameBuffer::new(width, height));
+        self.texture_renderer
+            .get_or_insert_with(TextureRenderer::new);
     }
 
     /// Create the camera-perspective debug FBOs used by F10.
-    /// `width`/`height` should match the window dimensions.
+    /// `width`/`height` should match the window dimensions. Does nothing
+    /// when headless.
     pub fn add_debug_maps(&mut self, width: u32, height: u32) {
+        if renderer::is_headless() {
+            return;
+        }
         self.terrain_debug_fbo = Some(ShadowFrameBuffer::new(width, height));
         self.water_debug_fbo   = Some(ShadowFrameBuffer::new(width, height));
+        self.texture_renderer
+            .get_or_insert_with(TextureRenderer::new);
     }
 
     /// Returns whether the currently-bound framebuffer allows water rendering.
@@ -73,18 +134,181 @@ impl Scene {
             .map_or(false, |fbo| fbo.get_id() == bound)
     }
 
-    pub fn update(&mut self, delta_time: f64) {
-        self.physics_engine.update((delta_time as f32).min(1.0 / 20.0));
+    /// Advances the simulation by as many fixed steps as fit into the
+    /// accumulated frame time, then runs timers and tasks and the
+    /// scheduler's stages, with the per-frame `update` pass at the start of
+    /// `Stage::Gameplay`, and delivers the events published during all of
+    /// them. The time's scaled delta is scaled by the scene's time scale
+    /// first. Input presses and releases are cleared at the end.
+    pub fn update(&mut self, time: &Time) {
+        self.time = time.get_snapshot();
+        let delta_time = time.get_delta() * self.time_scale;
+        if self.input.just_pressed("toggle_shadow_debug") {
+            self.show_shadow_debug = !self.show_shadow_debug;
+        }
+        self.run_added_hooks();
+        self.refresh_index();
+        self.accumulator += delta_time;
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_timestep {
+            if steps == MAX_FIXED_STEPS {
+                self.accumulator %= self.fixed_timestep;
+                break;
+            }
+            self.fixed_update();
+            self.accumulator -= self.fixed_timestep;
+            steps += 1;
+        }
+        // Frame delays count frames of game time, so they wait while the
+        // scene or the application's `Time` is paused.
+        if delta_time > 0.0 {
+            Timers::advance(self, delta_time);
+            Tasks::advance(self, delta_time);
+        }
+        let context = SystemContext { delta_time };
+        Scheduler::run_stage(self, Stage::Input, context);
+        Scheduler::run_stage(self, Stage::Animation, context);
+        Scheduler::run_stage(self, Stage::PhysicsSync, context);
         for i in 0..self.entities.len() {
-            let mut entity = self.entities.remove(i);
+            // Swapped with a placeholder rather than removed, so the other
+            // entities keep their positions and the index stays valid.
+            let mut entity = std::mem::replace(&mut self.entities[i], Entity::new(""));
             entity.update(self, delta_time);
-            self.entities.insert(i, entity);
+            self.entities[i] = entity;
         }
+        Scheduler::run_stage(self, Stage::Gameplay, context);
+        Scheduler::run_stage(self, Stage::RenderPrep, context);
+        self.apply_commands();
+        self.dispatch_events();
+        self.run_added_hooks();
+        self.propagate_transforms();
+        self.refresh_index();
+        self.input.end_frame();
     }
 
-    pub fn render(&self, window: &Window) {
-        let parent_transform = Matrix4::identity();
+    /// One simulation step: physics advances first, then every component's
+    /// `fixed_update` sees the new state.
+    fn fixed_update(&mut self) {
+        let fixed_delta = self.fixed_timestep;
+        self.physics_engine.update(fixed_delta as f32);
+        for i in 0..self.entities.len() {
+            let mut entity = std::mem::replace(&mut self.entities[i], Entity::new(""));
+            entity.fixed_update(self, fixed_delta);
+            self.entities[i] = entity;
+        }
+        self.apply_commands();
+    }
+
+    pub fn get_fixed_timestep(&self) -> f64 {
+        self.fixed_timestep
+    }
+
+    /// Sets the length of a simulation step in seconds (1/60 by default).
+    pub fn set_fixed_timestep(&mut self, fixed_timestep: f64) {
+        assert!(fixed_timestep > 0.0, "Fixed timestep must be positive");
+        self.fixed_timestep = fixed_timestep;
+    }
+
+    /// Frame timing and statistics from the application. Its delta does not
+    /// include the scene's own time scale.
+    pub fn get_time(&self) -> TimeSnapshot {
+        self.time
+    }
+
+    pub fn get_time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Speeds the scene's simulation, timers and tasks up or slows them
+    /// down. 0 pauses them; components and systems still update, with a
+    /// `delta_time` of 0.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        assert!(time_scale >= 0.0, "Time scale must not be negative");
+        self.time_scale = time_scale;
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Makes components that load in the background, like `Terrain`, add
+    /// their results in the same order and on the same frames every run, so
+    /// a replayed recording creates the same entities. Set it before adding
+    /// them; it costs frame time while they load.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
 
+    /// How far the current frame lies between the last two simulation
+    /// steps, from 0 to 1. Used to interpolate what physics moved.
+    pub fn get_interpolation_alpha(&self) -> f32 {
+        (self.accumulator / self.fixed_timestep) as f32
+    }
+
+    /// Runs `Component::on_added` for components stored since the last
+    /// pass, e.g. through `Commands::add_component`. Repeats while the
+    /// hooks themselves add components.
+    fn run_added_hooks(&mut self) {
+        while self.components.take_unadded() {
+            for i in 0..self.entities.len() {
+                let mut entity = std::mem::replace(&mut self.entities[i], Entity::new(""));
+                entity.run_added_hooks(self);
+                self.entities[i] = entity;
+            }
+        }
+    }
+
+    /// Brings every entity's world matrix up to date after parents moved.
+    /// Runs at the end of `update`, before the scene is rendered.
+    pub fn propagate_transforms(&mut self) {
+        for entity in self.entities.iter_mut() {
+            entity.propagate_transforms();
+        }
+    }
+
+    /// Rebuilds the index of entity locations if any entity tree changed
+    /// since the last rebuild. Runs at the start and end of `update`; call
+    /// it after building a scene by hand to get indexed lookups before the
+    /// first update.
+    pub fn refresh_index(&mut self) {
+        if !self.index.is_fresh(self.version) {
+            self.index.rebuild(&self.entities, self.version);
+        }
+    }
+
+    /// Marks the index stale after an entity tree changed shape.
+    pub(crate) fn mark_changed(&mut self) {
+        self.version += 1;
+    }
+
+    /// The components of every entity in the scene, stored by type.
+    pub fn get_component_store(&self) -> &ComponentStore {
+        &self.components
+    }
+
+    pub(crate) fn get_component_store_mut(&mut self) -> &mut ComponentStore {
+        &mut self.components
+    }
+
+    fn render_entities(&self, view_projection: &Matrix4<f32>, layers: LayerMask) {
+        self.drawn_layers.set(layers);
+        for entity in self.entities.iter() {
+            entity.render(self, view_projection, layers);
+        }
+    }
+
+    fn render_entities_transparent(&self, view_projection: &Matrix4<f32>, layers: LayerMask) {
+        self.drawn_layers.set(layers);
+        for entity in self.entities.iter() {
+            entity.render_transparent(self, view_projection, layers);
+        }
+    }
+
+    /// Does nothing for a headless window.
+    pub fn render(&self, window: &Window) {
+        if window.is_headless() {
+            return;
+        }
         // Shadow Pass
         if let Some(shadow_fbo) = &self.shadow_fbo {
             if let Some(skylight) = self.get_component::<SkyLight>() {
@@ -95,9 +319,7 @@ impl Scene {
                 }
                 shadow_fbo.bind();
                 window.clear_mask(gl::DEPTH_BUFFER_BIT | gl::COLOR_BUFFER_BIT);
-                for entity in self.entities.iter() {
-                    entity.render(self, &light_projection, parent_transform);
-                }
+                self.render_entities(&light_projection, self.shadow_layers);
                 FrameBuffer::unbind();
                 window.reset_viewport();
             }
@@ -114,15 +336,11 @@ impl Scene {
                     texture.bind();
                 }
             }
-            for entity in self.entities.iter() {
-                entity.render(self, &view_projection, parent_transform);
-            }
+            self.render_entities(&view_projection, self.render_layers);
             // Transparent pass — runs after every entity's opaque geometry is
             // in the depth buffer, so water with depth-write enabled correctly
             // depth-tests against the player and other dynamic geometry.
-            for entity in self.entities.iter() {
-                entity.render_transparent(self, &view_projection, parent_transform);
-            }
+            self.render_entities_transparent(&view_projection, self.render_layers);
         }
 
         // ── Debug overlay (F10) ───────────────────────────────────────────
@@ -136,9 +354,7 @@ impl Scene {
             if let (Some(tfbo), Some(vp)) = (&self.terrain_debug_fbo, vp) {
                 tfbo.bind();
                 window.clear_mask(gl::DEPTH_BUFFER_BIT | gl::COLOR_BUFFER_BIT);
-                for entity in self.entities.iter() {
-                    entity.render(self, &vp, parent_transform);
-                }
+                self.render_entities(&vp, self.render_layers);
                 FrameBuffer::unbind();
                 window.reset_viewport();
             }
@@ -147,16 +363,16 @@ impl Scene {
             if let (Some(wfbo), Some(vp)) = (&self.water_debug_fbo, vp) {
                 wfbo.bind();
                 window.clear_mask(gl::DEPTH_BUFFER_BIT | gl::COLOR_BUFFER_BIT);
-                for entity in self.entities.iter() {
-                    entity.render(self, &vp, parent_transform);
-                }
-                for entity in self.entities.iter() {
-                    entity.render_transparent(self, &vp, parent_transform);
-                }
+                self.render_entities(&vp, self.render_layers);
+                self.render_entities_transparent(&vp, self.render_layers);
                 FrameBuffer::unbind();
                 window.reset_viewport();
             }
 
+            let Some(texture_renderer) = &self.texture_renderer else {
+                return;
+            };
+
             // ── 2 × 2 panel layout on the right half of the screen ───────
             //
             //   x = 0.5 … 0.75           x = 0.75 … 1.0
@@ -170,27 +386,293 @@ impl Scene {
             //
             if let Some(tfbo) = &self.terrain_debug_fbo {
                 if let Some(tex) = tfbo.get_depth_texture() {
-                    self.texture_renderer.render_depth(tex, 0.5, 0.5, 0.25, 0.5);
+                    texture_renderer.render_depth(tex, 0.5, 0.5, 0.25, 0.5);
                 }
             }
             if let Some(wfbo) = &self.water_debug_fbo {
                 if let Some(tex) = wfbo.get_depth_texture() {
-                    self.texture_renderer.render_depth(tex, 0.5, 0.0, 0.25, 0.5);
+                    texture_renderer.render_depth(tex, 0.5, 0.0, 0.25, 0.5);
                 }
             }
             if let Some(sfbo) = &self.shadow_fbo {
                 if let Some(tex) = sfbo.get_depth_texture() {
-                    self.texture_renderer.render_depth(tex, 0.75, 0.5, 0.25, 0.5);
+                    texture_renderer.render_depth(tex, 0.75, 0.5, 0.25, 0.5);
                 }
                 if let Some(tex) = sfbo.get_color_texture() {
-                    self.texture_renderer.render_color(tex, 0.75, 0.0, 0.25, 0.5);
+                    texture_renderer.render_color(tex, 0.75, 0.0, 0.25, 0.5);
                 }
             }
         }
     }
 
-    pub fn add_entity(&mut self, entity: Entity) {
+    /// Creates an entity that already owns a handle from this scene, for
+    /// when the handle is needed before the entity is attached (e.g. to
+    /// queue further commands for it after `Commands::spawn`).
+    pub fn create_entity(&mut self, name: &str) -> Entity {
+        let mut entity = Entity::new(name);
+        entity.id = EntityHandle::from(self.entity_allocator.allocate());
+        entity
+    }
+
+    /// Gives `entity` and its children handles where they have none yet.
+    pub(crate) fn assign_handles(&mut self, entity: &mut Entity) {
+        entity.assign_handles(&mut self.entity_allocator);
+    }
+
+    /// Adds a top-level entity, assigning handles to it and its children
+    /// where they have none yet, and runs their components' `on_added`.
+    pub fn add_entity(&mut self, mut entity: Entity) -> EntityHandle {
+        entity.assign_handles(&mut self.entity_allocator);
+        entity.store_components(self);
+        entity.run_added_hooks(self);
+        entity.set_parent_matrix(Matrix4::identity());
+        entity.sync_rigid_bodies(self);
+        let id = entity.id;
         self.entities.push(entity);
+        self.mark_changed();
+        id
+    }
+
+    /// Whether `id` still refers to an entity of this scene. Handles of
+    /// despawned entities stay dead even after their slot is reused.
+    pub fn is_alive(&self, id: &EntityHandle) -> bool {
+        self.entity_allocator.is_alive((*id).into())
+    }
+
+    pub(crate) fn free_handle(&mut self, id: EntityHandle) {
+        self.entity_allocator.free(id.into());
+        self.events.remove_entity(id);
+        self.timers.remove_entity(id);
+        self.tasks.remove_entity(id);
+    }
+
+    /// Removes an entity (top-level or nested) together with its children.
+    /// Every removed component gets its `on_removed` hook, which releases
+    /// physics bodies and colliders. Returns whether the entity was found.
+    pub fn remove_entity(&mut self, id: &EntityHandle) -> bool {
+        match self.detach_entity(id) {
+            Some(mut entity) => {
+                entity.teardown(self);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Detaches an entity (top-level or nested) from the scene without
+    /// tearing it down, taking its components out of the scene's store
+    /// with it. Its handle stays reserved until it is torn down.
+    pub fn take_entity(&mut self, id: &EntityHandle) -> Option<Entity> {
+        let mut entity = self.detach_entity(id)?;
+        entity.take_components(self);
+        Some(entity)
+    }
+
+    /// Unlinks an entity from the tree, leaving its components stored.
+    fn detach_entity(&mut self, id: &EntityHandle) -> Option<Entity> {
+        let entity = match self.entities.iter().position(|entity| entity.id == *id) {
+            Some(index) => Some(self.entities.remove(index)),
+            None => self
+                .entities
+                .iter_mut()
+                .find_map(|entity| entity.take_child(id)),
+        };
+        if entity.is_some() {
+            self.mark_changed();
+        }
+        entity
+    }
+
+    /// Runs `f` with mutable access to both the scene and one of its
+    /// entities. The entity's top-level ancestor is detached for the
+    /// duration of the call, the same way `update` detaches entities.
+    pub fn with_entity_mut<R>(
+        &mut self,
+        id: &EntityHandle,
+        f: impl FnOnce(&mut Scene, &mut Entity) -> R,
+    ) -> Option<R> {
+        let index = if self.index.is_fresh(self.version) {
+            *self.index.get_locations().get(id)?.first()?
+        } else {
+            self.entities
+                .iter()
+                .position(|entity| entity.id == *id || entity.get_child(id).is_some())?
+        };
+        let mut root = std::mem::replace(&mut self.entities[index], Entity::new(""));
+        let result = if root.id == *id {
+            Some(f(self, &mut root))
+        } else {
+            root.get_child_mut(id).map(|entity| f(self, entity))
+        };
+        self.entities[index] = root;
+        result
+    }
+
+    /// Asks the `SceneManager` running this scene to switch scenes or
+    /// change overlays once the current update has finished.
+    pub fn request_transition(&mut self, transition: SceneTransition) {
+        self.transitions.push(transition);
+    }
+
+    pub(crate) fn take_transitions(&mut self) -> Vec<SceneTransition> {
+        std::mem::take(&mut self.transitions)
+    }
+
+    /// Structural changes requested while entities are updating. They are
+    /// applied by `apply_commands` once the update pass has finished.
+    pub fn get_commands_mut(&mut self) -> &mut Commands {
+        &mut self.commands
+    }
+
+    pub fn get_input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn get_input_mut(&mut self) -> &mut Input {
+        &mut self.input
+    }
+
+    pub fn get_timers(&self) -> &Timers {
+        &self.timers
+    }
+
+    /// Delayed and repeating callbacks, e.g.
+    /// `scene.get_timers_mut().once(Delay::Seconds(2.0), |scene| ..)`.
+    pub fn get_timers_mut(&mut self) -> &mut Timers {
+        &mut self.timers
+    }
+
+    pub fn get_tasks(&self) -> &Tasks {
+        &self.tasks
+    }
+
+    pub fn get_tasks_mut(&mut self) -> &mut Tasks {
+        &mut self.tasks
+    }
+
+    pub fn get_scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    /// Registering systems, e.g.
+    /// `scene.get_scheduler_mut().add_system::<&mut Wobble, _>(..)`.
+    pub fn get_scheduler_mut(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
+    pub fn get_events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Publishing and subscribing to events, e.g.
+    /// `scene.get_events_mut().publish(Some(entity.id), Landed { .. })`.
+    pub fn get_events_mut(&mut self) -> &mut EventBus {
+        &mut self.events
+    }
+
+    /// Delivers the events published since the last call. Runs at the end
+    /// of `update`; call it directly to flush events outside of a frame.
+    pub fn dispatch_events(&mut self) {
+        EventBus::dispatch(self);
+    }
+
+    /// Applies queued commands in the order they were recorded. Commands
+    /// queued while applying (e.g. from an `on_removed` hook) run as well.
+    pub fn apply_commands(&mut self) {
+        while let Some(commands) = self.commands.take() {
+            for command in commands {
+                self.apply_command(command);
+            }
+        }
+    }
+
+    fn apply_command(&mut self, command: Command) {
+        match command {
+            Command::Spawn { entity, parent } => self.attach_entity(entity, parent),
+            Command::Despawn(id) => {
+                self.remove_entity(&id);
+            }
+            Command::AddComponent(id, component) => {
+                let added = self.with_entity_mut(&id, |scene, entity| {
+                    entity.add_boxed_component(component);
+                    entity.store_pending(scene);
+                });
+                if added.is_none() {
+                    log::warn!("Cannot add component: entity {:?} not found", id);
+                }
+            }
+            Command::RemoveComponent(id, type_id) => {
+                self.with_entity_mut(&id, |scene, entity| {
+                    if let Some(mut component) = entity.take_component(scene, type_id) {
+                        component.on_removed(scene, entity);
+                    }
+                });
+            }
+            Command::Reparent { entity, parent } => {
+                if self.can_reparent(&entity, parent.as_ref()) {
+                    if let Some(entity) = self.take_entity(&entity) {
+                        self.attach_entity(entity, parent);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `id` can move under `parent`, which must exist and not be
+    /// the entity itself or one of its descendants. Warns if not, so the
+    /// tree is left as it is.
+    fn can_reparent(&self, id: &EntityHandle, parent: Option<&EntityHandle>) -> bool {
+        let Some(entity) = self.get_entity(id) else {
+            log::warn!("Cannot reparent: entity {:?} not found", id);
+            return false;
+        };
+        let Some(parent) = parent else {
+            return true;
+        };
+        if parent == id || entity.get_child(parent).is_some() {
+            log::warn!(
+                "Cannot reparent {:?} under itself or its descendant {:?}",
+                id,
+                parent
+            );
+            false
+        } else if self.get_entity(parent).is_none() {
+            log::warn!("Cannot reparent {:?}: parent {:?} not found", id, parent);
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Adds `entity` under `parent`, or at the top level for `None`. If the
+    /// parent no longer exists the entity is torn down instead of leaking
+    /// its physics bodies.
+    fn attach_entity(&mut self, mut entity: Entity, parent: Option<EntityHandle>) {
+        entity.assign_handles(&mut self.entity_allocator);
+        match parent {
+            Some(parent_id) => match self.get_entity(&parent_id).map(Entity::get_world_matrix) {
+                Some(parent_matrix) => {
+                    entity.set_parent_matrix(parent_matrix);
+                    entity.sync_rigid_bodies(self);
+                    entity.store_components(self);
+                    self.mark_changed();
+                    if let Some(parent) = self.get_entity_mut(&parent_id) {
+                        parent.push_child(entity);
+                    }
+                }
+                None => {
+                    log::warn!(
+                        "Parent {:?} of entity {:?} not found, dropping it",
+                        parent_id,
+                        entity.id
+                    );
+                    entity.teardown(self);
+                }
+            },
+            None => {
+                self.add_entity(entity);
+            }
+        }
     }
 
     pub fn handle_event(
@@ -199,36 +681,37 @@ impl Scene {
         window: &mut glfw::Window,
         event: &WindowEvent,
     ) {
-        if let WindowEvent::Key(Key::F10, _, Action::Press, _) = event {
-            self.show_shadow_debug = !self.show_shadow_debug;
-        }
+        self.input.handle_event(event);
         for entity in self.entities.iter_mut() {
-            entity.handle_event(glfw, window, event);
+            entity.handle_event(&mut self.components, glfw, window, event);
         }
+        if let Some(mode) = self.input.take_cursor_mode() {
+            window.set_cursor_mode(mode);
+            if mode == CursorMode::Disabled {
+                // Mouse look reads the offset from the centre.
+                window.set_cursor_pos(0.0, 0.0);
+            }
+        }
+    }
+
+    /// Feeds gamepad state to the scene's `Input`.
+    pub fn handle_gamepad_event(&mut self, event: &GamepadEvent) {
+        self.input.handle_gamepad_event(event);
     }
 
+    /// The first `T` in the scene's store, e.g. the only camera.
     pub fn get_component<T>(&self) -> Option<&T>
     where
         T: Component,
     {
-        for entity in self.entities.iter() {
-            if let Some(component) = entity.get_component::<T>() {
-                return Some(component);
-            }
-        }
-        None
+        self.components.first::<T>()
     }
 
     pub fn get_component_mut<T>(&mut self) -> Option<&mut T>
     where
         T: Component,
     {
-        for entity in self.entities.iter_mut() {
-            if let Some(component) = entity.get_component_mut::<T>() {
-                return Some(component);
-            }
-        }
-        None
+        self.components.first_mut::<T>()
     }
 
     // pub fn get_components<T>(&self) -> Vec<&T>
@@ -248,18 +731,59 @@ impl Scene {
     where
         T: Component,
     {
-        let mut entities = Vec::new();
-        for entity in self.entities.iter() {
-            entities.extend(entity.get_with_own_component::<T>());
-        }
-        entities
+        self.query_ref_filtered::<&Entity, With<T>>()
+    }
+
+    /// Fetches `Q` from every entity, including nested children, e.g.
+    /// `scene.query::<(&ModelComponent, &mut RigidBody)>()`. Components of
+    /// entities detached while they update (see `update`) are left out.
+    /// Panics if `Q` borrows the same component mutably more than once.
+    pub fn query<Q: QueryData>(&mut self) -> Vec<Q::Item<'_>> {
+        self.query_filtered::<Q, ()>()
+    }
+
+    /// Like `query`, restricted by a filter such as `Without<PlayerController>`
+    /// or `(With<ModelComponent>, Without<RigidBody>)`.
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(&mut self) -> Vec<Q::Item<'_>> {
+        Access::validate::<Q>();
+        let paths = self
+            .index
+            .is_fresh(self.version)
+            .then(|| self.index.get_locations());
+        // SAFETY: the store is borrowed mutably along with `self`, and
+        // `Access::validate` rejected aliasing accesses.
+        unsafe { query::collect::<Q, F>(&mut self.components, &self.entities, paths) }
+    }
+
+    /// Read-only query, usable from `render` where only `&Scene` is at hand.
+    pub fn query_ref<Q: ReadOnlyQueryData>(&self) -> Vec<Q::Item<'_>> {
+        self.query_ref_filtered::<Q, ()>()
+    }
+
+    pub fn query_ref_filtered<Q: ReadOnlyQueryData, F: QueryFilter>(&self) -> Vec<Q::Item<'_>> {
+        let store = &self.components as *const ComponentStore as *mut ComponentStore;
+        let paths = self
+            .index
+            .is_fresh(self.version)
+            .then(|| self.index.get_locations());
+        // SAFETY: read-only query data never writes to the store.
+        unsafe { query::collect::<Q, F>(store, &self.entities, paths) }
     }
 
     pub fn get_entities(&self) -> &Vec<Entity> {
         &self.entities
     }
 
+    /// The store and the entity trees, borrowed together for the
+    /// scheduler's systems.
+    pub(crate) fn get_query_source(&mut self) -> (*mut ComponentStore, &[Entity]) {
+        (&mut self.components, &self.entities)
+    }
+
     pub fn get_entity(&self, id: &EntityHandle) -> Option<&Entity> {
+        if self.index.is_fresh(self.version) {
+            return self.index.locate(&self.entities, id);
+        }
         for entity in self.entities.iter() {
             if entity.id == *id {
                 return Some(entity);
@@ -272,6 +796,9 @@ impl Scene {
     }
 
     pub fn get_entity_mut(&mut self, id: &EntityHandle) -> Option<&mut Entity> {
+        if self.index.is_fresh(self.version) {
+            return self.index.locate_mut(&mut self.entities, id);
+        }
         for entity in self.entities.iter_mut() {
             if entity.id == *id {
                 return Some(entity);
@@ -282,4 +809,205 @@ impl Scene {
         }
         None
     }
+
+    /// Resolves a `/`-separated path of entity names from the top level,
+    /// e.g. `"terrain/player"`. Each step takes the first match.
+    pub fn find_by_path(&self, path: &str) -> Option<&Entity> {
+        let mut names = path.split('/').filter(|name| !name.is_empty());
+        let first = names.next()?;
+        let mut entity = self
+            .entities
+            .iter()
+            .find(|entity| entity.get_name() == first)?;
+        for name in names {
+            entity = entity.find_child(name)?;
+        }
+        Some(entity)
+    }
+
+    pub fn find_by_path_mut(&mut self, path: &str) -> Option<&mut Entity> {
+        let mut names = path.split('/').filter(|name| !name.is_empty());
+        let first = names.next()?;
+        let mut entity = self
+            .entities
+            .iter_mut()
+            .find(|entity| entity.get_name() == first)?;
+        for name in names {
+            entity = entity.find_child_mut(name)?;
+        }
+        Some(entity)
+    }
+
+    /// Every entity carrying `tag`, including nested children, in tree order.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<&Entity> {
+        self.query_ref::<&Entity>()
+            .into_iter()
+            .filter(|entity| entity.has_tag(tag))
+            .collect()
+    }
+
+    pub fn get_render_layers(&self) -> LayerMask {
+        self.render_layers
+    }
+
+    /// Layers drawn by the camera passes (all by default).
+    pub fn set_render_layers(&mut self, layers: LayerMask) {
+        self.render_layers = layers;
+    }
+
+    /// E.g. to save the shadow map with `ShadowFrameBuffer::save_depth`.
+    pub fn get_shadow_map(&self) -> Option<&ShadowFrameBuffer> {
+        self.shadow_fbo.as_ref()
+    }
+
+    /// Layers of the pass being rendered, for components that draw their
+    /// descendants themselves, like `Terrain`.
+    pub fn get_drawn_layers(&self) -> LayerMask {
+        self.drawn_layers.get()
+    }
+
+    pub fn get_shadow_layers(&self) -> LayerMask {
+        self.shadow_layers
+    }
+
+    /// Layers that cast shadows (all but `LayerMask::DEBUG` by default).
+    pub fn set_shadow_layers(&mut self, layers: LayerMask) {
+        self.shadow_layers = layers;
+    }
+
+    pub fn get_component_registry(&self) -> &ComponentRegistry {
+        &self.component_registry
+    }
+
+    pub fn get_component_registry_mut(&mut self) -> &mut ComponentRegistry {
+        &mut self.component_registry
+    }
+
+    /// Writes every persistent entity and its registered components to
+    /// `path` as JSON. Unregistered components are left out.
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut entities = Vec::new();
+        for entity in self.entities.iter() {
+            if entity.is_persistent() {
+                entities.push(EntityData::from_entity(
+                    entity,
+                    self,
+                    &self.component_registry,
+                )?);
+            }
+        }
+        let json = serde_json::to_string_pretty(&SceneData { entities })?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Replaces the scene's entities and physics world with the contents of
+    /// a file written by `save`.
+    pub fn load(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let data: SceneData = serde_json::from_str(&fs::read_to_string(path)?)?;
+        self.load_data(data)
+    }
+
+    /// Like `load`, for scene data that was already read, e.g. on a
+    /// background thread by `SceneManager::load_file_async`.
+    ///
+    /// The entities are built into a separate scene first, so the current
+    /// one is left as it was if any of them fails to load.
+    pub fn load_data(&mut self, data: SceneData) -> Result<(), Box<dyn Error>> {
+        let mut staging = Scene::new();
+        staging.deterministic = self.deterministic;
+        let mut prefabs = PrefabCache::new();
+        let mut entities = Vec::new();
+        for entity_data in data.entities {
+            let entity =
+                entity_data.build(&mut staging, &self.component_registry, &mut prefabs, 0)?;
+            entities.push(entity);
+        }
+
+        self.entities.clear();
+        self.components = ComponentStore::new();
+        self.entity_allocator.clear();
+        self.mark_changed();
+        self.physics_engine = staging.physics_engine;
+        self.commands = Commands::new();
+        self.events.reset_entities();
+        self.timers.reset_entities();
+        self.tasks.reset_entities();
+        for entity in entities {
+            self.add_entity(entity);
+        }
+        Ok(())
+    }
+
+    /// Builds one entity of a saved scene and adds it at the top level, for
+    /// loading a scene over several frames.
+    pub(crate) fn load_entity(
+        &mut self,
+        data: EntityData,
+        prefabs: &mut PrefabCache,
+    ) -> Result<EntityHandle, Box<dyn Error>> {
+        let entity = self
+            .with_component_registry(|scene, registry| data.build(scene, registry, prefabs, 0))?;
+        Ok(self.add_entity(entity))
+    }
+
+    /// Loaders need `&mut Scene`, so the registry is taken out for the
+    /// duration of `f`, like entities are during `update`.
+    pub(crate) fn with_component_registry<R>(
+        &mut self,
+        f: impl FnOnce(&mut Scene, &ComponentRegistry) -> R,
+    ) -> R {
+        let registry = std::mem::replace(&mut self.component_registry, ComponentRegistry::new());
+        let result = f(self, &registry);
+        self.component_registry = registry;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::core::{entity::Entity, scene::Scene, time::Time, timer::Delay};
+
+    #[test]
+    fn children_get_handles_when_added() {
+        let mut scene = Scene::new();
+        let mut parent = Entity::new("parent");
+        parent.add_child(&mut scene, Entity::new("child"));
+        let child = parent.get_children()[0].id;
+        assert!(child.is_assigned());
+        assert!(scene.is_alive(&child));
+
+        let parent = scene.add_entity(parent);
+        assert_ne!(parent, child);
+        let children = scene.get_entity(&parent).unwrap().get_children();
+        assert_eq!(children[0].id, child);
+    }
+
+    #[test]
+    fn timers_wait_while_time_is_paused() {
+        let mut scene = Scene::new();
+        let fired = Rc::new(Cell::new(false));
+        let flag = fired.clone();
+        let timer = scene
+            .get_timers_mut()
+            .once(Delay::Frames(2), move |_| flag.set(true));
+
+        let mut time = Time::new();
+        time.set_paused(true);
+        for _ in 0..5 {
+            time.advance(1.0 / 60.0);
+            scene.update(&time);
+        }
+        assert!(!fired.get());
+        assert_eq!(scene.get_timers().get_remaining(timer), Some(2.0));
+
+        time.set_paused(false);
+        for _ in 0..2 {
+            time.advance(1.0 / 60.0);
+            scene.update(&time);
+        }
+        assert!(fired.get());
+    }
 }
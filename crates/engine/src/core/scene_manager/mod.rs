@@ -0,0 +1,49 @@
+use std::{collections::HashMap, error::Error, rc::Rc};
+
+use super::scene::Scene;
+
+mod scene_manager;
+
+type SceneBuilder = Box<dyn Fn() -> Result<Scene, Box<dyn Error>>>;
+type PollLoad = Box<dyn FnMut() -> Option<Result<Scene, Box<dyn Error>>>>;
+
+/// Owns a set of named scenes and decides which of them run: the active
+/// scene, overlays stacked on top of it (a pause menu, a HUD) and, while a
+/// switch waits for a background load, a loading screen in place of the
+/// active scene. Each `Application` runs one between its layers' updates
+/// and renders, see `Application::get_scenes_mut`.
+pub struct SceneManager {
+    builders: HashMap<String, SceneBuilder>,
+    scenes: HashMap<String, Scene>,
+    active: Option<String>,
+    overlays: Vec<Overlay>,
+    loads: Vec<PendingLoad>,
+    /// Scene to make active as soon as its background load finishes.
+    pending_switch: Option<String>,
+    loading_screen: Option<String>,
+    /// Creates the empty scene that `load_file_async` loads into.
+    scene_factory: Rc<dyn Fn() -> Scene>,
+}
+
+struct Overlay {
+    name: String,
+    /// Whether the scenes underneath stop updating and receiving events
+    /// while this overlay is shown. They are still rendered.
+    pauses_below: bool,
+}
+
+struct PendingLoad {
+    name: String,
+    /// Returns the finished scene once the background work is done.
+    poll: PollLoad,
+}
+
+/// A change to the running scenes, requested from inside a scene through
+/// `Scene::request_transition` and applied by the `SceneManager` after
+/// that scene's update.
+pub enum SceneTransition {
+    SwitchTo(String),
+    PushOverlay { name: String, pauses_below: bool },
+    PopOverlay,
+    Unload(String),
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    rc::Rc,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use glfw::{Glfw, WindowEvent};
+
+use crate::core::{
+    input::GamepadEvent,
+    prefab::PrefabCache,
+    scene::Scene,
+    serialization::{EntityData, SceneData},
+    time::Time,
+    window::Window,
+};
+
+use super::{Overlay, PendingLoad, PollLoad, SceneManager, SceneTransition};
+
+/// Time each `update` may spend building the entities of a scene loaded
+/// with `load_file_async`.
+const BUILD_BUDGET: Duration = Duration::from_millis(4);
+
+type Prepared<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+impl SceneManager {
+    pub fn new() -> Self {
+        Self {
+            builders: HashMap::new(),
+            scenes: HashMap::new(),
+            active: None,
+            overlays: Vec::new(),
+            loads: Vec::new(),
+            pending_switch: None,
+            loading_screen: None,
+            scene_factory: Rc::new(Scene::new),
+        }
+    }
+
+    /// Registers how to build the scene called `name`. It is built the
+    /// first time it is loaded, switched to or pushed as an overlay.
+    pub fn register<F>(&mut self, name: &str, builder: F)
+    where
+        F: Fn() -> Result<Scene, Box<dyn Error>> + 'static,
+    {
+        self.builders.insert(name.to_string(), Box::new(builder));
+    }
+
+    /// Adds a scene that was built by hand, replacing any scene of the same
+    /// name.
+    pub fn insert(&mut self, name: &str, scene: Scene) {
+        self.scenes.insert(name.to_string(), scene);
+    }
+
+    /// Sets how the empty scene for `load_file_async` is created, e.g. to
+    /// register extra components with its `ComponentRegistry`.
+    pub fn set_scene_factory<F: Fn() -> Scene + 'static>(&mut self, factory: F) {
+        self.scene_factory = Rc::new(factory);
+    }
+
+    /// Scene shown instead of the active one while `switch_to` waits for a
+    /// background load.
+    pub fn set_loading_screen(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        self.load(name)?;
+        self.loading_screen = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Builds a registered scene. Does nothing if it is already loaded.
+    pub fn load(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        if self.scenes.contains_key(name) {
+            return Ok(());
+        }
+        let builder = self
+            .builders
+            .get(name)
+            .ok_or_else(|| format!("Unknown scene '{}'", name))?;
+        let scene = builder()?;
+        self.scenes.insert(name.to_string(), scene);
+        Ok(())
+    }
+
+    /// Runs `prepare` on a background thread, then `finish` on the main
+    /// thread during a later `update`. Anything touching GL or components
+    /// belongs in `finish`, since scenes cannot leave the main thread.
+    pub fn load_async<T, P, F>(&mut self, name: &str, prepare: P, finish: F)
+    where
+        T: Send + 'static,
+        P: FnOnce() -> Result<T, Box<dyn Error + Send + Sync>> + Send + 'static,
+        F: FnOnce(T) -> Result<Scene, Box<dyn Error>> + 'static,
+    {
+        let receiver = spawn_prepare(prepare);
+        let mut finish = Some(finish);
+        let poll: PollLoad = Box::new(move || match receive(&receiver)? {
+            Ok(data) => finish.take().map(|finish| finish(data)),
+            Err(err) => Some(Err(err)),
+        });
+        self.loads.push(PendingLoad {
+            name: name.to_string(),
+            poll,
+        });
+    }
+
+    /// Reads and parses a file written by `Scene::save` in the background.
+    /// The entities are then built on the main thread a few at a time, so
+    /// each `update` only spends about `BUILD_BUDGET` on them.
+    pub fn load_file_async(&mut self, name: &str, path: &str) {
+        let path = path.to_string();
+        let receiver = spawn_prepare(move || {
+            let data: SceneData = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            Ok(data)
+        });
+        let scene_factory = self.scene_factory.clone();
+        let mut build: Option<SceneBuild> = None;
+        let poll: PollLoad = Box::new(move || {
+            if build.is_none() {
+                match receive(&receiver)? {
+                    Ok(data) => build = Some(SceneBuild::new(scene_factory(), data)),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            match build.as_mut()?.step(BUILD_BUDGET) {
+                Ok(true) => build.take().map(|build| Ok(build.scene)),
+                Ok(false) => None,
+                Err(err) => Some(Err(err)),
+            }
+        });
+        self.loads.push(PendingLoad {
+            name: name.to_string(),
+            poll,
+        });
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.scenes.contains_key(name)
+    }
+
+    pub fn is_loading(&self, name: &str) -> bool {
+        self.loads.iter().any(|load| load.name == name)
+    }
+
+    /// Drops a loaded scene, taking it off the overlay stack as well. If it
+    /// was active, no scene is active afterwards.
+    pub fn unload(&mut self, name: &str) -> Option<Scene> {
+        self.overlays.retain(|overlay| overlay.name != name);
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+        }
+        self.scenes.remove(name)
+    }
+
+    /// Makes `name` the active scene, building it first if needed. If it is
+    /// still loading in the background, the loading screen runs until it is
+    /// ready. The previous scene stays loaded, paused.
+    pub fn switch_to(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        if self.is_loading(name) {
+            self.pending_switch = Some(name.to_string());
+            return Ok(());
+        }
+        self.load(name)?;
+        self.pending_switch = None;
+        self.active = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Fails if `name` is already on the overlay stack.
+    pub fn push_overlay(&mut self, name: &str, pauses_below: bool) -> Result<(), Box<dyn Error>> {
+        if self.overlays.iter().any(|overlay| overlay.name == name) {
+            return Err(format!("Scene '{}' is already an overlay", name).into());
+        }
+        self.load(name)?;
+        self.overlays.push(Overlay {
+            name: name.to_string(),
+            pauses_below,
+        });
+        Ok(())
+    }
+
+    /// Removes the top-most overlay and returns its name. The scene stays
+    /// loaded.
+    pub fn pop_overlay(&mut self) -> Option<String> {
+        self.overlays.pop().map(|overlay| overlay.name)
+    }
+
+    pub fn get_active_name(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    pub fn get_active(&self) -> Option<&Scene> {
+        self.scenes.get(self.active.as_ref()?)
+    }
+
+    pub fn get_active_mut(&mut self) -> Option<&mut Scene> {
+        self.scenes.get_mut(self.active.as_ref()?)
+    }
+
+    pub fn get_scene(&self, name: &str) -> Option<&Scene> {
+        self.scenes.get(name)
+    }
+
+    pub fn get_scene_mut(&mut self, name: &str) -> Option<&mut Scene> {
+        self.scenes.get_mut(name)
+    }
+
+    /// Finishes background loads, then updates every running scene from the
+    /// bottom up and applies the transitions they requested.
+    pub fn update(&mut self, time: &Time) {
+        self.poll_loads();
+        for name in self.running_scenes().iter().rev() {
+            let transitions = match self.scenes.get_mut(name) {
+                Some(scene) => {
+                    scene.update(time);
+                    scene.take_transitions()
+                }
+                None => continue,
+            };
+            for transition in transitions {
+                self.apply_transition(transition);
+            }
+        }
+    }
+
+    /// Renders the active scene (or the loading screen), then each overlay
+    /// on top with a fresh depth buffer.
+    pub fn render(&self, window: &Window) {
+        if let Some(scene) = self.base_scene().and_then(|name| self.scenes.get(name)) {
+            scene.render(window);
+        }
+        for overlay in self.overlays.iter() {
+            if let Some(scene) = self.scenes.get(&overlay.name) {
+                window.clear_mask(gl::DEPTH_BUFFER_BIT);
+                scene.render(window);
+            }
+        }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        glfw: &mut Glfw,
+        window: &mut glfw::Window,
+        event: &WindowEvent,
+    ) {
+        for name in self.running_scenes() {
+            if let Some(scene) = self.scenes.get_mut(&name) {
+                scene.handle_event(glfw, window, event);
+            }
+        }
+    }
+
+    pub fn handle_gamepad_event(&mut self, event: &GamepadEvent) {
+        for name in self.running_scenes() {
+            if let Some(scene) = self.scenes.get_mut(&name) {
+                scene.handle_gamepad_event(event);
+            }
+        }
+    }
+
+    /// The scene under the overlays: the loading screen while a switch is
+    /// pending, otherwise the active scene.
+    fn base_scene(&self) -> Option<&str> {
+        match (&self.pending_switch, &self.loading_screen) {
+            (Some(_), Some(loading_screen)) => Some(loading_screen.as_str()),
+            _ => self.active.as_deref(),
+        }
+    }
+
+    /// Scenes that update and receive events, top-most first. An overlay
+    /// that pauses the scenes below ends the list.
+    fn running_scenes(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for overlay in self.overlays.iter().rev() {
+            names.push(overlay.name.clone());
+            if overlay.pauses_below {
+                return names;
+            }
+        }
+        if let Some(name) = self.base_scene() {
+            names.push(name.to_string());
+        }
+        names
+    }
+
+    fn poll_loads(&mut self) {
+        let mut i = 0;
+        while i < self.loads.len() {
+            let Some(result) = (self.loads[i].poll)() else {
+                i += 1;
+                continue;
+            };
+            let load = self.loads.remove(i);
+            match result {
+                Ok(scene) => {
+                    self.scenes.insert(load.name, scene);
+                }
+                Err(err) => {
+                    log::warn!("Failed to load scene '{}': {}", load.name, err);
+                    if self.pending_switch.as_deref() == Some(load.name.as_str()) {
+                        self.pending_switch = None;
+                    }
+                }
+            }
+        }
+        if let Some(name) = &self.pending_switch {
+            if self.scenes.contains_key(name) {
+                self.active = self.pending_switch.take();
+            }
+        }
+    }
+
+    fn apply_transition(&mut self, transition: SceneTransition) {
+        let result = match transition {
+            SceneTransition::SwitchTo(name) => self.switch_to(&name),
+            SceneTransition::PushOverlay { name, pauses_below } => {
+                self.push_overlay(&name, pauses_below)
+            }
+            SceneTransition::PopOverlay => {
+                self.pop_overlay();
+                Ok(())
+            }
+            SceneTransition::Unload(name) => {
+                self.unload(&name);
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            log::warn!("Scene transition failed: {}", err);
+        }
+    }
+}
+
+/// A scene being built from parsed `SceneData`, one top-level entity at a
+/// time.
+struct SceneBuild {
+    scene: Scene,
+    entities: std::vec::IntoIter<EntityData>,
+    prefabs: PrefabCache,
+}
+
+impl SceneBuild {
+    fn new(scene: Scene, data: SceneData) -> Self {
+        Self {
+            scene,
+            entities: data.entities.into_iter(),
+            prefabs: PrefabCache::new(),
+        }
+    }
+
+    /// Builds entities until `budget` has passed, at least one. Returns
+    /// whether all of them are built.
+    fn step(&mut self, budget: Duration) -> Result<bool, Box<dyn Error>> {
+        let start = Instant::now();
+        for data in self.entities.by_ref() {
+            self.scene.load_entity(data, &mut self.prefabs)?;
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+        Ok(self.entities.len() == 0)
+    }
+}
+
+/// Runs `prepare` on a new thread.
+fn spawn_prepare<T, P>(prepare: P) -> Receiver<Prepared<T>>
+where
+    T: Send + 'static,
+    P: FnOnce() -> Prepared<T> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let _ = thread::spawn(move || {
+        let _ = sender.send(prepare());
+    });
+    receiver
+}
+
+/// The result of a `spawn_prepare` thread, once it has finished.
+fn receive<T>(receiver: &Receiver<Prepared<T>>) -> Option<Result<T, Box<dyn Error>>> {
+    match receiver.try_recv() {
+        Ok(result) => Some(result.map_err(|err| err as Box<dyn Error>)),
+        Err(TryRecvError::Empty) => None,
+        Err(TryRecvError::Disconnected) => Some(Err("Scene loader thread stopped".into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, thread, time::Duration};
+
+    use crate::core::{
+        entity::Entity,
+        scene::Scene,
+        serialization::{EntityData, SceneData},
+        time::Time,
+    };
+
+    use super::{SceneBuild, SceneManager};
+
+    fn scene_data(names: &[&str]) -> SceneData {
+        let mut scene = Scene::new();
+        let entities: Vec<Entity> = names.iter().map(|name| scene.create_entity(name)).collect();
+        let entities = entities
+            .iter()
+            .map(|entity| {
+                EntityData::from_entity(entity, &scene, scene.get_component_registry()).unwrap()
+            })
+            .collect();
+        SceneData { entities }
+    }
+
+    #[test]
+    fn overlays_are_pushed_once() {
+        let mut manager = SceneManager::new();
+        manager.insert("pause", Scene::new());
+        assert!(manager.push_overlay("pause", true).is_ok());
+        assert!(manager.push_overlay("pause", true).is_err());
+        assert_eq!(manager.pop_overlay().as_deref(), Some("pause"));
+        assert_eq!(manager.pop_overlay(), None);
+    }
+
+    #[test]
+    fn scenes_are_built_a_few_entities_at_a_time() {
+        let mut build = SceneBuild::new(Scene::new(), scene_data(&["a", "b", "c"]));
+        assert!(!build.step(Duration::ZERO).unwrap());
+        assert_eq!(build.scene.get_entities().len(), 1);
+        assert!(!build.step(Duration::ZERO).unwrap());
+        assert!(build.step(Duration::ZERO).unwrap());
+        let names: Vec<String> = build
+            .scene
+            .get_entities()
+            .iter()
+            .map(Entity::get_name)
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn file_loads_replace_the_scene_when_done() {
+        let path = env::temp_dir().join(format!("menu-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut menu = Scene::new();
+        let start = menu.create_entity("start");
+        menu.add_entity(start);
+        menu.save(path).unwrap();
+
+        let mut manager = SceneManager::new();
+        manager.insert("menu", Scene::new());
+        manager.switch_to("menu").unwrap();
+        manager.load_file_async("menu", path);
+        assert!(manager.is_loading("menu"));
+        assert!(manager.get_active().unwrap().get_entities().is_empty());
+
+        let time = Time::new();
+        for _ in 0..100 {
+            manager.update(&time);
+            if !manager.is_loading("menu") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let entities = manager.get_active().unwrap().get_entities();
+        assert_eq!(entities[0].get_name(), "start");
+        fs::remove_file(path).unwrap();
+    }
+}
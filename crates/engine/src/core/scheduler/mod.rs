@@ -0,0 +1,93 @@
+use std::{
+    sync::mpsc::{Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use super::{
+    entity::{query::Access, ComponentStore, Entity},
+    scene::Scene,
+};
+
+mod scheduler;
+mod thread_pool;
+
+/// Update stages, run in this order by `Scene::update` after the fixed
+/// steps. `Component::update` runs at the start of `Gameplay`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Input,
+    Animation,
+    PhysicsSync,
+    Gameplay,
+    RenderPrep,
+}
+
+/// Per-frame values handed to every system.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemContext {
+    pub delta_time: f64,
+}
+
+/// Runs the systems registered for each `Stage`. Consecutive parallel
+/// systems whose declared accesses do not conflict form a batch, whose
+/// work is split across a thread pool; exclusive systems run alone on the
+/// main thread, so anything touching GL belongs in one of those.
+pub struct Scheduler {
+    systems: Vec<SystemEntry>,
+    thread_count: usize,
+    /// Started on the first batch that has work for more than one thread.
+    pool: Option<ThreadPool>,
+    /// While a stage runs, the scene holds a placeholder with the names of
+    /// the running scheduler's systems, and queues their removal here.
+    running: Vec<String>,
+    removed: Vec<String>,
+}
+
+struct SystemEntry {
+    name: String,
+    stage: Stage,
+    kind: SystemKind,
+}
+
+enum SystemKind {
+    Parallel(Box<dyn ParallelSystem>),
+    Exclusive(Box<dyn FnMut(&mut Scene, SystemContext)>),
+}
+
+/// Work split off a batch. Borrows the scene's components, so it must
+/// finish before `Scheduler::run_stage` returns.
+type Job<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// A query-based system with its component type erased.
+trait ParallelSystem {
+    fn access(&self) -> &Access;
+
+    /// Fetches the system's items from `store` for the enabled entities in
+    /// `entities` and packs them into jobs of at most `chunk_size` items
+    /// each.
+    ///
+    /// # Safety
+    /// No other live reference may alias the components this system's
+    /// access declares, until the jobs are dropped.
+    unsafe fn prepare<'a>(
+        &'a self,
+        store: *mut ComponentStore,
+        entities: &'a [Entity],
+        context: SystemContext,
+        chunk_size: usize,
+    ) -> Vec<Job<'a>>;
+}
+
+/// Fixed set of worker threads for `Job`s.
+struct ThreadPool {
+    sender: Option<Sender<Box<dyn FnOnce() + Send>>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// Results of the tasks `ThreadPool::run` has queued. Waits for the
+/// outstanding ones when dropped, so `run` cannot return or unwind while a
+/// worker still uses the borrows in its jobs.
+struct PendingTasks {
+    receiver: Receiver<thread::Result<()>>,
+    queued: usize,
+}
@@ -0,0 +1,362 @@
+use std::{marker::PhantomData, thread};
+
+use crate::core::{
+    entity::{
+        query::{self, Access, QueryData, QueryFilter},
+        ComponentStore, Entity,
+    },
+    scene::Scene,
+};
+
+use super::{
+    Job, ParallelSystem, Scheduler, Stage, SystemContext, SystemEntry, SystemKind, ThreadPool,
+};
+
+/// Each thread gets about this many jobs per batch, so threads that finish
+/// early can take over work from slower ones.
+const JOBS_PER_THREAD: usize = 4;
+
+struct QuerySystem<Q, F, S> {
+    access: Access,
+    system: S,
+    marker: PhantomData<fn() -> (Q, F)>,
+}
+
+impl<Q, F, S> ParallelSystem for QuerySystem<Q, F, S>
+where
+    Q: QueryData,
+    F: QueryFilter,
+    S: Fn(Q::Item<'_>, SystemContext) + Sync,
+    for<'a> Q::Item<'a>: Send,
+{
+    fn access(&self) -> &Access {
+        &self.access
+    }
+
+    unsafe fn prepare<'a>(
+        &'a self,
+        store: *mut ComponentStore,
+        entities: &'a [Entity],
+        context: SystemContext,
+        max_jobs: usize,
+    ) -> Vec<Job<'a>> {
+        let items = query::collect_enabled::<Q, F>(store, entities, &self.access);
+
+        let chunk_size = items.len().div_ceil(max_jobs.max(1)).max(1);
+        let system = &self.system;
+        let mut jobs: Vec<Job<'a>> = Vec::new();
+        let mut items = items.into_iter().peekable();
+        while items.peek().is_some() {
+            let chunk: Vec<Q::Item<'a>> = items.by_ref().take(chunk_size).collect();
+            jobs.push(Box::new(move || {
+                for item in chunk {
+                    system(item, context);
+                }
+            }));
+        }
+        jobs
+    }
+}
+
+impl Scheduler {
+    /// Uses as many threads as the machine has cores.
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+            thread_count: thread::available_parallelism().map_or(1, |n| n.get()),
+            pool: None,
+            running: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    /// Stands in for the scene's scheduler while it runs, and records what
+    /// the systems change meanwhile.
+    fn placeholder(running: &Scheduler) -> Self {
+        Self {
+            systems: Vec::new(),
+            thread_count: running.thread_count,
+            pool: None,
+            running: running
+                .systems
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect(),
+            removed: Vec::new(),
+        }
+    }
+
+    /// Registers a system that runs once for every enabled entity matching
+    /// `Q`, e.g. `add_system::<&mut AnimationComponent, _>(..)`. `Q` is
+    /// also the system's declared access: systems in the same stage that
+    /// neither write what the other reads nor both write the same component
+    /// run at the same time, and each one's entities are split across the
+    /// thread pool.
+    pub fn add_system<Q, S>(&mut self, stage: Stage, name: &str, system: S)
+    where
+        Q: QueryData + 'static,
+        S: Fn(Q::Item<'_>, SystemContext) + Send + Sync + 'static,
+        for<'a> Q::Item<'a>: Send,
+    {
+        self.add_system_filtered::<Q, (), S>(stage, name, system);
+    }
+
+    /// Like `add_system`, restricted by a filter such as `Without<RigidBody>`.
+    pub fn add_system_filtered<Q, F, S>(&mut self, stage: Stage, name: &str, system: S)
+    where
+        Q: QueryData + 'static,
+        F: QueryFilter + 'static,
+        S: Fn(Q::Item<'_>, SystemContext) + Send + Sync + 'static,
+        for<'a> Q::Item<'a>: Send,
+    {
+        Access::validate::<Q>();
+        let mut access = Access::default();
+        Q::access(&mut access);
+        self.systems.push(SystemEntry {
+            name: name.to_string(),
+            stage,
+            kind: SystemKind::Parallel(Box::new(QuerySystem::<Q, F, S> {
+                access,
+                system,
+                marker: PhantomData,
+            })),
+        });
+    }
+
+    /// Registers a system that runs once per frame on the main thread with
+    /// the whole scene, after the systems registered before it in the same
+    /// stage have finished. Use these for GL calls, the physics world and
+    /// structural changes.
+    pub fn add_exclusive_system(
+        &mut self,
+        stage: Stage,
+        name: &str,
+        system: impl FnMut(&mut Scene, SystemContext) + 'static,
+    ) {
+        self.systems.push(SystemEntry {
+            name: name.to_string(),
+            stage,
+            kind: SystemKind::Exclusive(Box::new(system)),
+        });
+    }
+
+    /// Removes every system registered under `name`. Returns whether there
+    /// was one. Called from a system, the removal takes effect once the
+    /// stage is done.
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|entry| entry.name != name);
+        let mut removed = self.systems.len() != count;
+        if self.running.iter().any(|running| running == name) {
+            self.running.retain(|running| running != name);
+            self.removed.push(name.to_string());
+            removed = true;
+        }
+        removed
+    }
+
+    pub fn get_thread_count(&self) -> usize {
+        self.thread_count
+    }
+
+    /// With 1, every system runs on the main thread. Called from a system,
+    /// it takes effect once the stage is done.
+    pub fn set_thread_count(&mut self, thread_count: usize) {
+        self.thread_count = thread_count.max(1);
+        self.pool = None;
+    }
+
+    /// Runs the scene's systems for `stage`. The scheduler is taken out of
+    /// the scene meanwhile; systems added or removed and thread counts set
+    /// by exclusive systems are applied once the stage is done.
+    pub(crate) fn run_stage(scene: &mut Scene, stage: Stage, context: SystemContext) {
+        let placeholder = Scheduler::placeholder(scene.get_scheduler());
+        let mut scheduler = std::mem::replace(scene.get_scheduler_mut(), placeholder);
+        scheduler.run(scene, stage, context);
+        let changes = std::mem::replace(scene.get_scheduler_mut(), scheduler);
+        let scheduler = scene.get_scheduler_mut();
+        for name in changes.removed.iter() {
+            scheduler.remove_system(name);
+        }
+        scheduler.systems.extend(changes.systems);
+        if changes.thread_count != scheduler.thread_count {
+            scheduler.set_thread_count(changes.thread_count);
+        }
+    }
+
+    fn run(&mut self, scene: &mut Scene, stage: Stage, context: SystemContext) {
+        let mut batch: Vec<usize> = Vec::new();
+        for i in 0..self.systems.len() {
+            if self.systems[i].stage != stage {
+                continue;
+            }
+            let exclusive = matches!(self.systems[i].kind, SystemKind::Exclusive(_));
+            if exclusive || batch.iter().any(|&j| self.conflicts(i, j)) {
+                self.run_batch(scene, &batch, context);
+                batch.clear();
+            }
+            match &mut self.systems[i].kind {
+                SystemKind::Exclusive(system) => system(scene, context),
+                SystemKind::Parallel(_) => batch.push(i),
+            }
+        }
+        self.run_batch(scene, &batch, context);
+    }
+
+    fn conflicts(&self, a: usize, b: usize) -> bool {
+        match (&self.systems[a].kind, &self.systems[b].kind) {
+            (SystemKind::Parallel(a), SystemKind::Parallel(b)) => {
+                a.access().conflicts_with(b.access())
+            }
+            _ => true,
+        }
+    }
+
+    fn run_batch(&mut self, scene: &mut Scene, batch: &[usize], context: SystemContext) {
+        if batch.is_empty() {
+            return;
+        }
+        let threads = self.thread_count;
+        let (store, entities) = scene.get_query_source();
+        let mut jobs = Vec::new();
+        for &i in batch {
+            if let SystemKind::Parallel(system) = &self.systems[i].kind {
+                // SAFETY: systems only share a batch when their accesses do
+                // not conflict, and the scene is not touched until every job
+                // has finished.
+                jobs.extend(unsafe {
+                    system.prepare(store, entities, context, threads * JOBS_PER_THREAD)
+                });
+            }
+        }
+
+        if threads == 1 || jobs.len() <= 1 {
+            for job in jobs {
+                job();
+            }
+        } else {
+            self.pool
+                .get_or_insert_with(|| ThreadPool::new(threads))
+                .run(jobs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use glfw::{Glfw, Window, WindowEvent};
+
+    use crate::core::{
+        entity::{component::Component, Entity},
+        scene::Scene,
+        scheduler::Stage,
+        time::Time,
+    };
+
+    struct Counter(u32);
+    struct Seen(u32);
+
+    impl Component for Counter {
+        fn update(&mut self, _: &mut Scene, _: &mut Entity, _: f64) {}
+        fn handle_event(&mut self, _: &mut Glfw, _: &mut Window, _: &WindowEvent) {}
+    }
+
+    impl Component for Seen {
+        fn update(&mut self, _: &mut Scene, _: &mut Entity, _: f64) {}
+        fn handle_event(&mut self, _: &mut Glfw, _: &mut Window, _: &WindowEvent) {}
+    }
+
+    /// 100 counting entities, every tenth one disabled.
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.get_scheduler_mut().set_thread_count(4);
+        for i in 0..100 {
+            let mut entity = scene.create_entity("counter");
+            entity.add_component(Counter(0));
+            entity.add_component(Seen(0));
+            entity.set_enabled(i % 10 != 0);
+            scene.add_entity(entity);
+        }
+        scene
+    }
+
+    fn update(scene: &mut Scene) {
+        let mut time = Time::new();
+        time.advance(1.0 / 60.0);
+        scene.update(&time);
+    }
+
+    #[test]
+    fn parallel_systems_visit_every_enabled_entity() {
+        let mut scene = scene();
+        scene.get_scheduler_mut().add_system::<&mut Counter, _>(
+            Stage::Gameplay,
+            "count",
+            |counter, _| counter.0 += 1,
+        );
+
+        update(&mut scene);
+        update(&mut scene);
+
+        let mut counts: Vec<u32> = scene.query_ref::<&Counter>().iter().map(|c| c.0).collect();
+        counts.sort();
+        assert_eq!(counts[..10], [0; 10]);
+        assert!(counts[10..].iter().all(|&count| count == 2));
+    }
+
+    #[test]
+    fn conflicting_systems_run_in_registration_order() {
+        let mut scene = scene();
+        let scheduler = scene.get_scheduler_mut();
+        scheduler
+            .add_system::<&mut Counter, _>(Stage::Gameplay, "count", |counter, _| counter.0 += 1);
+        scheduler.add_system::<(&Counter, &mut Seen), _>(
+            Stage::Gameplay,
+            "see",
+            |(counter, seen), _| seen.0 = counter.0,
+        );
+        let checked = Rc::new(Cell::new(0));
+        let flag = checked.clone();
+        scheduler.add_exclusive_system(Stage::Gameplay, "check", move |scene, _| {
+            for (counter, seen) in scene.query_ref::<(&Counter, &Seen)>() {
+                assert_eq!(counter.0, seen.0);
+            }
+            flag.set(flag.get() + 1);
+        });
+
+        update(&mut scene);
+        update(&mut scene);
+        assert_eq!(checked.get(), 2);
+
+        assert!(scene.get_scheduler_mut().remove_system("check"));
+        update(&mut scene);
+        assert_eq!(checked.get(), 2);
+    }
+
+    #[test]
+    fn changes_made_by_systems_apply_after_the_stage() {
+        let mut scene = scene();
+        let scheduler = scene.get_scheduler_mut();
+        scheduler
+            .add_system::<&mut Counter, _>(Stage::Gameplay, "count", |counter, _| counter.0 += 1);
+        scheduler.add_exclusive_system(Stage::Gameplay, "stop", |scene, _| {
+            let scheduler = scene.get_scheduler_mut();
+            assert!(scheduler.remove_system("count"));
+            assert!(scheduler.remove_system("stop"));
+            assert!(!scheduler.remove_system("missing"));
+            scheduler.set_thread_count(1);
+        });
+
+        update(&mut scene);
+        update(&mut scene);
+
+        let scheduler = scene.get_scheduler();
+        assert_eq!(scheduler.get_thread_count(), 1);
+        let mut counts: Vec<u32> = scene.query_ref::<&Counter>().iter().map(|c| c.0).collect();
+        counts.sort();
+        assert_eq!(counts[..10], [0; 10]);
+        assert!(counts[10..].iter().all(|&count| count == 1));
+    }
+}
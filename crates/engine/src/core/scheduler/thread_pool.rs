@@ -0,0 +1,153 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use super::{Job, PendingTasks, ThreadPool};
+
+impl ThreadPool {
+    pub fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Box<dyn FnOnce() + Send>>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads)
+            .map(|i| {
+                let receiver = Arc::clone(&receiver);
+                thread::Builder::new()
+                    .name(format!("scheduler-{}", i))
+                    .spawn(move || loop {
+                        let task = receiver.lock().unwrap().recv();
+                        match task {
+                            Ok(task) => task(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("Failed to spawn scheduler thread")
+            })
+            .collect();
+        Self {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Runs every job on the pool and blocks until all of them finished.
+    /// A panic in a job is raised again on the calling thread.
+    pub fn run(&self, jobs: Vec<Job<'_>>) {
+        let sender = self.sender.as_ref().unwrap();
+        let (done_sender, done_receiver) = mpsc::channel();
+        let mut pending = PendingTasks {
+            receiver: done_receiver,
+            queued: 0,
+        };
+        for job in jobs {
+            let done_sender = done_sender.clone();
+            let task: Job<'_> = Box::new(move || {
+                let _ = done_sender.send(panic::catch_unwind(AssertUnwindSafe(job)));
+            });
+            // SAFETY: `pending` waits for every queued task before this call
+            // returns or unwinds, so the borrows in `job` outlive it.
+            let task: Box<dyn FnOnce() + Send> = unsafe { std::mem::transmute(task) };
+            sender.send(task).expect("Scheduler threads have shut down");
+            pending.queued += 1;
+        }
+        drop(done_sender);
+
+        let mut panic = None;
+        while let Some(result) = pending.next() {
+            if let Err(payload) = result {
+                panic = Some(payload);
+            }
+        }
+        if let Some(payload) = panic {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl PendingTasks {
+    /// Waits for the next queued task. `None` once all of them have
+    /// finished, or the rest were dropped without running.
+    fn next(&mut self) -> Option<thread::Result<()>> {
+        if self.queued == 0 {
+            return None;
+        }
+        match self.receiver.recv() {
+            Ok(result) => {
+                self.queued -= 1;
+                Some(result)
+            }
+            Err(_) => {
+                self.queued = 0;
+                None
+            }
+        }
+    }
+}
+
+impl Drop for PendingTasks {
+    fn drop(&mut self) {
+        while self.next().is_some() {}
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // Closing the channel makes every worker leave its loop.
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    use super::super::{Job, ThreadPool};
+
+    #[test]
+    fn run_finishes_jobs_that_borrow_locals() {
+        let pool = ThreadPool::new(4);
+        let mut values = vec![0; 64];
+        let jobs: Vec<Job<'_>> = values
+            .chunks_mut(8)
+            .map(|chunk| -> Job<'_> {
+                Box::new(move || {
+                    for value in chunk.iter_mut() {
+                        *value += 1;
+                    }
+                })
+            })
+            .collect();
+        pool.run(jobs);
+        assert!(values.iter().all(|value| *value == 1));
+    }
+
+    #[test]
+    fn run_waits_for_every_job_before_raising_a_panic() {
+        let pool = ThreadPool::new(2);
+        let finished = AtomicUsize::new(0);
+        let jobs: Vec<Job<'_>> = (0..4)
+            .map(|i| -> Job<'_> {
+                let finished = &finished;
+                Box::new(move || {
+                    if i == 0 {
+                        panic!("job failed");
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                    finished.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| pool.run(jobs)));
+        assert!(result.is_err());
+        assert_eq!(finished.load(Ordering::SeqCst), 3);
+    }
+}
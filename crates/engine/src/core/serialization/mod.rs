@@ -0,0 +1,112 @@
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    entity::{component::Component, Entity},
+    scene::Scene,
+};
+
+mod registry;
+mod scene_data;
+
+/// On-disk representation of a `Scene`. Written as pretty-printed JSON by
+/// `Scene::save` and read back by `Scene::load`.
+#[derive(Serialize, Deserialize)]
+pub struct SceneData {
+    pub entities: Vec<EntityData>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EntityData {
+    /// 0 lets the scene assign a fresh handle.
+    #[serde(default)]
+    pub id: u64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub position: [f32; 3],
+    /// Quaternion stored as `[w, x, y, z]`.
+    #[serde(default = "identity_rotation")]
+    pub rotation: [f32; 4],
+    #[serde(default = "unit_scale")]
+    pub scale: [f32; 3],
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Bits of the entity's `LayerMask`; `None` keeps the default (or, for
+    /// prefab instances, the prefab's) layers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layers: Option<u32>,
+    #[serde(default)]
+    pub components: Vec<ComponentData>,
+    #[serde(default)]
+    pub children: Vec<EntityData>,
+    /// Set on prefab instances. The entity is rebuilt from the prefab on
+    /// load; the fields above then replace the prefab root's name (unless
+    /// empty) and transform, and `components`/`children` are added to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefab: Option<PrefabLink>,
+}
+
+fn identity_rotation() -> [f32; 4] {
+    [1.0, 0.0, 0.0, 0.0]
+}
+
+fn unit_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+/// Reference from an instance to the prefab file it was created from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PrefabLink {
+    pub path: String,
+    /// Per-instance parameters, merged into the saved data of the prefab
+    /// root's components, keyed by component type name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ComponentData {
+    #[serde(rename = "type")]
+    pub type_name: String,
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// Implemented by components that can be written to and restored from a
+/// scene file. Register the type with `ComponentRegistry::register`.
+///
+/// `load` runs after every component listed before this one in the file has
+/// been attached to `entity`, so a component may look up its siblings there
+/// (e.g. `PlayerController` resolving the `RigidBody` handle).
+pub trait SerializableComponent: Component + Sized {
+    const TYPE_NAME: &'static str;
+
+    fn save(&self, scene: &Scene) -> Result<Value, Box<dyn Error>>;
+    fn load(data: Value, scene: &mut Scene, entity: &mut Entity) -> Result<Self, Box<dyn Error>>;
+}
+
+type SaveFn = Box<dyn Fn(&dyn Component, &Scene) -> Result<Value, Box<dyn Error>>>;
+type LoadFn =
+    Box<dyn Fn(Value, &mut Scene, &mut Entity) -> Result<Box<dyn Component>, Box<dyn Error>>>;
+
+struct Registration {
+    type_name: String,
+    save: SaveFn,
+    load: LoadFn,
+}
+
+/// Maps component types to the names used in scene files and to the
+/// functions that save and load them. Components that are not registered
+/// are skipped when saving.
+pub struct ComponentRegistry {
+    registrations: Vec<Registration>,
+    by_type: HashMap<TypeId, usize>,
+    by_name: HashMap<String, usize>,
+}
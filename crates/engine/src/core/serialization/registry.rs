@@ -0,0 +1,121 @@
+use std::{any::TypeId, collections::HashMap, error::Error};
+
+use serde_json::Value;
+
+use crate::core::{
+    entity::{
+        component::{
+            camera_component::CameraComponent, debug_component::DebugController,
+            model_component::ModelComponent, Component,
+        },
+        Entity,
+    },
+    physics::rigidbody::RigidBody,
+    renderer::light::skylight::SkyLight,
+    scene::Scene,
+};
+
+use super::{ComponentData, ComponentRegistry, Registration, SerializableComponent};
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self {
+            registrations: Vec::new(),
+            by_type: HashMap::new(),
+            by_name: HashMap::new(),
+        }
+    }
+
+    /// Registry pre-populated with the core components. Game-level ones,
+    /// such as the player's (`Player::register`) or a `Terrain`, are
+    /// registered by the game.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register::<CameraComponent>();
+        registry.register::<SkyLight>();
+        registry.register::<ModelComponent>();
+        registry.register::<RigidBody>();
+        registry.register::<DebugController>();
+        registry
+    }
+
+    pub fn register<T: SerializableComponent>(&mut self) {
+        self.register_with::<T, _, _>(
+            T::TYPE_NAME,
+            |component, scene| component.save(scene),
+            |data, scene, entity| T::load(data, scene, entity),
+        );
+    }
+
+    /// Registers a component through closures instead of the
+    /// `SerializableComponent` trait. Useful for components whose state
+    /// lives outside the engine, such as an `AnimationComponent` whose graph
+    /// is built by the game.
+    pub fn register_with<T, S, L>(&mut self, type_name: &str, save: S, load: L)
+    where
+        T: Component,
+        S: Fn(&T, &Scene) -> Result<Value, Box<dyn Error>> + 'static,
+        L: Fn(Value, &mut Scene, &mut Entity) -> Result<T, Box<dyn Error>> + 'static,
+    {
+        let type_name = type_name.to_string();
+        let registration = Registration {
+            type_name: type_name.clone(),
+            save: Box::new(move |component: &dyn Component, scene: &Scene| {
+                match component.as_any().downcast_ref::<T>() {
+                    Some(component) => save(component, scene),
+                    None => Err("Component type does not match its registration".into()),
+                }
+            }),
+            load: Box::new(move |data: Value, scene: &mut Scene, entity: &mut Entity| {
+                let component: Box<dyn Component> = Box::new(load(data, scene, entity)?);
+                Ok(component)
+            }),
+        };
+
+        let index = match self.by_type.get(&TypeId::of::<T>()) {
+            Some(&index) => {
+                self.by_name.remove(&self.registrations[index].type_name);
+                self.registrations[index] = registration;
+                index
+            }
+            None => {
+                self.registrations.push(registration);
+                self.registrations.len() - 1
+            }
+        };
+        self.by_type.insert(TypeId::of::<T>(), index);
+        self.by_name.insert(type_name, index);
+    }
+
+    pub fn is_registered<T: Component>(&self) -> bool {
+        self.by_type.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns `Ok(None)` when the component's type is not registered.
+    pub fn save_component(
+        &self,
+        component: &dyn Component,
+        scene: &Scene,
+    ) -> Result<Option<ComponentData>, Box<dyn Error>> {
+        let Some(&index) = self.by_type.get(&component.as_any().type_id()) else {
+            return Ok(None);
+        };
+        let registration = &self.registrations[index];
+        Ok(Some(ComponentData {
+            type_name: registration.type_name.clone(),
+            data: (registration.save)(component, scene)?,
+        }))
+    }
+
+    pub fn load_component(
+        &self,
+        data: ComponentData,
+        scene: &mut Scene,
+        entity: &mut Entity,
+    ) -> Result<Box<dyn Component>, Box<dyn Error>> {
+        match self.by_name.get(&data.type_name) {
+            Some(&index) => (self.registrations[index].load)(data.data, scene, entity),
+            None => Err(format!("Unknown component type '{}'", data.type_name).into()),
+        }
+    }
+}
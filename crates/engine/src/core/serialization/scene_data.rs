@@ -0,0 +1,131 @@
+use std::error::Error;
+
+use cgmath::Quaternion;
+
+use crate::core::{
+    entity::{Entity, EntityHandle, LayerMask, Transform},
+    prefab::PrefabCache,
+    scene::Scene,
+};
+
+use super::{ComponentRegistry, EntityData};
+
+/// Prefabs may contain instances of other prefabs. Past this depth loading
+/// fails, so a prefab that contains itself cannot recurse forever.
+const MAX_PREFAB_DEPTH: usize = 16;
+
+impl EntityData {
+    /// Prefab instances are written as their link and transform, plus the
+    /// components and children added to them after they were built.
+    pub fn from_entity(
+        entity: &Entity,
+        scene: &Scene,
+        registry: &ComponentRegistry,
+    ) -> Result<Self, Box<dyn Error>> {
+        let position = entity.get_position();
+        let rotation = entity.get_rotation();
+        let scale = entity.get_scale();
+        let instance = entity.get_prefab().is_some();
+
+        let mut components = Vec::new();
+        for (component, state) in entity.iter_components(scene) {
+            if instance && state.from_prefab {
+                continue;
+            }
+            if let Some(data) = registry.save_component(component, scene)? {
+                components.push(data);
+            }
+        }
+
+        let mut children = Vec::new();
+        for child in entity.get_children() {
+            if child.is_persistent() && !(instance && child.is_from_prefab()) {
+                children.push(EntityData::from_entity(child, scene, registry)?);
+            }
+        }
+
+        Ok(Self {
+            id: entity.id.into(),
+            name: entity.get_name(),
+            position: [position.x, position.y, position.z],
+            rotation: [rotation.s, rotation.v.x, rotation.v.y, rotation.v.z],
+            scale: scale.into(),
+            tags: entity.get_tags().to_vec(),
+            layers: Some(entity.get_layers().bits()),
+            components,
+            children,
+            prefab: entity.get_prefab().cloned(),
+        })
+    }
+
+    /// Rebuilds the entity. Components are attached in file order so each
+    /// one can see the components loaded before it.
+    pub fn into_entity(
+        self,
+        scene: &mut Scene,
+        registry: &ComponentRegistry,
+    ) -> Result<Entity, Box<dyn Error>> {
+        self.build(scene, registry, &mut PrefabCache::new(), 0)
+    }
+
+    /// `depth` counts the prefabs being instantiated around this entity.
+    /// Linked prefab files are read through `prefabs`.
+    pub(crate) fn build(
+        self,
+        scene: &mut Scene,
+        registry: &ComponentRegistry,
+        prefabs: &mut PrefabCache,
+        depth: usize,
+    ) -> Result<Entity, Box<dyn Error>> {
+        let is_instance = self.prefab.is_some();
+        let mut entity = match self.prefab {
+            Some(link) => {
+                if depth >= MAX_PREFAB_DEPTH {
+                    return Err(format!("Prefab '{}' is nested too deeply", link.path).into());
+                }
+                let prefab = prefabs.load(&link.path)?;
+                let mut entity =
+                    prefab.build(scene, registry, &link.components, prefabs, depth + 1)?;
+                if !self.name.is_empty() {
+                    entity.set_name(self.name);
+                }
+                entity.set_prefab(Some(link));
+                entity
+            }
+            None => Entity::new(&self.name),
+        };
+        entity.id = EntityHandle::from(self.id);
+        let [w, x, y, z] = self.rotation;
+        entity.set_transform(
+            scene,
+            Transform {
+                translation: self.position.into(),
+                rotation: Quaternion::new(w, x, y, z),
+                scale: self.scale.into(),
+            },
+        );
+        if is_instance {
+            // The prefab's children were built around its own transform.
+            entity.sync_rigid_bodies(scene);
+        }
+
+        if let Some(layers) = self.layers {
+            entity.set_layers(scene, LayerMask::from_bits(layers));
+        }
+        for tag in &self.tags {
+            entity.add_tag(tag);
+        }
+
+        for data in self.components {
+            let component = registry.load_component(data, scene, &mut entity)?;
+            entity.add_boxed_component(component);
+        }
+
+        for child in self.children {
+            let child = child.build(scene, registry, prefabs, depth)?;
+            entity.add_child(scene, child);
+        }
+
+        Ok(entity)
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+mod time;
+
+/// Frame timing owned by the `Application`, passed to every
+/// `Layer::on_update` and readable from `Scene::get_time`.
+///
+/// Scaled values follow the time scale and stand still while paused;
+/// unscaled ones and the statistics always follow the frame time.
+#[derive(Clone, Debug)]
+pub struct Time {
+    delta: f64,
+    unscaled_delta: f64,
+    elapsed: f64,
+    unscaled_elapsed: f64,
+    /// Frames advanced so far, paused ones included.
+    frame: u64,
+    time_scale: f64,
+    paused: bool,
+    /// Frames to run at full delta while paused, added by `step`.
+    pending_steps: u32,
+    /// Exponential moving average of the unscaled delta.
+    smoothed_delta: f64,
+    /// The last `FRAME_SAMPLES` unscaled deltas, oldest first.
+    samples: VecDeque<f64>,
+}
+
+/// The values of a `Time` for one frame, without its frame time history.
+/// What `Scene::get_time` returns.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeSnapshot {
+    delta: f64,
+    unscaled_delta: f64,
+    elapsed: f64,
+    unscaled_elapsed: f64,
+    frame: u64,
+    time_scale: f64,
+    paused: bool,
+    fps: f64,
+    smoothed_delta: f64,
+    /// The 99th percentile of the recent unscaled frame times.
+    slow_frame_time: f64,
+}
@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+
+use super::{Time, TimeSnapshot};
+
+/// Frames kept for the frame time percentiles.
+const FRAME_SAMPLES: usize = 240;
+/// Weight of the newest frame in the smoothed frame time.
+const SMOOTHING: f64 = 0.1;
+
+impl Time {
+    pub fn new() -> Self {
+        Self {
+            delta: 0.0,
+            unscaled_delta: 0.0,
+            elapsed: 0.0,
+            unscaled_elapsed: 0.0,
+            frame: 0,
+            time_scale: 1.0,
+            paused: false,
+            pending_steps: 0,
+            smoothed_delta: 0.0,
+            samples: VecDeque::with_capacity(FRAME_SAMPLES),
+        }
+    }
+
+    /// Starts a frame that took `unscaled_delta` seconds. Done by the
+    /// `Application`; call it yourself to drive a `Scene` directly.
+    pub fn advance(&mut self, unscaled_delta: f64) {
+        self.unscaled_delta = unscaled_delta;
+        self.delta = if !self.paused {
+            unscaled_delta * self.time_scale
+        } else if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            unscaled_delta * self.time_scale
+        } else {
+            0.0
+        };
+        self.elapsed += self.delta;
+        self.unscaled_elapsed += unscaled_delta;
+        self.frame += 1;
+
+        self.smoothed_delta = if self.frame == 1 {
+            unscaled_delta
+        } else {
+            self.smoothed_delta + (unscaled_delta - self.smoothed_delta) * SMOOTHING
+        };
+        if self.samples.len() == FRAME_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(unscaled_delta);
+    }
+
+    /// Seconds of game time this frame: scaled, and 0 while paused.
+    pub fn get_delta(&self) -> f64 {
+        self.delta
+    }
+
+    pub fn get_unscaled_delta(&self) -> f64 {
+        self.unscaled_delta
+    }
+
+    pub fn get_elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn get_unscaled_elapsed(&self) -> f64 {
+        self.unscaled_elapsed
+    }
+
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn get_time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Applies to every layer from the next frame on, on top of each
+    /// scene's own time scale. Below 1 for slow motion.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        assert!(time_scale >= 0.0, "Time scale must not be negative");
+        self.time_scale = time_scale;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Makes the scaled delta 0 from the next frame on. Unpausing drops
+    /// steps that were not taken yet.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            self.pending_steps = 0;
+        }
+    }
+
+    /// While paused, lets the next frame run with its normal delta.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    /// Smoothed over roughly the last ten frames.
+    pub fn get_fps(&self) -> f64 {
+        if self.smoothed_delta > 0.0 {
+            1.0 / self.smoothed_delta
+        } else {
+            0.0
+        }
+    }
+
+    pub fn get_smoothed_delta(&self) -> f64 {
+        self.smoothed_delta
+    }
+
+    /// The unscaled frame time that `percentile` percent of the recent
+    /// frames stayed within, e.g. 99 for the slowest 1%.
+    pub fn get_frame_time_percentile(&self, percentile: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut samples: Vec<f64> = self.samples.iter().copied().collect();
+        samples.sort_by(f64::total_cmp);
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (samples.len() - 1) as f64).round();
+        samples[rank as usize]
+    }
+
+    /// The current values, for keeping past this frame without copying
+    /// the frame time samples.
+    pub fn get_snapshot(&self) -> TimeSnapshot {
+        TimeSnapshot {
+            delta: self.delta,
+            unscaled_delta: self.unscaled_delta,
+            elapsed: self.elapsed,
+            unscaled_elapsed: self.unscaled_elapsed,
+            frame: self.frame,
+            time_scale: self.time_scale,
+            paused: self.paused,
+            fps: self.get_fps(),
+            smoothed_delta: self.smoothed_delta,
+            slow_frame_time: self.get_frame_time_percentile(99.0),
+        }
+    }
+}
+
+impl TimeSnapshot {
+    pub fn get_delta(&self) -> f64 {
+        self.delta
+    }
+
+    pub fn get_unscaled_delta(&self) -> f64 {
+        self.unscaled_delta
+    }
+
+    pub fn get_elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn get_unscaled_elapsed(&self) -> f64 {
+        self.unscaled_elapsed
+    }
+
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn get_time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn get_fps(&self) -> f64 {
+        self.fps
+    }
+
+    pub fn get_smoothed_delta(&self) -> f64 {
+        self.smoothed_delta
+    }
+
+    /// The unscaled frame time 99% of the recent frames stayed within.
+    pub fn get_slow_frame_time(&self) -> f64 {
+        self.slow_frame_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Time;
+
+    #[test]
+    fn paused_time_has_no_delta_until_stepped() {
+        let mut time = Time::new();
+        time.set_time_scale(0.5);
+        time.advance(0.1);
+        assert_eq!(time.get_delta(), 0.05);
+
+        time.set_paused(true);
+        time.advance(0.1);
+        assert_eq!(time.get_delta(), 0.0);
+        assert_eq!(time.get_unscaled_delta(), 0.1);
+
+        time.step();
+        time.advance(0.1);
+        assert_eq!(time.get_delta(), 0.05);
+        time.advance(0.1);
+        assert_eq!(time.get_delta(), 0.0);
+        assert_eq!(time.get_frame(), 4);
+    }
+
+    #[test]
+    fn snapshot_keeps_the_frame_statistics() {
+        let mut time = Time::new();
+        for i in 1..=100 {
+            time.advance(if i == 50 { 0.5 } else { 0.01 });
+        }
+        let snapshot = time.get_snapshot();
+        assert_eq!(snapshot.get_frame(), 100);
+        assert_eq!(snapshot.get_slow_frame_time(), 0.01);
+        assert_eq!(snapshot.get_smoothed_delta(), time.get_smoothed_delta());
+        assert_eq!(time.get_frame_time_percentile(100.0), 0.5);
+    }
+}
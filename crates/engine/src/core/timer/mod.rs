@@ -0,0 +1,103 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+};
+
+use super::{allocator::HandleAllocator, entity::EntityHandle, scene::Scene};
+
+mod tasks;
+mod timers;
+
+/// How long a timer waits, in scaled scene seconds or in updates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delay {
+    Seconds(f64),
+    Frames(u32),
+}
+
+/// Generational handle to a timer, so cancelling a timer that already fired
+/// never hits one created later in its slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerHandle(u64);
+
+/// Delayed and repeating callbacks, run by `Scene::update` after the fixed
+/// steps. They follow the scene's time scale and stand still while it is 0
+/// or the application's `Time` is paused.
+pub struct Timers {
+    /// In creation order, which is also the order due timers fire in.
+    timers: Vec<Timer>,
+    allocator: HandleAllocator,
+}
+
+struct Timer {
+    handle: TimerHandle,
+    interval: Delay,
+    repeat: bool,
+    /// Seconds or frames left until the timer fires.
+    remaining: f64,
+    /// Entity whose despawning cancels the timer.
+    owner: Option<EntityHandle>,
+    callback: Box<dyn FnMut(&mut Scene)>,
+}
+
+/// Generational handle to a task, like `TimerHandle`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TaskHandle(u64);
+
+/// Coroutine-style tasks: futures polled once per `Scene::update`, right
+/// after the timers, that wait with `TaskContext::wait_seconds` and reach
+/// the scene through `TaskContext::with_scene`.
+pub struct Tasks {
+    tasks: Vec<Task>,
+    shared: Rc<TaskShared>,
+    allocator: HandleAllocator,
+}
+
+struct Task {
+    handle: TaskHandle,
+    owner: Option<EntityHandle>,
+    future: Pin<Box<dyn Future<Output = ()>>>,
+}
+
+/// State shared between `Tasks` and every `TaskContext` it handed out.
+struct TaskShared {
+    /// Set only while tasks are being polled.
+    scene: Cell<*mut Scene>,
+    /// Scaled seconds and updates since the scene started running tasks.
+    time: Cell<f64>,
+    frame: Cell<u64>,
+    /// Tasks cancelled, and entities despawned, while the task list was
+    /// taken out for polling.
+    cancelled: RefCell<HashSet<TaskHandle>>,
+    despawned: RefCell<HashSet<EntityHandle>>,
+}
+
+/// Handed to each task when it is spawned.
+#[derive(Clone)]
+pub struct TaskContext {
+    shared: Rc<TaskShared>,
+}
+
+/// Future returned by `TaskContext::wait_seconds`.
+pub struct WaitSeconds {
+    shared: Rc<TaskShared>,
+    until: f64,
+}
+
+/// Future returned by `TaskContext::wait_frames`.
+pub struct WaitFrames {
+    shared: Rc<TaskShared>,
+    until: u64,
+}
+
+/// Updates `scene` `updates` times, a quarter second each.
+#[cfg(test)]
+fn run_updates(scene: &mut Scene, time: &mut super::time::Time, updates: usize) {
+    for _ in 0..updates {
+        time.advance(0.25);
+        scene.update(time);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    ptr,
+    rc::Rc,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+use crate::core::{allocator::HandleAllocator, entity::EntityHandle, scene::Scene};
+
+use super::{Task, TaskContext, TaskHandle, TaskShared, Tasks, WaitFrames, WaitSeconds};
+
+impl Tasks {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            shared: Rc::new(TaskShared {
+                scene: Cell::new(ptr::null_mut()),
+                time: Cell::new(0.0),
+                frame: Cell::new(0),
+                cancelled: RefCell::new(HashSet::new()),
+                despawned: RefCell::new(HashSet::new()),
+            }),
+            allocator: HandleAllocator::new(),
+        }
+    }
+
+    /// Starts a task, first polled during the next update, e.g.
+    ///
+    /// ```ignore
+    /// let id = entity.id;
+    /// scene.get_tasks_mut().spawn(Some(id), move |ctx| async move {
+    ///     ctx.wait_seconds(2.0).await;
+    ///     ctx.with_scene(|scene| scene.get_commands_mut().despawn(id));
+    /// });
+    /// ```
+    ///
+    /// A task with an `owner` is dropped when that entity is despawned.
+    pub fn spawn<F, Fut>(&mut self, owner: Option<EntityHandle>, task: F) -> TaskHandle
+    where
+        F: FnOnce(TaskContext) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let handle = TaskHandle(self.allocator.allocate());
+        let context = TaskContext {
+            shared: Rc::clone(&self.shared),
+        };
+        self.tasks.push(Task {
+            handle,
+            owner,
+            future: Box::pin(task(context)),
+        });
+        handle
+    }
+
+    /// Drops the task. Returns whether it was still running.
+    pub fn cancel(&mut self, task: TaskHandle) -> bool {
+        self.shared.cancelled.borrow_mut().insert(task);
+        self.tasks.retain(|t| t.handle != task);
+        self.allocator.free(task.0)
+    }
+
+    pub fn is_running(&self, task: TaskHandle) -> bool {
+        self.allocator.is_alive(task.0)
+    }
+
+    pub(crate) fn remove_entity(&mut self, id: EntityHandle) {
+        self.shared.despawned.borrow_mut().insert(id);
+        self.retain(|task| task.owner != Some(id));
+    }
+
+    /// Drops every task owned by an entity, for when the scene's entities
+    /// are replaced.
+    pub(crate) fn reset_entities(&mut self) {
+        self.retain(|task| task.owner.is_none());
+    }
+
+    /// Keeps the tasks `keep` returns true for, freeing the others' handles.
+    fn retain(&mut self, mut keep: impl FnMut(&Task) -> bool) {
+        let allocator = &mut self.allocator;
+        self.tasks.retain(|task| {
+            let kept = keep(task);
+            if !kept {
+                allocator.free(task.handle.0);
+            }
+            kept
+        });
+    }
+
+    /// Advances the task clock and polls every task once. The list is taken
+    /// out of the scene while polling, so tasks may spawn and cancel tasks.
+    pub(crate) fn advance(scene: &mut Scene, delta_time: f64) {
+        let tasks = scene.get_tasks_mut();
+        let shared = Rc::clone(&tasks.shared);
+        let mut running = std::mem::take(&mut tasks.tasks);
+        shared.time.set(shared.time.get() + delta_time);
+        shared.frame.set(shared.frame.get() + 1);
+
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        shared.scene.set(scene);
+        let mut finished = Vec::new();
+        running.retain_mut(|task| {
+            let pending =
+                !shared.is_cancelled(task) && task.future.as_mut().poll(&mut context).is_pending();
+            if !pending {
+                finished.push(task.handle);
+            }
+            pending
+        });
+        shared.scene.set(ptr::null_mut());
+
+        running.retain(|task| {
+            let cancelled = shared.is_cancelled(task);
+            if cancelled {
+                finished.push(task.handle);
+            }
+            !cancelled
+        });
+        let tasks = scene.get_tasks_mut();
+        for handle in finished {
+            tasks.allocator.free(handle.0);
+        }
+        running.append(&mut tasks.tasks);
+        tasks.tasks = running;
+        shared.cancelled.borrow_mut().clear();
+        shared.despawned.borrow_mut().clear();
+    }
+}
+
+impl TaskShared {
+    fn is_cancelled(&self, task: &Task) -> bool {
+        self.cancelled.borrow().contains(&task.handle)
+            || task
+                .owner
+                .is_some_and(|owner| self.despawned.borrow().contains(&owner))
+    }
+}
+
+impl TaskContext {
+    /// Completes once `seconds` of scaled scene time have passed.
+    pub fn wait_seconds(&self, seconds: f64) -> WaitSeconds {
+        WaitSeconds {
+            shared: Rc::clone(&self.shared),
+            until: self.shared.time.get() + seconds,
+        }
+    }
+
+    /// Completes `frames` updates from now.
+    pub fn wait_frames(&self, frames: u32) -> WaitFrames {
+        WaitFrames {
+            shared: Rc::clone(&self.shared),
+            until: self.shared.frame.get() + frames as u64,
+        }
+    }
+
+    pub fn next_frame(&self) -> WaitFrames {
+        self.wait_frames(1)
+    }
+
+    /// Scaled seconds the scene has run tasks for.
+    pub fn get_time(&self) -> f64 {
+        self.shared.time.get()
+    }
+
+    /// Runs `f` with the scene the task belongs to. Panics when called
+    /// from outside the task, or from within another `with_scene`.
+    pub fn with_scene<R>(&self, f: impl FnOnce(&mut Scene) -> R) -> R {
+        let scene = self.shared.scene.replace(ptr::null_mut());
+        assert!(
+            !scene.is_null(),
+            "TaskContext::with_scene called outside of its task's poll"
+        );
+        // SAFETY: the pointer is only set while `Tasks::advance` polls, and
+        // it does not touch the scene until polling is done. Taking the
+        // pointer out for the call rules out a second `&mut Scene`.
+        let result = f(unsafe { &mut *scene });
+        self.shared.scene.set(scene);
+        result
+    }
+}
+
+impl Future for WaitSeconds {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        if self.shared.time.get() >= self.until {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Future for WaitFrames {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        if self.shared.frame.get() >= self.until {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Tasks are polled every update, so nothing needs waking.
+fn noop_waker() -> Waker {
+    fn raw() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    // SAFETY: the vtable functions ignore the data pointer.
+    unsafe { Waker::from_raw(raw()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::core::{scene::Scene, time::Time, timer::run_updates};
+
+    #[test]
+    fn tasks_resume_after_waiting() {
+        let mut scene = Scene::new();
+        let mut time = Time::new();
+        let entity = scene.create_entity("bomb");
+        let id = scene.add_entity(entity);
+        let task = scene.get_tasks_mut().spawn(None, move |ctx| async move {
+            ctx.wait_seconds(0.5).await;
+            ctx.next_frame().await;
+            ctx.with_scene(|scene| scene.get_commands_mut().despawn(id));
+        });
+
+        run_updates(&mut scene, &mut time, 2);
+        assert!(scene.get_entity(&id).is_some());
+        assert!(scene.get_tasks().is_running(task));
+
+        run_updates(&mut scene, &mut time, 2);
+        assert!(scene.get_entity(&id).is_none());
+        assert!(!scene.get_tasks().is_running(task));
+    }
+
+    #[test]
+    fn despawning_the_owner_drops_its_tasks() {
+        let mut scene = Scene::new();
+        let mut time = Time::new();
+        let entity = scene.create_entity("bomb");
+        let id = scene.add_entity(entity);
+        let finished = Rc::new(Cell::new(false));
+        let flag = finished.clone();
+        let task = scene
+            .get_tasks_mut()
+            .spawn(Some(id), move |ctx| async move {
+                ctx.wait_frames(2).await;
+                flag.set(true);
+            });
+
+        run_updates(&mut scene, &mut time, 1);
+        scene.remove_entity(&id);
+        run_updates(&mut scene, &mut time, 3);
+        assert!(!finished.get());
+        assert!(!scene.get_tasks().is_running(task));
+    }
+
+    #[test]
+    fn finished_task_handles_stay_finished() {
+        let mut scene = Scene::new();
+        let mut time = Time::new();
+        let first = scene.get_tasks_mut().spawn(None, |_| async {});
+        run_updates(&mut scene, &mut time, 1);
+        assert!(!scene.get_tasks().is_running(first));
+
+        // The next task may reuse the slot, but not the handle.
+        let second = scene.get_tasks_mut().spawn(None, |ctx| async move {
+            ctx.next_frame().await;
+        });
+        assert_ne!(first, second);
+        assert!(!scene.get_tasks_mut().cancel(first));
+        assert!(scene.get_tasks().is_running(second));
+        assert!(scene.get_tasks_mut().cancel(second));
+        assert!(!scene.get_tasks().is_running(second));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::core::{allocator::HandleAllocator, entity::EntityHandle, scene::Scene};
+
+use super::{Delay, Timer, TimerHandle, Timers};
+
+impl Delay {
+    fn amount(&self) -> f64 {
+        match self {
+            Delay::Seconds(seconds) => seconds.max(0.0),
+            Delay::Frames(frames) => *frames as f64,
+        }
+    }
+}
+
+impl Timer {
+    /// Counts down by one update of `delta_time` seconds and returns
+    /// whether the timer is due.
+    fn tick(&mut self, delta_time: f64) -> bool {
+        self.remaining -= match self.interval {
+            Delay::Seconds(_) => delta_time,
+            Delay::Frames(_) => 1.0,
+        };
+        self.remaining <= 0.0
+    }
+}
+
+impl Timers {
+    pub fn new() -> Self {
+        Self {
+            timers: Vec::new(),
+            allocator: HandleAllocator::new(),
+        }
+    }
+
+    /// Runs `callback` once after `delay`.
+    pub fn once(
+        &mut self,
+        delay: Delay,
+        callback: impl FnMut(&mut Scene) + 'static,
+    ) -> TimerHandle {
+        self.insert(delay, false, Box::new(callback))
+    }
+
+    /// Runs `callback` every `interval` until the timer is cancelled. A
+    /// timer that falls behind fires at most once per update.
+    pub fn repeat(
+        &mut self,
+        interval: Delay,
+        callback: impl FnMut(&mut Scene) + 'static,
+    ) -> TimerHandle {
+        self.insert(interval, true, Box::new(callback))
+    }
+
+    /// Cancels the timer once `entity` is despawned.
+    pub fn bind_to(&mut self, timer: TimerHandle, entity: EntityHandle) {
+        if let Some(timer) = self.timers.iter_mut().find(|t| t.handle == timer) {
+            timer.owner = Some(entity);
+        }
+    }
+
+    /// Returns whether the timer was still pending.
+    pub fn cancel(&mut self, timer: TimerHandle) -> bool {
+        match self.timers.iter().position(|t| t.handle == timer) {
+            Some(index) => {
+                self.timers.remove(index);
+                self.allocator.free(timer.0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_pending(&self, timer: TimerHandle) -> bool {
+        self.allocator.is_alive(timer.0)
+    }
+
+    /// Seconds or frames until the timer fires next.
+    pub fn get_remaining(&self, timer: TimerHandle) -> Option<f64> {
+        self.timers
+            .iter()
+            .find(|t| t.handle == timer)
+            .map(|t| t.remaining.max(0.0))
+    }
+
+    pub(crate) fn remove_entity(&mut self, id: EntityHandle) {
+        let allocator = &mut self.allocator;
+        self.timers.retain(|timer| {
+            let keep = timer.owner != Some(id);
+            if !keep {
+                allocator.free(timer.handle.0);
+            }
+            keep
+        });
+    }
+
+    /// Drops every timer bound to an entity, for when the scene's entities
+    /// are replaced.
+    pub(crate) fn reset_entities(&mut self) {
+        let allocator = &mut self.allocator;
+        self.timers.retain(|timer| {
+            if timer.owner.is_some() {
+                allocator.free(timer.handle.0);
+            }
+            timer.owner.is_none()
+        });
+    }
+
+    fn insert(
+        &mut self,
+        delay: Delay,
+        repeat: bool,
+        callback: Box<dyn FnMut(&mut Scene)>,
+    ) -> TimerHandle {
+        let handle = TimerHandle(self.allocator.allocate());
+        self.timers.push(Timer {
+            handle,
+            interval: delay,
+            repeat,
+            remaining: delay.amount(),
+            owner: None,
+            callback,
+        });
+        handle
+    }
+
+    /// Advances every timer and runs the callbacks of those that are due.
+    /// Each callback is taken out of its timer while it runs, so it may
+    /// create and cancel timers, including its own.
+    pub(crate) fn advance(scene: &mut Scene, delta_time: f64) {
+        let due: Vec<TimerHandle> = scene
+            .get_timers_mut()
+            .timers
+            .iter_mut()
+            .filter_map(|timer| timer.tick(delta_time).then_some(timer.handle))
+            .collect();
+
+        for handle in due {
+            let timers = scene.get_timers_mut();
+            let Some(timer) = timers.timers.iter_mut().find(|t| t.handle == handle) else {
+                continue;
+            };
+            let mut callback = std::mem::replace(&mut timer.callback, Box::new(|_| {}));
+            callback(scene);
+
+            let timers = scene.get_timers_mut();
+            let Some(index) = timers.timers.iter().position(|t| t.handle == handle) else {
+                continue;
+            };
+            let timer = &mut timers.timers[index];
+            if timer.repeat {
+                timer.remaining = (timer.remaining + timer.interval.amount()).max(0.0);
+                timer.callback = callback;
+            } else {
+                timers.timers.remove(index);
+                timers.allocator.free(handle.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::core::{
+        scene::Scene,
+        time::Time,
+        timer::{run_updates, Delay},
+    };
+
+    #[test]
+    fn repeating_timers_fire_every_interval_until_cancelled() {
+        let mut scene = Scene::new();
+        let mut time = Time::new();
+        let count = Rc::new(Cell::new(0));
+        let counter = count.clone();
+        let timer = scene
+            .get_timers_mut()
+            .repeat(Delay::Seconds(0.5), move |_| counter.set(counter.get() + 1));
+
+        run_updates(&mut scene, &mut time, 4);
+        assert_eq!(count.get(), 2);
+
+        assert!(scene.get_timers_mut().cancel(timer));
+        run_updates(&mut scene, &mut time, 4);
+        assert_eq!(count.get(), 2);
+        assert!(!scene.get_timers().is_pending(timer));
+    }
+
+    #[test]
+    fn callbacks_can_cancel_their_own_timer() {
+        let mut scene = Scene::new();
+        let mut time = Time::new();
+        let count = Rc::new(Cell::new(0));
+        let counter = count.clone();
+        let handle = Rc::new(Cell::new(None));
+        let own = handle.clone();
+        let timer = scene
+            .get_timers_mut()
+            .repeat(Delay::Frames(1), move |scene| {
+                counter.set(counter.get() + 1);
+                if counter.get() == 3 {
+                    scene.get_timers_mut().cancel(own.get().unwrap());
+                }
+            });
+        handle.set(Some(timer));
+
+        run_updates(&mut scene, &mut time, 6);
+        assert_eq!(count.get(), 3);
+    }
+
+    #[test]
+    fn despawning_the_owner_cancels_bound_timers() {
+        let mut scene = Scene::new();
+        let mut time = Time::new();
+        let entity = scene.create_entity("bomb");
+        let entity = scene.add_entity(entity);
+        let fired = Rc::new(Cell::new(false));
+        let flag = fired.clone();
+        let timer = scene
+            .get_timers_mut()
+            .once(Delay::Frames(2), move |_| flag.set(true));
+        scene.get_timers_mut().bind_to(timer, entity);
+
+        scene.remove_entity(&entity);
+        run_updates(&mut scene, &mut time, 3);
+        assert!(!fired.get());
+        assert!(!scene.get_timers().is_pending(timer));
+
+        // The stale handle must not cancel a timer created in its slot.
+        let next = scene.get_timers_mut().once(Delay::Frames(1), |_| {});
+        assert!(!scene.get_timers_mut().cancel(timer));
+        assert!(scene.get_timers().is_pending(next));
+    }
+}
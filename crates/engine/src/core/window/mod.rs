@@ -0,0 +1,83 @@
+use std::cell::Cell;
+
+use glfw::GlfwReceiver;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use super::{input::Gamepads, utils::DataSource};
+
+mod config;
+mod window;
+
+lazy_static! {
+    /// The `vsync` console variable. `Window::set_vsync` writes it, and the
+    /// `Application` applies changes to it at the end of each frame.
+    pub(crate) static ref VSYNC_VARIABLE: DataSource<bool> = DataSource::new(true);
+}
+
+pub struct Window {
+    /// `None` for a headless window.
+    context: Option<GlfwContext>,
+    gamepads: Gamepads,
+    display_mode: DisplayMode,
+    /// Index into GLFW's connected monitors used for fullscreen modes.
+    monitor: usize,
+    vsync: bool,
+    /// Resolution used in `DisplayMode::Fullscreen`.
+    fullscreen_size: (u32, u32),
+    /// Position and size restored when going back to `DisplayMode::Windowed`.
+    windowed_rect: (i32, i32, u32, u32),
+    /// Set by `request_display_mode`, applied by the `Application` at the
+    /// end of the frame.
+    requested_display_mode: Cell<Option<DisplayMode>>,
+    /// Path from `request_screenshot`, saved by the `Application` before
+    /// the frame is shown.
+    requested_screenshot: Cell<Option<String>>,
+    /// GLFW time at the last `calculate_frametime`.
+    last_frame_time: f64,
+    pub width: u32,
+    pub height: u32,
+}
+
+struct GlfwContext {
+    window: glfw::PWindow,
+    glfw: glfw::Glfw,
+    events: GlfwReceiver<(f64, glfw::WindowEvent)>,
+}
+
+/// Settings for `Application::new`, built in code or loaded from JSON.
+/// Missing fields in a file keep their default.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Size in windowed mode, and the resolution in `DisplayMode::Fullscreen`.
+    pub width: u32,
+    pub height: u32,
+    pub display_mode: DisplayMode,
+    /// Index of the monitor for the fullscreen modes, 0 being the primary
+    /// one. Falls back to the primary monitor if there is no such monitor.
+    pub monitor: usize,
+    pub vsync: bool,
+    /// MSAA samples per pixel, 0 to turn multisampling off.
+    pub samples: u32,
+    pub resizable: bool,
+    pub cursor_mode: CursorMode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    /// Exclusive fullscreen at the configured resolution.
+    Fullscreen,
+    /// An undecorated window covering the monitor at its current resolution.
+    Borderless,
+}
+
+/// Serializable stand-in for `glfw::CursorMode`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorMode {
+    Normal,
+    Hidden,
+    Disabled,
+}
@@ -0,0 +1,323 @@
+use glfw::Context;
+use std::{cell::Cell, error::Error, ptr};
+
+use crate::core::{
+    input::{GamepadEvent, Gamepads},
+    renderer::capture,
+};
+
+use super::{DisplayMode, GlfwContext, Window, WindowConfig, VSYNC_VARIABLE};
+
+impl Window {
+    pub fn new(config: &WindowConfig) -> Result<Self, Box<dyn Error>> {
+        let mut glfw = glfw::init(glfw::log_errors)
+            .map_err(|e| format!("Failed to initialize GLFW: {:?}", e))?;
+
+        glfw.window_hint(glfw::WindowHint::Samples(
+            (config.samples > 0).then_some(config.samples),
+        ));
+        glfw.window_hint(glfw::WindowHint::Resizable(config.resizable));
+
+        // Created windowed and then moved to its monitor, the same way the
+        // display mode changes at runtime.
+        let (mut window, events) = glfw
+            .create_window(
+                config.width,
+                config.height,
+                &config.title,
+                glfw::WindowMode::Windowed,
+            )
+            .ok_or("Failed to create the window")?;
+
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_scroll_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_char_polling(true);
+        window.set_focus_polling(true);
+        window.set_cursor_mode(config.cursor_mode.into());
+        window.set_cursor_pos(0.0, 0.0);
+        let (width, height) = window.get_framebuffer_size();
+
+        gl::load_with(|symbol| {
+            window
+                .get_proc_address(symbol)
+                .map_or(ptr::null(), |f| f as *const _)
+        });
+        unsafe {
+            if config.samples > 0 {
+                gl::Enable(gl::MULTISAMPLE);
+            }
+        }
+
+        let mut window = Self {
+            context: Some(GlfwContext {
+                window,
+                glfw,
+                events,
+            }),
+            gamepads: Gamepads::new(),
+            display_mode: DisplayMode::Windowed,
+            monitor: config.monitor,
+            vsync: config.vsync,
+            fullscreen_size: (config.width, config.height),
+            windowed_rect: (0, 0, config.width, config.height),
+            requested_display_mode: Cell::new(None),
+            requested_screenshot: Cell::new(None),
+            last_frame_time: 0.0,
+            width: width as u32,
+            height: height as u32,
+        };
+        window.set_vsync(config.vsync);
+        if config.display_mode != DisplayMode::Windowed {
+            window.set_display_mode(config.display_mode);
+        }
+        Ok(window)
+    }
+
+    /// A window without GLFW or a GL context, for `Application::new_headless`.
+    /// It never receives events, and drawing to it does nothing.
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self {
+            context: None,
+            gamepads: Gamepads::new(),
+            display_mode: DisplayMode::Windowed,
+            monitor: 0,
+            vsync: false,
+            fullscreen_size: (width, height),
+            windowed_rect: (0, 0, width, height),
+            requested_display_mode: Cell::new(None),
+            requested_screenshot: Cell::new(None),
+            last_frame_time: 0.0,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.context.is_none()
+    }
+
+    pub fn get_display_mode(&self) -> DisplayMode {
+        self.display_mode
+    }
+
+    /// Moves the window to or from its monitor right away. Going back to
+    /// `DisplayMode::Windowed` restores the last windowed position and size.
+    /// The new size arrives as a `FramebufferSize` event.
+    pub fn set_display_mode(&mut self, display_mode: DisplayMode) {
+        let Some(context) = &mut self.context else {
+            self.display_mode = display_mode;
+            return;
+        };
+        if self.display_mode == DisplayMode::Windowed {
+            let (x, y) = context.window.get_pos();
+            let (width, height) = context.window.get_size();
+            self.windowed_rect = (x, y, width as u32, height as u32);
+        }
+        let (x, y, width, height) = self.windowed_rect;
+        let (fullscreen_width, fullscreen_height) = self.fullscreen_size;
+        let monitor_index = self.monitor;
+        let GlfwContext { window, glfw, .. } = context;
+        self.display_mode = glfw.with_connected_monitors(|_, monitors| {
+            let monitor = monitors.get(monitor_index).or(monitors.first());
+            let video_mode = monitor.and_then(|monitor| monitor.get_video_mode());
+            match (display_mode, monitor, video_mode) {
+                (DisplayMode::Windowed, _, _) => {}
+                (_, Some(monitor), Some(video_mode)) => {
+                    if display_mode == DisplayMode::Fullscreen {
+                        window.set_monitor(
+                            glfw::WindowMode::FullScreen(monitor),
+                            0,
+                            0,
+                            fullscreen_width,
+                            fullscreen_height,
+                            Some(video_mode.refresh_rate),
+                        );
+                    } else {
+                        let (monitor_x, monitor_y) = monitor.get_pos();
+                        window.set_decorated(false);
+                        window.set_monitor(
+                            glfw::WindowMode::Windowed,
+                            monitor_x,
+                            monitor_y,
+                            video_mode.width,
+                            video_mode.height,
+                            None,
+                        );
+                    }
+                    return display_mode;
+                }
+                _ => log::warn!("No monitor found, staying windowed"),
+            }
+            window.set_decorated(true);
+            window.set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+            DisplayMode::Windowed
+        });
+    }
+
+    /// Like `set_display_mode`, but from a shared reference, e.g. in
+    /// `Layer::on_update`. Applied at the end of the frame.
+    pub fn request_display_mode(&self, display_mode: DisplayMode) {
+        self.requested_display_mode.set(Some(display_mode));
+    }
+
+    /// Applies the requested display mode and the `vsync` console variable.
+    pub(crate) fn apply_requests(&mut self) {
+        if let Some(display_mode) = self.requested_display_mode.take() {
+            self.set_display_mode(display_mode);
+        }
+        let vsync = VSYNC_VARIABLE.read();
+        if vsync != self.vsync && !self.is_headless() {
+            self.set_vsync(vsync);
+        }
+    }
+
+    /// Saves the back buffer to a PNG at `path`. Call it after drawing and
+    /// before `swap_buffers`, or use `request_screenshot`.
+    pub fn save_screenshot(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        if self.is_headless() {
+            return Err("A headless window has nothing to capture".into());
+        }
+        capture::save_color(0, self.width, self.height, path)
+    }
+
+    /// Saves the current frame to a PNG at `path` once every layer has
+    /// drawn it.
+    pub fn request_screenshot(&self, path: &str) {
+        self.requested_screenshot.set(Some(path.to_string()));
+    }
+
+    pub(crate) fn take_requested_screenshot(&self) -> Option<String> {
+        self.requested_screenshot.take()
+    }
+
+    pub fn is_vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Also sets the `vsync` console variable.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.vsync = vsync;
+        if let Some(context) = &mut self.context {
+            VSYNC_VARIABLE.write(vsync);
+            context.glfw.set_swap_interval(if vsync {
+                glfw::SwapInterval::Sync(1)
+            } else {
+                glfw::SwapInterval::None
+            });
+        }
+    }
+
+    pub fn clear(&self, clear_color: (f32, f32, f32, f32), mask: u32) {
+        if self.is_headless() {
+            return;
+        }
+        unsafe {
+            gl::ClearColor(clear_color.0, clear_color.1, clear_color.2, clear_color.3);
+            gl::Clear(mask);
+        }
+    }
+
+    pub fn clear_mask(&self, mask: u32) {
+        if self.is_headless() {
+            return;
+        }
+        unsafe {
+            gl::Clear(mask);
+        }
+    }
+
+    /// Polls GLFW and returns the events that arrived since the last call.
+    pub fn poll_events(&mut self) -> Vec<glfw::WindowEvent> {
+        let Some(context) = &mut self.context else {
+            return Vec::new();
+        };
+        context.glfw.poll_events();
+        glfw::flush_messages(&context.events)
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Hands each of `events` to `event_handler` together with the GLFW
+    /// handles. The events need not come from `poll_events`, e.g. when
+    /// replaying a recording. A headless window only takes note of size
+    /// changes.
+    pub fn dispatch_events<F>(&mut self, events: Vec<glfw::WindowEvent>, mut event_handler: F)
+    where
+        F: FnMut(&mut glfw::Window, &mut glfw::Glfw, glfw::WindowEvent),
+    {
+        for event in events {
+            match event {
+                glfw::WindowEvent::FramebufferSize(width, height) => {
+                    self.width = width as u32;
+                    self.height = height as u32;
+                }
+                _ => {}
+            }
+            if let Some(context) = &mut self.context {
+                event_handler(&mut context.window, &mut context.glfw, event);
+            }
+        }
+    }
+
+    pub fn handle_events<F>(&mut self, event_handler: F)
+    where
+        F: FnMut(&mut glfw::Window, &mut glfw::Glfw, glfw::WindowEvent),
+    {
+        let events = self.poll_events();
+        self.dispatch_events(events, event_handler);
+    }
+
+    /// Reads the gamepads' state. Call after `poll_events`.
+    pub fn poll_gamepads(&mut self) -> Vec<GamepadEvent> {
+        match &self.context {
+            Some(context) => self.gamepads.poll(&context.glfw),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    pub fn get_gamepads_mut(&mut self) -> &mut Gamepads {
+        &mut self.gamepads
+    }
+
+    /// Never true for a headless window.
+    pub fn should_close(&mut self) -> bool {
+        self.context
+            .as_ref()
+            .is_some_and(|context| context.window.should_close())
+    }
+
+    pub fn swap_buffers(&mut self) {
+        if let Some(context) = &mut self.context {
+            context.window.swap_buffers();
+        }
+    }
+
+    /// Seconds since the last call. Always 0 for a headless window, which
+    /// needs a fixed frame time.
+    pub fn calculate_frametime(&mut self) -> f64 {
+        let Some(context) = &self.context else {
+            return 0.0;
+        };
+        let current_time = context.glfw.get_time();
+        let delta_time = current_time - self.last_frame_time;
+        self.last_frame_time = current_time;
+        delta_time
+    }
+
+    pub fn reset_viewport(&self) {
+        if self.is_headless() {
+            return;
+        }
+        unsafe {
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+}
@@ -1,5 +1,11 @@
-use cgmath::{EuclideanSpace, InnerSpace, Matrix4, SquareMatrix, Vector3};
-use rapier3d::prelude::{ColliderBuilder, RigidBodyBuilder, RigidBodyHandle};
+use std::error::Error;
+
+use cgmath::{InnerSpace, Point3, Vector3};
+use rapier3d::{
+    math::Rotation,
+    prelude::{ColliderBuilder, RigidBodyBuilder, RigidBodyHandle, Vector},
+};
+use serde_json::Value;
 
 use crate::core::{
     entity::{
@@ -7,6 +13,8 @@ use crate::core::{
         Entity,
     },
     scene::Scene,
+    scheduler::SystemContext,
+    serialization::SerializableComponent,
 };
 
 const MIN_BONE_LENGTH: f32 = 0.04;
@@ -20,8 +28,8 @@ pub struct BoneColliders {
 impl BoneColliders {
     pub fn build(scene: &mut Scene, entity: &Entity) -> Self {
         let segments = entity
-            .get_component::<ModelComponent>()
-            .map(|mc| mc.get_model().get_bone_segments(&Matrix4::identity()))
+            .get_component::<ModelComponent>(scene)
+            .map(|mc| mc.get_model().get_bone_segments(&entity.get_world_matrix()))
             .unwrap_or_default();
 
         let mut handles = Vec::new();
@@ -36,12 +44,13 @@ impl BoneColliders {
             let mid = parent_pos + dir * 0.5;
 
             let rb = RigidBodyBuilder::kinematic_position_based()
-                .translation(rapier3d::prelude::Vector::new(mid.x, mid.y, mid.z))
+                .translation(Vector::new(mid.x, mid.y, mid.z))
                 .build();
             let rb_handle = scene.physics_engine.add_rigid_body(rb);
 
             let collider = ColliderBuilder::capsule_y(half_height, radius)
                 .sensor(true)
+                .user_data(entity.get_layers().to_user_data())
                 .build();
             scene.physics_engine.add_collider(collider, Some(rb_handle));
 
@@ -50,18 +59,38 @@ impl BoneColliders {
 
         Self { handles }
     }
-}
 
-impl Component for BoneColliders {
-    fn update(&mut self, scene: &mut Scene, entity: &mut Entity, _dt: f64) {
-        let entity_transform = Matrix4::from_translation(entity.get_position().to_vec().into())
-            * Matrix4::from(entity.get_rotation());
-
-        let segments = entity
-            .get_component::<ModelComponent>()
-            .map(|mc| mc.get_model().get_bone_segments(&entity_transform))
-            .unwrap_or_default();
+    /// Moves the capsules of every `BoneColliders` onto the bones of its
+    /// entity's model. Registered by `Player::register` for
+    /// `Stage::PhysicsSync`, which runs after the animations are sampled.
+    pub(crate) fn sync(scene: &mut Scene, _: SystemContext) {
+        let targets: Vec<(RigidBodyHandle, Vector, Rotation)> = scene
+            .query_ref::<(&Entity, &ModelComponent, &BoneColliders)>()
+            .into_iter()
+            .filter(|(entity, _, _)| {
+                entity.is_enabled() && entity.is_component_enabled::<BoneColliders>(scene)
+            })
+            .flat_map(|(entity, model, bone_colliders)| {
+                let segments = model
+                    .get_model()
+                    .get_bone_segments(&entity.get_world_matrix());
+                bone_colliders.get_targets(&segments)
+            })
+            .collect();
+
+        for (rb_handle, translation, rotation) in targets {
+            let rb = &mut scene.physics_engine.rigid_bodies[rb_handle];
+            rb.set_next_kinematic_translation(translation);
+            rb.set_next_kinematic_rotation(rotation);
+        }
+    }
 
+    /// Where each capsule goes to cover its bone in `segments`.
+    fn get_targets(
+        &self,
+        segments: &[(String, Point3<f32>, Point3<f32>)],
+    ) -> Vec<(RigidBodyHandle, Vector, Rotation)> {
+        let mut targets = Vec::new();
         for (rb_handle, bone_name) in &self.handles {
             let Some((_, parent_pos, bone_pos)) =
                 segments.iter().find(|(n, _, _)| n == bone_name)
@@ -77,12 +106,15 @@ impl Component for BoneColliders {
 
             let mid = parent_pos + dir * 0.5;
             let rot = rotation_y_to(dir / len);
-
-            let rb = &mut scene.physics_engine.rigid_bodies[*rb_handle];
-            rb.set_next_kinematic_translation(rapier3d::prelude::Vector::new(mid.x, mid.y, mid.z));
-            rb.set_next_kinematic_rotation(rot);
+            targets.push((*rb_handle, Vector::new(mid.x, mid.y, mid.z), rot));
         }
+        targets
     }
+}
+
+impl Component for BoneColliders {
+    /// The capsules follow the bones in `BoneColliders::sync`.
+    fn update(&mut self, _: &mut Scene, _: &mut Entity, _: f64) {}
 
     fn handle_event(
         &mut self,
@@ -91,10 +123,30 @@ impl Component for BoneColliders {
         _: &glfw::WindowEvent,
     ) {
     }
+
+    fn on_removed(&mut self, scene: &mut Scene, _: &mut Entity) {
+        for (rb_handle, _) in self.handles.drain(..) {
+            scene.physics_engine.remove_rigid_body(rb_handle);
+        }
+    }
+}
+
+impl SerializableComponent for BoneColliders {
+    const TYPE_NAME: &'static str = "BoneColliders";
+
+    fn save(&self, _: &Scene) -> Result<Value, Box<dyn Error>> {
+        Ok(Value::Null)
+    }
+
+    /// The capsules are rebuilt from the skeleton, so the entity's
+    /// `ModelComponent` must be loaded first.
+    fn load(_: Value, scene: &mut Scene, entity: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        Ok(BoneColliders::build(scene, entity))
+    }
 }
 
 /// Glamx quaternion rotating the +Y axis to align with `dir` (already normalized).
-fn rotation_y_to(dir: Vector3<f32>) -> rapier3d::math::Rotation {
+fn rotation_y_to(dir: Vector3<f32>) -> Rotation {
     use rapier3d::glamx::{Quat, Vec3};
     let dot = dir.y; // dot with (0,1,0)
     if dot > 0.9999 {
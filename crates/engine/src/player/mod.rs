@@ -5,8 +5,20 @@ pub use bone_colliders::BoneColliders;
 use cgmath::{Rad, Vector3};
 use rapier3d::prelude::RigidBodyHandle;
 
+use crate::core::event::Event;
+
 pub struct Player {}
 
+/// Published by `PlayerController` when the player hits the ground after
+/// falling.
+#[derive(Clone, Debug)]
+pub struct Landed {
+    /// Downward speed (m/s) just before the impact.
+    pub impact_speed: f32,
+}
+
+impl Event for Landed {}
+
 pub struct PlayerController {
     forward: f32,
     backward: f32,
@@ -30,4 +42,12 @@ pub struct PlayerController {
     is_free_camera: bool,
     /// Local-space camera offset behind/above the player (rotated by yaw each frame).
     camera_offset: Vector3<f32>,
+    /// World-space root motion gathered by `update` since the last fixed
+    /// step, and the frame time it covers.
+    root_motion: Vector3<f32>,
+    root_motion_time: f64,
+    /// Horizontal velocity applied on each fixed step, derived from root motion.
+    horizontal_velocity: Vector3<f32>,
+    /// Vertical velocity set on the last fixed step, to detect landing.
+    vertical_velocity: f32,
 }
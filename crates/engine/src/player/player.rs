@@ -1,38 +1,77 @@
-use std::f32::consts::FRAC_PI_2;
+use std::{error::Error, f32::consts::FRAC_PI_2};
 
-use cgmath::{Point3, Quaternion, Rad, Rotation3, Vector3, Zero};
-use glfw::{Action, CursorMode, Glfw, Key, WindowEvent};
+use cgmath::{Point3, Quaternion, Rad, Rotation3, Transform, Vector3, Zero};
+use glfw::{CursorMode, Glfw, WindowEvent};
 use rapier3d::prelude::{ColliderBuilder, RigidBodyBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::core::{
     entity::{
         component::{
-            animation_component::AnimationComponent, camera_component::CameraComponent,
-            model_component::ModelComponent, Component,
+            animation_component::{AnimationComponent, SetAnimationInput},
+            camera_component::CameraComponent,
+            model_component::ModelComponent,
+            Component,
         },
-        Entity,
+        Entity, LayerMask,
     },
     model::{animation_graph::AnimationGraph, ModelBuilder},
     physics::rigidbody::RigidBody,
     scene::Scene,
+    scheduler::Stage,
+    serialization::SerializableComponent,
 };
 
-use super::{BoneColliders, Player, PlayerController};
+use super::{BoneColliders, Landed, Player, PlayerController};
 
 const CAPSULE_HALF_HEIGHT: f32 = 0.5;
 const CAPSULE_RADIUS: f32 = 0.5;
 const JUMP_VELOCITY: f32 = 4.0;
+/// Falls slower than this (m/s) do not count as landing.
+const LANDING_SPEED: f32 = 1.0;
 
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
+/// Look speed (radians per second) with the gamepad stick fully deflected.
+const STICK_LOOK_SPEED: f32 = 2.5;
+/// The mannequin is modelled in centimetres.
+const MODEL_SCALE: f32 = 0.01;
+
+#[derive(Serialize, Deserialize)]
+struct PlayerControllerData {
+    yaw: f32,
+    pitch: f32,
+    sensitivity: f32,
+    camera_offset: [f32; 3],
+}
 
 impl Player {
+    /// Registers the player's components for saving and loading, and the
+    /// system that moves the bone colliders. Call once per scene, before
+    /// loading one that has a player.
+    pub fn register(scene: &mut Scene) {
+        let registry = scene.get_component_registry_mut();
+        if registry.is_registered::<PlayerController>() {
+            return;
+        }
+        registry.register::<PlayerController>();
+        registry.register::<BoneColliders>();
+        scene.get_scheduler_mut().add_exclusive_system(
+            Stage::PhysicsSync,
+            "bone_colliders",
+            BoneColliders::sync,
+        );
+    }
+
     pub fn new<P: Into<Point3<f32>>>(
         scene: &mut Scene,
         position: P,
         animation_graph: AnimationGraph,
     ) -> Result<Entity, Box<dyn std::error::Error>> {
-        let mut entity = Entity::new("player");
+        let mut entity = scene.create_entity("player");
         entity.set_position(scene, position);
+        entity.set_scale(Vector3::new(MODEL_SCALE, MODEL_SCALE, MODEL_SCALE));
+        entity.set_layers(scene, LayerMask::PLAYER);
 
         let mut model = ModelBuilder::new("Mannequin.fbx")?.build();
         model.init();
@@ -41,9 +80,10 @@ impl Player {
 
         let collider = ColliderBuilder::capsule_y(CAPSULE_HALF_HEIGHT, CAPSULE_RADIUS)
             .friction(0.0)
+            .user_data(LayerMask::PLAYER.to_user_data())
             .build();
 
-        let translation = entity.get_position();
+        let translation = entity.get_world_position();
         let rigid_body = RigidBodyBuilder::dynamic()
             .translation(rapier3d::prelude::Vector::new(
                 translation.x,
@@ -57,7 +97,7 @@ impl Player {
             .physics_engine
             .add_collider(collider, Some(rigid_body_handle));
 
-        let rigidbody_component = RigidBody { rigid_body_handle };
+        let rigidbody_component = RigidBody::from_handle(rigid_body_handle);
         let controller = PlayerController::new(rigid_body_handle);
 
         entity.add_component(animation_component);
@@ -93,16 +133,61 @@ impl PlayerController {
             is_free_camera: false,
             // Local-space offset: slightly right, above head, behind the player.
             camera_offset: Vector3::new(0.25, 1.33, -2.05),
+            root_motion: Vector3::zero(),
+            root_motion_time: 0.0,
+            horizontal_velocity: Vector3::zero(),
+            vertical_velocity: 0.0,
         }
     }
 }
 
 impl Component for PlayerController {
     fn update(&mut self, scene: &mut Scene, entity: &mut Entity, dt: f64) {
-        // --- Mouse look (only in locked mode) ---
+        // --- Actions ---
+        if scene.get_input().just_pressed("toggle_free_camera") {
+            self.is_free_camera = !self.is_free_camera;
+            let cursor_mode = if self.is_free_camera {
+                CursorMode::Normal
+            } else {
+                CursorMode::Disabled
+            };
+            scene.get_input_mut().set_cursor_mode(cursor_mode);
+            self.mouse_dx = 0.0;
+            self.mouse_dy = 0.0;
+        }
+        // Movement — only active in locked (player-control) mode.
+        if !self.is_free_camera {
+            // Analog sticks give values between 0 and 1, which the
+            // animation graph treats like held keys.
+            let input = scene.get_input();
+            let actions = [
+                ("move_forward", &mut self.forward),
+                ("move_backward", &mut self.backward),
+                ("move_left", &mut self.left),
+                ("move_right", &mut self.right),
+                ("sprint", &mut self.sprint),
+                ("jump", &mut self.jump),
+            ];
+            for (action, value) in actions {
+                let pressed = input.get_value(action);
+                if *value != pressed {
+                    *value = pressed;
+                    self.dirty = true;
+                }
+            }
+            if input.just_pressed("jump") {
+                self.jump_impulse_pending = true;
+            }
+        }
+
+        // --- Mouse and stick look (only in locked mode) ---
         if !self.is_free_camera {
-            self.yaw += Rad(self.mouse_dx * self.sensitivity);
-            self.pitch += Rad(-self.mouse_dy * self.sensitivity);
+            let input = scene.get_input();
+            let stick_look = STICK_LOOK_SPEED * dt as f32;
+            self.yaw += Rad(self.mouse_dx * self.sensitivity)
+                + Rad(input.get_axis("look_right") * stick_look);
+            self.pitch += Rad(-self.mouse_dy * self.sensitivity)
+                + Rad(input.get_axis("look_up") * stick_look);
             if self.pitch.0 < -SAFE_FRAC_PI_2 {
                 self.pitch = Rad(-SAFE_FRAC_PI_2);
             } else if self.pitch.0 > SAFE_FRAC_PI_2 {
@@ -120,56 +205,38 @@ impl Component for PlayerController {
         entity.set_rotation(scene, model_rotation);
 
         // --- Animation inputs ---
-        if let Some(anim) = entity.get_component_mut::<AnimationComponent>() {
-            if self.dirty {
-                anim.set_input("forward", self.forward);
-                anim.set_input("backward", self.backward);
-                anim.set_input("left", self.left);
-                anim.set_input("right", self.right);
-                anim.set_input("sprint", self.sprint);
-                anim.set_input("jump", self.jump);
+        if self.dirty {
+            let inputs = [
+                ("forward", self.forward),
+                ("backward", self.backward),
+                ("left", self.left),
+                ("right", self.right),
+                ("sprint", self.sprint),
+                ("jump", self.jump),
+            ];
+            for (name, value) in inputs {
+                scene.get_events_mut().publish(
+                    Some(entity.id),
+                    SetAnimationInput {
+                        name: name.to_string(),
+                        value,
+                    },
+                );
             }
         }
 
-        // --- Root motion → world-space velocity ---
-        // Root motion is in model-local space where +Z is forward.
-        // Rotate it by the same transform used for the entity (θ = π/2 - yaw):
-        //   world_x = rm.x * sin(yaw) + rm.z * cos(yaw)
-        //   world_z = -rm.x * cos(yaw) + rm.z * sin(yaw)
-        let root_motion = if let Some(mc) = entity.get_component_mut::<ModelComponent>() {
+        // --- Root motion (turned into velocity in fixed_update) ---
+        // Root motion is in model-local space where +Z is forward. The
+        // entity's world matrix (just rotated above) applies both the model
+        // scale and the yaw rotation.
+        let root_motion = if let Some(mc) = entity.get_component_mut::<ModelComponent>(scene) {
             mc.get_model_mut().reset_position()
         } else {
             Vector3::zero()
         };
 
-        let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
-        let world_rm = Vector3::new(
-            root_motion.x * sin_yaw + root_motion.z * cos_yaw,
-            root_motion.y,
-            -root_motion.x * cos_yaw + root_motion.z * sin_yaw,
-        );
-
-        let current_vy = {
-            let rb = &scene.physics_engine.rigid_bodies[self.rigid_body_handle];
-            // Clamp upward velocity: ghost collisions from trimesh edges can inject a large
-            // positive vy that gets preserved here and compounds across frames.
-            rb.linvel().y.min(JUMP_VELOCITY)
-        };
-        let vy = if self.jump_impulse_pending {
-            self.jump_impulse_pending = false;
-            JUMP_VELOCITY
-        } else {
-            current_vy
-        };
-        let hvel = if dt > 0.0 {
-            world_rm / dt as f32
-        } else {
-            Vector3::zero()
-        };
-        {
-            let rb = &mut scene.physics_engine.rigid_bodies[self.rigid_body_handle];
-            rb.set_linvel(rapier3d::prelude::Vector::new(hvel.x, vy, hvel.z), true);
-        }
+        self.root_motion += entity.get_world_matrix().transform_vector(root_motion);
+        self.root_motion_time += dt;
 
         // --- Camera sync ---
         let camera_component = scene.get_component_mut::<CameraComponent>().unwrap();
@@ -177,6 +244,7 @@ impl Component for PlayerController {
 
         if !self.is_free_camera {
             // Rotate local camera offset into world space by the player's yaw.
+            let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
             let off = self.camera_offset;
             let world_offset = Vector3::new(
                 off.x * sin_yaw + off.z * cos_yaw,
@@ -184,7 +252,7 @@ impl Component for PlayerController {
                 -off.x * cos_yaw + off.z * sin_yaw,
             );
             let camera = camera_component.get_camera_mut();
-            camera.set_position(entity.get_position());
+            camera.set_position(entity.get_world_position());
             camera.set_relative_position(Point3::new(
                 world_offset.x,
                 world_offset.y,
@@ -196,102 +264,75 @@ impl Component for PlayerController {
         self.dirty = false;
     }
 
-    fn handle_event(&mut self, _: &mut Glfw, window: &mut glfw::Window, event: &WindowEvent) {
-        match event {
-            // Toggle free-camera mode
-            WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                self.is_free_camera = !self.is_free_camera;
-                if self.is_free_camera {
-                    window.set_cursor_mode(CursorMode::Normal);
-                } else {
-                    window.set_cursor_mode(CursorMode::Disabled);
-                    // Reset cursor to center so there is no position jump on re-lock.
-                    window.set_cursor_pos(0.0, 0.0);
-                    self.mouse_dx = 0.0;
-                    self.mouse_dy = 0.0;
-                }
-            }
+    fn fixed_update(&mut self, scene: &mut Scene, entity: &mut Entity, _: f64) {
+        // Average the root motion of the frames since the last step. Steps
+        // that follow within the same frame keep the previous velocity.
+        if self.root_motion_time > 0.0 {
+            self.horizontal_velocity = self.root_motion / self.root_motion_time as f32;
+            self.root_motion = Vector3::zero();
+            self.root_motion_time = 0.0;
+        }
 
-            // Capture raw mouse delta for look rotation (locked mode only).
-            WindowEvent::CursorPos(x, y) if !self.is_free_camera => {
-                if window.get_cursor_mode() == CursorMode::Disabled {
-                    self.mouse_dx = *x as f32;
-                    self.mouse_dy = *y as f32;
-                    window.set_cursor_pos(0.0, 0.0);
-                }
-            }
+        let rb = &mut scene.physics_engine.rigid_bodies[self.rigid_body_handle];
+        // Gravity only speeds a fall up, so a fall that slowed to less than
+        // half its speed since the last step was stopped by the ground.
+        let impact_speed = -self.vertical_velocity;
+        let landed = impact_speed > LANDING_SPEED && -rb.linvel().y < impact_speed / 2.0;
+        let vy = if self.jump_impulse_pending {
+            self.jump_impulse_pending = false;
+            JUMP_VELOCITY
+        } else {
+            // Clamp upward velocity: ghost collisions from trimesh edges can inject a large
+            // positive vy that gets preserved here and compounds across steps.
+            rb.linvel().y.min(JUMP_VELOCITY)
+        };
+        let hvel = self.horizontal_velocity;
+        rb.set_linvel(rapier3d::prelude::Vector::new(hvel.x, vy, hvel.z), true);
+        self.vertical_velocity = vy;
 
-            // Movement — only active in locked (player-control) mode.
-            WindowEvent::Key(Key::W, _, action, _) if !self.is_free_camera => match action {
-                Action::Press => {
-                    self.forward = 1.0;
-                    self.dirty = true;
-                }
-                Action::Release => {
-                    self.forward = 0.0;
-                    self.dirty = true;
-                }
-                _ => {}
-            },
-            WindowEvent::Key(Key::S, _, action, _) if !self.is_free_camera => match action {
-                Action::Press => {
-                    self.backward = 1.0;
-                    self.dirty = true;
-                }
-                Action::Release => {
-                    self.backward = 0.0;
-                    self.dirty = true;
-                }
-                _ => {}
-            },
-            WindowEvent::Key(Key::A, _, action, _) if !self.is_free_camera => match action {
-                Action::Press => {
-                    self.left = 1.0;
-                    self.dirty = true;
-                }
-                Action::Release => {
-                    self.left = 0.0;
-                    self.dirty = true;
-                }
-                _ => {}
-            },
-            WindowEvent::Key(Key::D, _, action, _) if !self.is_free_camera => match action {
-                Action::Press => {
-                    self.right = 1.0;
-                    self.dirty = true;
-                }
-                Action::Release => {
-                    self.right = 0.0;
-                    self.dirty = true;
-                }
-                _ => {}
-            },
-            WindowEvent::Key(Key::LeftShift, _, action, _) if !self.is_free_camera => {
-                match action {
-                    Action::Press => {
-                        self.sprint = 1.0;
-                        self.dirty = true;
-                    }
-                    Action::Release => {
-                        self.sprint = 0.0;
-                        self.dirty = true;
-                    }
-                    _ => {}
-                }
+        if landed {
+            scene
+                .get_events_mut()
+                .publish(Some(entity.id), Landed { impact_speed });
+        }
+    }
+
+    fn handle_event(&mut self, _: &mut Glfw, window: &mut glfw::Window, event: &WindowEvent) {
+        // Capture raw mouse delta for look rotation (locked mode only).
+        if let WindowEvent::CursorPos(x, y) = event {
+            if !self.is_free_camera && window.get_cursor_mode() == CursorMode::Disabled {
+                self.mouse_dx = *x as f32;
+                self.mouse_dy = *y as f32;
+                window.set_cursor_pos(0.0, 0.0);
             }
-            WindowEvent::Key(Key::Space, _, action, _) if !self.is_free_camera => match action {
-                Action::Press => {
-                    self.jump = 1.0;
-                    self.jump_impulse_pending = true;
-                    self.dirty = true;
-                }
-                Action::Release => {
-                    self.jump = 0.0;
-                    self.dirty = true;
-                }
-                _ => {}
-            },
-            _ => {}
         }
     }
 }
+
+impl SerializableComponent for PlayerController {
+    const TYPE_NAME: &'static str = "PlayerController";
+
+    fn save(&self, _: &Scene) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(PlayerControllerData {
+            yaw: self.yaw.0,
+            pitch: self.pitch.0,
+            sensitivity: self.sensitivity,
+            camera_offset: self.camera_offset.into(),
+        })?)
+    }
+
+    /// Requires the entity's `RigidBody` to be loaded first.
+    fn load(data: Value, scene: &mut Scene, entity: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        let data: PlayerControllerData = serde_json::from_value(data)?;
+        let rigid_body_handle = entity
+            .get_component::<RigidBody>(scene)
+            .ok_or("PlayerController requires a RigidBody on the same entity")?
+            .rigid_body_handle;
+        let mut controller = PlayerController::new(rigid_body_handle);
+        controller.yaw = Rad(data.yaw);
+        controller.pitch = Rad(data.pitch);
+        controller.sensitivity = data.sensitivity;
+        controller.camera_offset = data.camera_offset.into();
+        Ok(controller)
+    }
+}
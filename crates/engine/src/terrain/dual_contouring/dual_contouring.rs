@@ -8,16 +8,16 @@ use libnoise::prelude::*;
 use crate::{
     core::{
         entity::{component::Component, Entity},
-        renderer::{light::skylight::SkyLight, line::Line, shader::{Shader, VertexAttributes}, texture::Texture},
+        renderer::{light::skylight::SkyLight, line::Line, shader::{embedded_shader, Shader, VertexAttributes}, texture::Texture},
         scene::Scene,
     },
     terrain::{Chunk, ChunkBounds, Terrain, CHUNK_SIZE, CHUNK_SIZE_FLOAT, USE_LOD},
 };
 
 thread_local! {
-    static WATER_SHADER: Shader = Shader::new(
-        include_str!("water_vertex.glsl"),
-        include_str!("water_fragment.glsl"),
+    static WATER_SHADER: Shader = embedded_shader!(
+        "terrain/dual_contouring/water_vertex.glsl",
+        "terrain/dual_contouring/water_fragment.glsl"
     );
 }
 
@@ -221,6 +221,8 @@ impl DualContouringChunk {
 }
 
 impl Chunk for DualContouringChunk {
+    const TERRAIN_TYPE_NAME: &'static str = "Terrain<DualContouringChunk>";
+
     fn new(seed: u64, position: (f32, f32, f32), lod: usize) -> Self {
         let noise = Source::perlin(seed).scale([0.003; 2]).fbm(6, 1.0, 2.0, 0.5);
         let cave = Source::perlin(seed).scale([0.1; 3]);
@@ -273,10 +275,10 @@ impl Chunk for DualContouringChunk {
         )
     }
 
-    fn get_shader_source() -> (String, String) {
-        (
-            include_str!("vertex.glsl").to_string(),
-            include_str!("fragment.glsl").to_string(),
+    fn create_shader() -> Shader {
+        embedded_shader!(
+            "terrain/dual_contouring/vertex.glsl",
+            "terrain/dual_contouring/fragment.glsl"
         )
     }
 
@@ -9,7 +9,11 @@ use ndarray::ArrayBase;
 use crate::{
     core::{
         entity::{component::Component, Entity},
-        renderer::{line::Line, shader::VertexAttributes, texture::Texture},
+        renderer::{
+            line::Line,
+            shader::{embedded_shader, Shader, VertexAttributes},
+            texture::Texture,
+        },
         scene::Scene,
     },
     terrain::{Chunk, ChunkBounds, Terrain, CHUNK_SIZE_FLOAT},
@@ -129,6 +133,8 @@ impl MarchingCubesChunk {
 }
 
 impl Chunk for MarchingCubesChunk {
+    const TERRAIN_TYPE_NAME: &'static str = "Terrain<MarchingCubesChunk>";
+
     fn new(seed: u64, position: (f32, f32, f32), _: usize) -> Self {
         let generator = Source::perlin(seed).scale([0.003; 2]);
         let hills = Source::perlin(seed).scale([0.01; 2]);
@@ -202,10 +208,10 @@ impl Chunk for MarchingCubesChunk {
         )
     }
 
-    fn get_shader_source() -> (String, String) {
-        (
-            include_str!("vertex.glsl").to_string(),
-            include_str!("fragment.glsl").to_string(),
+    fn create_shader() -> Shader {
+        embedded_shader!(
+            "terrain/marching_cubes/vertex.glsl",
+            "terrain/marching_cubes/fragment.glsl"
         )
     }
 
@@ -2,8 +2,11 @@ use std::sync::mpsc;
 
 use cgmath::{Matrix4, Point3};
 use glfw::MouseButton;
+use lazy_static::lazy_static;
 
 use crate::core::{
+    entity::EntityHandle,
+    event::Event,
     mouse_picker::MousePicker,
     renderer::{
         line::Line,
@@ -11,32 +14,65 @@ use crate::core::{
         texture::Texture,
     },
     scene::Scene,
+    utils::DataSource,
 };
 
+/// Default for the `chunk_radius` console variable.
 pub const CHUNK_RADIUS: usize = 5;
 pub const CHUNK_SIZE: usize = 128;
 pub const CHUNK_SIZE_FLOAT: f32 = CHUNK_SIZE as f32;
 pub const USE_LOD: bool = false;
 
+lazy_static! {
+    /// The `chunk_radius` console variable, read by every terrain each frame.
+    pub(crate) static ref CHUNK_RADIUS_VARIABLE: DataSource<usize> =
+        DataSource::new(CHUNK_RADIUS);
+}
+
 pub mod dual_contouring;
 pub mod marching_cubes;
 mod terrain;
 pub mod voxel;
 
 pub struct Terrain<T: Chunk> {
-    chunk_receiver: mpsc::Receiver<T>,
+    seed: u64,
+    /// `None` marks a loader thread that has sent all of its chunks.
+    chunk_receiver: mpsc::Receiver<Option<T>>,
+    chunk_sender: mpsc::Sender<Option<T>>,
+    /// Loader threads still sending chunks.
+    active_loaders: usize,
+    /// Rings of chunks around the origin that have been requested.
+    radius: usize,
+    /// Chunks come from a single thread and `update` waits for each one.
+    deterministic: bool,
     shader: Shader,
     textures: Vec<Texture>,
     mouse_picker: MousePicker,
+    /// Button of a click not yet applied to the chunks, which needs the
+    /// scene to pick them.
+    click: Option<MouseButton>,
+}
+
+/// Published by a `Terrain` when a generated chunk has been added under it.
+#[derive(Clone, Debug)]
+pub struct ChunkLoaded {
+    pub chunk: EntityHandle,
+    pub position: Point3<f32>,
 }
 
+impl Event for ChunkLoaded {}
+
 pub trait Chunk {
+    /// `TYPE_NAME` of a `Terrain` of these chunks, so each chunk type gets
+    /// its own registration.
+    const TERRAIN_TYPE_NAME: &'static str;
+
     fn new(seed: u64, position: (f32, f32, f32), lod: usize) -> Self;
     fn buffer_data(&mut self);
     fn get_bounds(&self) -> ChunkBounds;
     fn process_line(&mut self, line: &Line, button: &MouseButton) -> bool;
     fn get_position(&self) -> Point3<f32>;
-    fn get_shader_source() -> (String, String);
+    fn create_shader() -> Shader;
     fn get_textures() -> Vec<Texture>;
     fn get_triangle_count(&self) -> usize;
     fn get_vertices(&self) -> Vec<[f32; 3]>;
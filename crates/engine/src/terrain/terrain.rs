@@ -1,5 +1,7 @@
 use std::{
     cmp::max,
+    error::Error,
+    ops::RangeInclusive,
     sync::mpsc::{self, Sender},
     thread,
 };
@@ -7,24 +9,31 @@ use std::{
 use cgmath::{EuclideanSpace, Matrix4, Point3};
 use glfw::MouseButton;
 use rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::core::{
     entity::{
         component::{camera_component::CameraComponent, Component},
-        Entity,
+        Entity, LayerMask,
     },
     mouse_picker::MousePicker,
     physics::rigidbody::RigidBody,
     renderer::{
+        self,
         light::skylight::SkyLight,
         line::Line,
         shader::{DynamicVertexArray, Shader, VertexAttributes},
     },
     scene::Scene,
+    serialization::SerializableComponent,
     view_frustum::ViewFrustum,
 };
 
-use super::{Chunk, ChunkBounds, ChunkMesh, Terrain, CHUNK_RADIUS, CHUNK_SIZE, CHUNK_SIZE_FLOAT};
+use super::{
+    Chunk, ChunkBounds, ChunkLoaded, ChunkMesh, Terrain, CHUNK_RADIUS_VARIABLE, CHUNK_SIZE,
+    CHUNK_SIZE_FLOAT,
+};
 
 impl ChunkBounds {
     pub fn parse(position: cgmath::Vector3<f32>) -> Self {
@@ -84,50 +93,153 @@ impl ChunkBounds {
     }
 }
 
+/// Ring around the origin that the chunk at `position` belongs to.
+fn ring(position: Point3<f32>) -> usize {
+    (position.x.abs().max(position.z.abs()) / CHUNK_SIZE_FLOAT).round() as usize
+}
+
 impl<T: Chunk + Component + Send + 'static> Terrain<T> {
     pub fn new(seed: u64) -> Self {
+        Self::spawn(seed, false)
+    }
+
+    /// Generates the chunks on one thread and adds exactly one per update,
+    /// in the same order every run, for scenes with
+    /// `Scene::set_deterministic`.
+    pub fn new_deterministic(seed: u64) -> Self {
+        Self::spawn(seed, true)
+    }
+
+    fn spawn(seed: u64, deterministic: bool) -> Self {
         let (tx, rx) = mpsc::channel();
         let origin = T::new(seed, (0.0, 0.0, 0.0), 0);
-        tx.send(origin).unwrap();
-        let shader_source = T::get_shader_source();
-        let shader = Shader::new(&shader_source.0, &shader_source.1);
-
-        let tx1 = tx.clone();
-        let tx2 = tx.clone();
-        let tx3 = tx.clone();
-        let tx4 = tx.clone();
-        let _ = thread::spawn(move || Terrain::chunkloader(seed, CHUNK_RADIUS as i32, 1, 1, tx1));
-        let _ = thread::spawn(move || Terrain::chunkloader(seed, CHUNK_RADIUS as i32, -1, 1, tx2));
-        let _ = thread::spawn(move || Terrain::chunkloader(seed, CHUNK_RADIUS as i32, 1, -1, tx3));
-        let _ = thread::spawn(move || Terrain::chunkloader(seed, CHUNK_RADIUS as i32, -1, -1, tx4));
+        tx.send(Some(origin)).unwrap();
+        let shader = T::create_shader();
 
-        Self {
+        let mut terrain = Self {
+            seed,
             chunk_receiver: rx,
+            chunk_sender: tx,
+            active_loaders: 0,
+            radius: 0,
+            deterministic,
             shader,
-            textures: T::get_textures(),
+            textures: if renderer::is_headless() {
+                Vec::new()
+            } else {
+                T::get_textures()
+            },
             mouse_picker: MousePicker::new(),
+            click: None,
+        };
+        terrain.radius = CHUNK_RADIUS_VARIABLE.read();
+        terrain.load_rings(1..=terrain.radius as i32);
+        terrain
+    }
+
+    /// Starts generating the chunks of `rings` on background threads.
+    fn load_rings(&mut self, rings: RangeInclusive<i32>) {
+        let seed = self.seed;
+        let quadrants = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+        if self.deterministic {
+            let tx = self.chunk_sender.clone();
+            let _ = thread::spawn(move || {
+                for (x_dir, z_dir) in quadrants {
+                    Terrain::chunkloader(seed, rings.clone(), x_dir, z_dir, tx.clone());
+                }
+                let _ = tx.send(None);
+            });
+            self.active_loaders += 1;
+        } else {
+            for (x_dir, z_dir) in quadrants {
+                let tx = self.chunk_sender.clone();
+                let rings = rings.clone();
+                let _ = thread::spawn(move || {
+                    Terrain::chunkloader(seed, rings, x_dir, z_dir, tx.clone());
+                    let _ = tx.send(None);
+                });
+                self.active_loaders += 1;
+            }
         }
     }
 
-    pub fn process_line(&mut self, line: Option<(Line, MouseButton)>) {
-        if let Some((line, _button)) = line {
-            for _chunk_bounds in ChunkBounds::get_chunk_bounds_on_line(&line) {
-                // for chunk in entity.get_with_own_component_mut::<T>() {
-                //     let chunk = chunk.get_component_mut::<T>().unwrap();
-                //     if chunk.get_bounds() == chunk_bounds {
-                //         chunk.process_line(&line, &button);
-                //     }
-                // }
+    /// Follows the `chunk_radius` console variable: loads the rings it
+    /// grew by, or removes the chunks outside of it.
+    fn sync_radius(&mut self, scene: &mut Scene, entity: &mut Entity) {
+        let radius = CHUNK_RADIUS_VARIABLE.read();
+        if radius > self.radius {
+            self.load_rings(self.radius as i32 + 1..=radius as i32);
+        } else if radius < self.radius {
+            let outside: Vec<_> = entity
+                .get_children()
+                .iter()
+                .filter(|child| {
+                    child
+                        .get_component::<T>(scene)
+                        .is_some_and(|chunk| ring(chunk.get_position()) > radius)
+                })
+                .map(|child| child.id)
+                .collect();
+            for id in outside {
+                entity.remove_child(scene, &id);
             }
         }
+        self.radius = radius;
     }
 
-    fn chunkloader(seed: u64, radius: i32, x_dir: i32, z_dir: i32, tx: Sender<T>) {
-        let mut x: i32 = 1;
+    /// The next generated chunk within the radius, if one is ready. Waits
+    /// for it while a loader is running when deterministic.
+    fn receive_chunk(&mut self) -> Option<T> {
+        loop {
+            let message = if self.deterministic && self.active_loaders > 0 {
+                self.chunk_receiver.recv().ok()?
+            } else {
+                self.chunk_receiver.try_recv().ok()?
+            };
+            match message {
+                Some(chunk) if ring(chunk.get_position()) <= self.radius => return Some(chunk),
+                Some(_) => {}
+                None => self.active_loaders -= 1,
+            }
+        }
+    }
+
+    /// Edits the chunk where the last click's ray hits the terrain.
+    fn process_click(&mut self, scene: &mut Scene, entity: &Entity, button: MouseButton) {
+        let Some((line, _)) = self.mouse_picker.ray.clone() else {
+            return;
+        };
+        let Some(hit) =
+            self.mouse_picker
+                .pick(&scene.physics_engine, line.length, LayerMask::TERRAIN)
+        else {
+            return;
+        };
+        // Just past the surface, so a hit on a chunk border picks the chunk
+        // that was hit.
+        let bounds = ChunkBounds::parse((hit.point + line.direction * 0.01).to_vec());
+        for chunk in entity.query::<&mut T>(scene) {
+            if chunk.get_bounds() == bounds
+                && chunk.process_line(&line, &button)
+                && !renderer::is_headless()
+            {
+                chunk.buffer_data();
+            }
+        }
+    }
+
+    fn chunkloader(
+        seed: u64,
+        rings: RangeInclusive<i32>,
+        x_dir: i32,
+        z_dir: i32,
+        tx: Sender<Option<T>>,
+    ) {
+        let mut x: i32 = *rings.start();
         let mut z: i32 = 0;
 
         loop {
-            if x > radius {
+            if x > *rings.end() {
                 break;
             }
             let position = if z_dir > 0 {
@@ -136,7 +248,7 @@ impl<T: Chunk + Component + Send + 'static> Terrain<T> {
                 ((z * z_dir) as f32, 0.0, (x * x_dir) as f32)
             };
             let new_chunk = T::new(seed, position, max(x.abs(), z.abs()) as usize);
-            let result = tx.send(new_chunk);
+            let result = tx.send(Some(new_chunk));
             if result.is_err() {
                 break;
             }
@@ -151,13 +263,16 @@ impl<T: Chunk + Component + Send + 'static> Terrain<T> {
         }
     }
 
-    pub fn get_triangle_count(&self, entity: &Entity) -> usize {
-        let mut count = 0;
-        for chunk in entity.get_with_own_component::<T>() {
-            let chunk = chunk.get_component::<T>().unwrap();
-            count += chunk.get_triangle_count();
-        }
-        count
+    pub fn get_triangle_count(&self, scene: &Scene, entity: &Entity) -> usize {
+        entity
+            .query_ref::<&T>(scene)
+            .iter()
+            .map(|chunk| chunk.get_triangle_count())
+            .sum()
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
     }
 
     pub fn get_shader(&self) -> &Shader {
@@ -171,22 +286,27 @@ impl<T: Chunk + Component + Send + 'static> Terrain<T> {
 
 impl<T: Chunk + Component + Send + 'static> Component for Terrain<T> {
     fn update(&mut self, scene: &mut Scene, entity: &mut Entity, _: f64) {
-        if let Ok(mut chunk) = self.chunk_receiver.try_recv() {
-            chunk.buffer_data();
-            let mut chunk_exists = false;
-            for existing_chunk in entity.get_with_own_component::<T>() {
-                let existing_chunk = existing_chunk.get_component::<T>().unwrap();
-                if existing_chunk.get_position() == chunk.get_position() {
-                    chunk_exists = true;
-                    break;
-                }
+        self.sync_radius(scene, entity);
+        if let Some(button) = self.click.take() {
+            self.process_click(scene, entity, button);
+        }
+        if let Some(mut chunk) = self.receive_chunk() {
+            if !renderer::is_headless() {
+                chunk.buffer_data();
             }
+            let chunk_exists = entity
+                .query_ref::<&T>(scene)
+                .iter()
+                .any(|existing_chunk| existing_chunk.get_position() == chunk.get_position());
             if !chunk_exists {
-                let mut chunk_entity = Entity::new(&format!(
+                let mut chunk_entity = scene.create_entity(&format!(
                     "chunk-{}@{:?}",
                     entity.child_count(),
                     chunk.get_position()
                 ));
+                // Chunks are regenerated from the seed, so they are not saved.
+                chunk_entity.set_persistent(false);
+                chunk_entity.set_layers(scene, LayerMask::TERRAIN);
                 let vertices: Vec<Vector> = chunk
                     .get_vertices()
                     .iter()
@@ -203,15 +323,25 @@ impl<T: Chunk + Component + Send + 'static> Component for Terrain<T> {
                 .expect("Failed to create collider")
                     .translation(Vector::new(position.x, position.y, position.z))
                     .build();
-                scene.physics_engine.add_collider(collider, None);
                 chunk_entity.add_component(chunk);
+                // The chunk entity sits at the origin, so the collider keeps its
+                // world-space translation. Parenting it to the chunk's body lets
+                // despawning the chunk release the trimesh as well.
                 chunk_entity.add_component(RigidBody::new(
                     RigidBodyType::Fixed,
                     scene,
                     &chunk_entity,
-                    None,
+                    Some(collider),
                 ));
-                entity.add_child(chunk_entity);
+                let chunk_id = chunk_entity.id;
+                entity.add_child(scene, chunk_entity);
+                scene.get_events_mut().publish(
+                    Some(entity.id),
+                    ChunkLoaded {
+                        chunk: chunk_id,
+                        position,
+                    },
+                );
             }
         }
         if let Some(camera_component) = scene.get_component::<CameraComponent>() {
@@ -256,12 +386,12 @@ impl<T: Chunk + Component + Send + 'static> Component for Terrain<T> {
                 .set_uniform_1i("isShadowPass", if bound_fbo != 0 { 1 } else { 0 });
 
             // Opaque pass — all terrain geometry
-            for chunk in entity.get_with_own_component::<T>() {
-                if let Some(chunk) = chunk.get_component::<T>() {
-                    if ViewFrustum::is_bounds_in_frustum_matrix(view_projection, chunk.get_bounds())
-                    {
-                        chunk.render(scene, entity, parent_transform, &view_projection);
-                    }
+            let layers = scene.get_drawn_layers();
+            for (chunk_entity, chunk) in entity.query_ref::<(&Entity, &T)>(scene) {
+                if chunk_entity.get_layers().intersects(layers)
+                    && ViewFrustum::is_bounds_in_frustum_matrix(view_projection, chunk.get_bounds())
+                {
+                    chunk.render(scene, entity, parent_transform, &view_projection);
                 }
             }
 
@@ -284,11 +414,12 @@ impl<T: Chunk + Component + Send + 'static> Component for Terrain<T> {
         // Transparent pass — water and other overlays. Runs after every
         // entity's opaque geometry is in the depth buffer (terrain, player,
         // props), so water correctly depth-tests against all of them.
-        for chunk in entity.get_with_own_component::<T>() {
-            if let Some(chunk) = chunk.get_component::<T>() {
-                if ViewFrustum::is_bounds_in_frustum_matrix(view_projection, chunk.get_bounds()) {
-                    Chunk::render_transparent(chunk, scene, view_projection, parent_transform);
-                }
+        let layers = scene.get_drawn_layers();
+        for (chunk_entity, chunk) in entity.query_ref::<(&Entity, &T)>(scene) {
+            if chunk_entity.get_layers().intersects(layers)
+                && ViewFrustum::is_bounds_in_frustum_matrix(view_projection, chunk.get_bounds())
+            {
+                Chunk::render_transparent(chunk, scene, view_projection, parent_transform);
             }
         }
     }
@@ -299,8 +430,31 @@ impl<T: Chunk + Component + Send + 'static> Component for Terrain<T> {
         window: &mut glfw::Window,
         event: &glfw::WindowEvent,
     ) {
-        let line = self.mouse_picker.handle_event(glfw, window, event);
-        self.process_line(line);
+        if let Some((_, button)) = self.mouse_picker.handle_event(glfw, window, event) {
+            self.click = Some(button);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TerrainData {
+    seed: u64,
+}
+
+impl<T: Chunk + Component + Send + 'static> SerializableComponent for Terrain<T> {
+    const TYPE_NAME: &'static str = T::TERRAIN_TYPE_NAME;
+
+    fn save(&self, _: &Scene) -> Result<Value, Box<dyn Error>> {
+        Ok(serde_json::to_value(TerrainData { seed: self.seed })?)
+    }
+
+    fn load(data: Value, scene: &mut Scene, _: &mut Entity) -> Result<Self, Box<dyn Error>> {
+        let data: TerrainData = serde_json::from_value(data)?;
+        if scene.is_deterministic() {
+            Ok(Terrain::new_deterministic(data.seed))
+        } else {
+            Ok(Terrain::new(data.seed))
+        }
     }
 }
 
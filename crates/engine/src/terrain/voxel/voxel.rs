@@ -2,7 +2,11 @@ use crate::terrain::{Chunk, CHUNK_SIZE, CHUNK_SIZE_FLOAT};
 use crate::{
     core::{
         entity::{component::Component, Entity},
-        renderer::{line::Line, shader::VertexAttributes, texture::Texture},
+        renderer::{
+            line::Line,
+            shader::{embedded_shader, Shader, VertexAttributes},
+            texture::Texture,
+        },
         scene::Scene,
     },
     terrain::{ChunkBounds, Terrain},
@@ -310,6 +314,8 @@ impl VoxelChunk {
 }
 
 impl Chunk for VoxelChunk {
+    const TERRAIN_TYPE_NAME: &'static str = "Terrain<VoxelChunk>";
+
     fn new(seed: u64, position: (f32, f32, f32), _: usize) -> Self {
         let generator = Source::perlin(seed).scale([0.003; 2]);
         let hills = Source::perlin(seed).scale([0.01; 2]);
@@ -427,11 +433,8 @@ impl Chunk for VoxelChunk {
         )
     }
 
-    fn get_shader_source() -> (String, String) {
-        (
-            include_str!("vertex.glsl").to_string(),
-            include_str!("fragment.glsl").to_string(),
-        )
+    fn create_shader() -> Shader {
+        embedded_shader!("terrain/voxel/vertex.glsl", "terrain/voxel/fragment.glsl")
     }
 
     fn get_textures() -> Vec<Texture> {
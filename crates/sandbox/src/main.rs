@@ -1,15 +1,22 @@
 use cgmath::Deg;
 use ferrite_ui::ui::{UIElement, UI};
-use glfw::{Glfw, WindowEvent};
+use glfw::{Glfw, Key, WindowEvent};
+use serde_json::Value;
 
 use ferrite::{
     core::{
         application::{Application, Layer},
+        assets::HotReload,
         camera::{Camera, CameraController, Projection},
+        console::ConsoleLayer,
         entity::{
-            component::{camera_component::CameraComponent, debug_component::DebugController},
-            Entity,
+            component::{
+                animation_component::AnimationComponent, camera_component::CameraComponent,
+                debug_component::DebugController,
+            },
+            LayerMask,
         },
+        input::{Binding, GamepadEvent, InputMap},
         model::{
             animation_graph::{AnimationGraph, State},
             Animation,
@@ -17,115 +24,272 @@ use ferrite::{
         primitives::{Position, Size},
         renderer::light::skylight::SkyLight,
         scene::Scene,
-        window::Window,
+        scene_manager::SceneManager,
+        time::Time,
+        window::{DisplayMode, Window, WindowConfig},
     },
     player::Player,
     terrain::{dual_contouring::DualContouringChunk, Terrain},
 };
-use std::error::Error;
+use std::{
+    error::Error,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SCENE_FILE: &str = "world.json";
+/// Name of the game world in the application's `SceneManager`.
+const WORLD_SCENE: &str = "world";
+/// Optional overrides for the default key bindings.
+const INPUT_FILE: &str = "input.json";
+/// Optional window settings, see `WindowConfig`.
+const WINDOW_FILE: &str = "window.json";
 
+/// `--record <file>` saves this session's input, `--replay <file>` plays a
+/// saved one back instead of reading the keyboard, mouse and gamepads.
+/// `--capture <directory>` saves every frame as a PNG at 60 frames per
+/// second. `--hot-reload` reloads shaders, textures and animations when
+/// their files change. The key left of 1 opens the developer console.
 fn main() {
-    let mut application = Application::new(1280, 720, "Engine");
-    match WorldLayer::new(1280, 720) {
-        Ok(layer) => {
-            application.add_layer(Box::new(layer));
-            application.start();
+    let mut config = WindowConfig::new("Engine", 1280, 720);
+    if Path::new(WINDOW_FILE).exists() {
+        match WindowConfig::load(WINDOW_FILE) {
+            Ok(loaded) => config = loaded,
+            Err(e) => eprintln!("Failed to load window settings: {e}"),
+        }
+    }
+    let mut application = match Application::new(&config) {
+        Ok(application) => application,
+        Err(e) => {
+            eprintln!("Failed to open window: {e}");
+            return;
+        }
+    };
+    let args: Vec<String> = std::env::args().collect();
+    let mut deterministic = false;
+    if let Some(i) = args.iter().position(|arg| arg == "--record") {
+        match args.get(i + 1) {
+            Some(path) => match application.record(path) {
+                Ok(()) => deterministic = true,
+                Err(e) => eprintln!("Failed to start recording: {e}"),
+            },
+            None => eprintln!("--record needs a file name"),
+        }
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--replay") {
+        match args.get(i + 1) {
+            Some(path) => match application.replay(path) {
+                Ok(()) => deterministic = true,
+                Err(e) => eprintln!("Failed to load replay: {e}"),
+            },
+            None => eprintln!("--replay needs a file name"),
+        }
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--capture") {
+        match args.get(i + 1) {
+            Some(directory) => {
+                if let Err(e) = application.start_frame_capture(directory, 1.0 / 60.0) {
+                    eprintln!("Failed to start frame capture: {e}");
+                }
+            }
+            None => eprintln!("--capture needs a directory"),
+        }
+    }
+    if args.iter().any(|arg| arg == "--hot-reload") {
+        HotReload::set_enabled(true);
+    }
+    let window = application.get_window();
+    let (width, height) = (window.width, window.height);
+    let scene = match create_world(width, height, deterministic) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("Failed to initialize world: {e}");
+            return;
         }
-        Err(e) => eprintln!("Failed to initialize world: {e}"),
+    };
+    let layer = WorldLayer::new(&scene);
+    let scenes = application.get_scenes_mut();
+    // Quick loads build the saved world into a scene set up like this one.
+    scenes.set_scene_factory(move || create_empty_world(width, height, deterministic));
+    scenes.insert(WORLD_SCENE, scene);
+    if let Err(e) = scenes.switch_to(WORLD_SCENE) {
+        eprintln!("Failed to start world: {e}");
+        return;
     }
+    application.push_layer(Box::new(layer));
+    application.push_overlay(Box::new(ConsoleLayer::new()));
+    application.start();
 }
 
-struct WorldLayer {
-    scene: Scene,
-    ui: UI,
+/// Input bindings, loaders and shadow maps of the world, without entities.
+fn create_empty_world(width: u32, height: u32, deterministic: bool) -> Scene {
+    let mut scene = Scene::new();
+    scene.set_deterministic(deterministic);
+    let mut input_map = InputMap::default();
+    input_map.bind("quick_save", Binding::Key(Key::F5));
+    input_map.bind("quick_load", Binding::Key(Key::F9));
+    input_map.bind("toggle_fullscreen", Binding::Key(Key::F11));
+    input_map.bind("toggle_pause", Binding::Key(Key::P));
+    input_map.bind("step_frame", Binding::Key(Key::O));
+    if Path::new(INPUT_FILE).exists() {
+        if let Err(e) = input_map.load(INPUT_FILE) {
+            eprintln!("Failed to load key bindings: {e}");
+        }
+    }
+    scene.get_input_mut().set_map(input_map);
+    Player::register(&mut scene);
+    scene
+        .get_component_registry_mut()
+        .register::<Terrain<DualContouringChunk>>();
+    // The animation graph is built in code, so saving only records that
+    // the component exists and loading rebuilds the graph.
+    scene
+        .get_component_registry_mut()
+        .register_with::<AnimationComponent, _, _>(
+            "AnimationComponent",
+            |_, _| Ok(Value::Null),
+            |_, _, _| Ok(AnimationComponent::new(create_animation_graph()?)),
+        );
+    scene.add_shadow_map(4096, 4096);
+    scene.add_debug_maps(width, height);
+    scene
 }
 
-impl WorldLayer {
-    pub fn new(width: u32, height: u32) -> Result<WorldLayer, Box<dyn Error>> {
-        let mut scene = Scene::new();
-        scene.add_shadow_map(4096, 4096);
-        scene.add_debug_maps(width, height);
-        let camera = Camera::new((0.0, 0.0, 0.0), Deg(0.0), Deg(0.0));
-        let projection: Projection = Projection::new(width, height, Deg(45.0), 0.1, 100.0);
-        let camera_controller = CameraController::new(10.0, 1.0);
-        let mut entity = Entity::new("camera");
-        entity.add_component(CameraComponent::new(camera, projection, camera_controller));
-        scene.add_entity(entity);
-
-        let mut skylight = Entity::new("skylight");
-        skylight.add_component(SkyLight::new((30.0, 20.0, 10.0)));
-        scene.add_entity(skylight);
-
-        let ui = UI::new();
-
-        let mut terrain_entity = Entity::new("terrain");
+fn create_world(width: u32, height: u32, deterministic: bool) -> Result<Scene, Box<dyn Error>> {
+    let mut scene = create_empty_world(width, height, deterministic);
+    let camera = Camera::new((0.0, 0.0, 0.0), Deg(0.0), Deg(0.0));
+    let projection: Projection = Projection::new(width, height, Deg(45.0), 0.1, 100.0);
+    let camera_controller = CameraController::new(10.0, 1.0);
+    let mut entity = scene.create_entity("camera");
+    entity.add_component(CameraComponent::new(camera, projection, camera_controller));
+    scene.add_entity(entity);
+
+    let mut skylight = scene.create_entity("skylight");
+    skylight.add_component(SkyLight::new((30.0, 20.0, 10.0)));
+    scene.add_entity(skylight);
+
+    let mut terrain_entity = scene.create_entity("terrain");
+    if deterministic {
+        terrain_entity.add_component(Terrain::<DualContouringChunk>::new_deterministic(2));
+    } else {
         terrain_entity.add_component(Terrain::<DualContouringChunk>::new(2));
-        terrain_entity.add_child(Player::new(
-            &mut scene,
-            (0.0, 52.0, 0.0),
-            create_animation_graph()?,
-        )?);
+    }
+    let player = Player::new(&mut scene, (0.0, 52.0, 0.0), create_animation_graph()?)?;
+    terrain_entity.add_child(&mut scene, player);
+
+    scene.add_entity(terrain_entity);
 
-        scene.add_entity(terrain_entity);
+    let mut debug = scene.create_entity("debug");
+    debug.set_layers(&mut scene, LayerMask::DEBUG);
+    debug.add_component(DebugController::new());
+    scene.add_entity(debug);
 
-        let mut debug = Entity::new("debug");
-        debug.add_component(DebugController::new());
-        scene.add_entity(debug);
+    Ok(scene)
+}
+
+/// Game controls and the camera panel for the `WORLD_SCENE` of the
+/// application's `SceneManager`.
+struct WorldLayer {
+    ui: UI,
+}
 
-        Ok(Self { scene, ui })
+impl WorldLayer {
+    pub fn new(scene: &Scene) -> WorldLayer {
+        let mut ui = UI::new();
+        if let Some(camera) = scene.get_component::<CameraComponent>() {
+            let camera_speed_ref = camera.get_camera_controller().get_speed_ref();
+            let mut panel = UI::panel(
+                "Camera controls",
+                Position {
+                    x: 10.0,
+                    y: 130.0,
+                    z: 0.0,
+                },
+                Size::default(),
+            );
+            panel.add_child(UI::text("Camera Speed", 16.0));
+            let mut input = UI::input(camera_speed_ref.clone());
+            input.set_size(Size {
+                width: 190.0,
+                height: 26.0,
+            });
+            panel.add_child(input);
+            panel.add_child(UI::button(
+                "Reset Speed",
+                Box::new(move |scene| {
+                    let camera_controller = scene
+                        .get_component_mut::<CameraComponent>()
+                        .unwrap()
+                        .get_camera_controller_mut();
+                    camera_controller.set_speed(10.0);
+                }),
+            ));
+            ui.add(panel);
+        }
+        Self { ui }
     }
 }
 
 impl Layer for WorldLayer {
-    fn on_attach(&mut self) {
-        let camera_speed_ref = self
-            .scene
-            .get_component::<CameraComponent>()
-            .unwrap()
-            .get_camera_controller()
-            .get_speed_ref();
-        let mut panel = UI::panel(
-            "Camera controls",
-            Position {
-                x: 10.0,
-                y: 130.0,
-                z: 0.0,
-            },
-            Size::default(),
-        );
-        panel.add_child(UI::text("Camera Speed", 16.0));
-        let mut input = UI::input(camera_speed_ref.clone());
-        input.set_size(Size {
-            width: 190.0,
-            height: 26.0,
-        });
-        panel.add_child(input);
-        panel.add_child(UI::button(
-            "Reset Speed",
-            Box::new(move |scene| {
-                let camera_controller = scene
-                    .get_component_mut::<CameraComponent>()
-                    .unwrap()
-                    .get_camera_controller_mut();
-                camera_controller.set_speed(10.0);
-            }),
-        ));
-        self.ui.add(panel);
+    fn on_update(&mut self, scenes: &mut SceneManager, window: &Window, time: &mut Time) {
+        let Some(scene) = scenes.get_scene_mut(WORLD_SCENE) else {
+            return;
+        };
+        if scene.get_input().just_pressed("quick_save") {
+            if let Err(e) = scene.save(SCENE_FILE) {
+                eprintln!("Failed to save scene: {e}");
+            }
+        }
+        if scene.get_input().just_pressed("toggle_fullscreen") {
+            window.request_display_mode(match window.get_display_mode() {
+                DisplayMode::Windowed => DisplayMode::Borderless,
+                _ => DisplayMode::Windowed,
+            });
+        }
+        if scene.get_input().just_pressed("screenshot") {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis());
+            window.request_screenshot(&format!("screenshot_{timestamp}.png"));
+        }
+        if scene.get_input().just_pressed("toggle_pause") {
+            time.set_paused(!time.is_paused());
+        }
+        if scene.get_input().just_pressed("step_frame") {
+            time.step();
+        }
+        self.ui.update(scene);
+        // The world keeps running while the saved one is built, and is
+        // replaced once it is ready.
+        if scene.get_input().just_pressed("quick_load") && !scenes.is_loading(WORLD_SCENE) {
+            scenes.load_file_async(WORLD_SCENE, SCENE_FILE);
+        }
     }
 
-    fn on_update(&mut self, window: &Window, delta_time: f64) {
-        self.scene.update(delta_time);
-        self.scene.render(window);
-
-        self.ui.update(&mut self.scene);
+    fn on_render(&mut self, _: &mut SceneManager, _: &Window) {
         self.ui.render();
     }
 
-    fn on_event(&mut self, glfw: &mut Glfw, window: &mut glfw::Window, event: &WindowEvent) {
-        if self.ui.handle_events(&mut self.scene, window, glfw, &event) {
-            return;
+    fn on_event(
+        &mut self,
+        scenes: &mut SceneManager,
+        glfw: &mut Glfw,
+        window: &mut glfw::Window,
+        event: &WindowEvent,
+    ) -> bool {
+        match scenes.get_scene_mut(WORLD_SCENE) {
+            Some(scene) => self.ui.handle_events(scene, window, glfw, &event),
+            None => false,
+        }
+    }
+
+    fn on_gamepad_event(&mut self, _: &mut SceneManager, event: &GamepadEvent) -> bool {
+        match event {
+            GamepadEvent::Connected { name, .. } => log::info!("Gamepad connected: {name}"),
+            GamepadEvent::Disconnected { id } => log::info!("Gamepad {id} disconnected"),
+            _ => {}
         }
-        self.scene.handle_event(glfw, window, event);
+        false
     }
 
     fn get_name(&self) -> &str {